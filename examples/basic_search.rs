@@ -47,11 +47,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             for (i, flight) in result.flights.iter().take(3).enumerate() {
                 println!("\n--- Flight {} ---", i + 1);
                 println!("Airline: {}", flight.name);
-                if let Some(airline_code) = &flight.airline_code {
-                    println!("Airline Code: {}", airline_code);
-                }
-                if let Some(flight_number) = &flight.flight_number {
-                    println!("Flight Number: {}", flight_number);
+                if let Some(legs) = &flight.flight_legs {
+                    for leg in legs {
+                        println!("Flight: {} {}", leg.airline_code, leg.flight_number);
+                    }
                 }
                 println!("Departure: {}", flight.departure);
                 println!("Arrival: {}", flight.arrival);
@@ -0,0 +1,55 @@
+//! Custom city resolution with an office-location registry
+//!
+//! Office nicknames like "HQ" are resolved from a fixed registry first; every
+//! other name falls through to the built-in chain (city cache, city store,
+//! Wikidata, offline airport database).
+
+use rust_flights::resolver::{CityResolver, Location, LocationRegistry, ResolverChain};
+use rust_flights::{
+    get_flights_by_city_with, CityFlightData, CityFlightSearchRequest, Passengers, SeatClass, TripType,
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("🏢 Custom City Resolver Demo");
+    println!("============================\n");
+
+    let offices = LocationRegistry::new()
+        .with("HQ", Location::city("San Francisco", "/m/0d6lp"))
+        .with("London office", Location::city("London", "/m/04jpl"))
+        .with("Berlin office", Location::airport("Berlin Brandenburg", "BER"));
+    let resolver = ResolverChain::standard()?.first(offices);
+
+    for name in ["HQ", "Berlin office", "Lyon", "Portland, US"] {
+        match resolver.resolve(name).await {
+            Ok(candidates) => match candidates.first() {
+                Some(location) => println!("📍 {:<15} → {} ({:?} {})", name, location.name, location.kind, location.code),
+                None => println!("❓ {:<15} → not found", name),
+            },
+            Err(e) => println!("⚠️  {:<15} → {}", name, e),
+        }
+    }
+
+    println!("\n✈️  HQ → London office");
+    let request = CityFlightSearchRequest {
+        flights: vec![CityFlightData {
+            date: "2025-08-15".to_string(),
+            from_city: "HQ".to_string(),
+            to_city: "London office".to_string(),
+            max_stops: Some(1),
+            airlines: None,
+            departure_time: None,
+            arrival_time: None,
+        }],
+        trip_type: TripType::OneWay,
+        passengers: Passengers::default(),
+        seat_class: SeatClass::Economy,
+    };
+
+    match get_flights_by_city_with(request, &resolver).await {
+        Ok(result) => println!("✅ Found {} flights", result.flights.len()),
+        Err(e) => println!("⚠️  Error: {}", e),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,442 @@
+//! Bundled airport dataset with codes, coordinates, time zones and metro-area
+//! groupings.
+//!
+//! The data lives in `airports.json` and is compiled into the binary, so lookups
+//! never touch the network. It covers major commercial airports rather than every
+//! airfield in the world.
+
+use crate::names::{edit_distance, fold_diacritics};
+use crate::FlightError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Mean Earth radius used for great-circle distances
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Broad world region used to narrow destination searches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Region {
+    NorthAmerica,   // Including Central America and the Caribbean
+    SouthAmerica,
+    Europe,
+    MiddleEast,
+    Africa,
+    Asia,
+    Oceania,
+}
+
+impl Region {
+    /// Region for an ISO 3166-1 alpha-2 country code in the bundled dataset
+    pub fn for_country(country_code: &str) -> Option<Region> {
+        let region = match country_code.to_uppercase().as_str() {
+            "US" | "CA" | "MX" | "PR" | "CU" | "CR" | "PA" => Region::NorthAmerica,
+            "CO" | "EC" | "PE" | "CL" | "AR" | "BR" => Region::SouthAmerica,
+            "GB" | "IE" | "FR" | "NL" | "BE" | "DE" | "CH" | "AT" | "ES" | "PT" | "IT" | "GR" | "TR"
+            | "DK" | "SE" | "NO" | "FI" | "IS" | "PL" | "CZ" | "HU" | "RO" | "BG" | "UA" | "RU" => Region::Europe,
+            "AE" | "QA" | "BH" | "SA" | "IL" | "JO" | "IR" => Region::MiddleEast,
+            "EG" | "MA" | "TN" | "DZ" | "SN" | "GH" | "NG" | "ET" | "KE" | "RW" | "TZ" | "ZA" => Region::Africa,
+            "IN" | "LK" | "MV" | "NP" | "BD" | "PK" | "KZ" | "UZ" | "TH" | "MM" | "VN" | "MY" | "SG"
+            | "ID" | "PH" | "HK" | "MO" | "TW" | "CN" | "KR" | "JP" => Region::Asia,
+            "AU" | "NZ" | "FJ" | "PF" => Region::Oceania,
+            _ => return None,
+        };
+        Some(region)
+    }
+}
+
+impl FromStr for Region {
+    type Err = FlightError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized: String = s.chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+        match normalized.as_str() {
+            "northamerica" => Ok(Region::NorthAmerica),
+            "southamerica" | "latinamerica" => Ok(Region::SouthAmerica),
+            "europe" => Ok(Region::Europe),
+            "middleeast" => Ok(Region::MiddleEast),
+            "africa" => Ok(Region::Africa),
+            "asia" => Ok(Region::Asia),
+            "oceania" | "australia" => Ok(Region::Oceania),
+            _ => Err(FlightError::ParseError(format!("Invalid region: {}", s))),
+        }
+    }
+}
+
+/// A commercial airport from the bundled dataset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Airport {
+    pub iata: String,
+    pub icao: String,
+    pub name: String,
+    pub city: String,
+    pub country: String,   // ISO 3166-1 alpha-2
+    pub latitude: f64,
+    pub longitude: f64,
+    pub timezone: String,  // IANA time zone, e.g. "Europe/London"
+}
+
+impl Airport {
+    /// Great-circle distance to another airport in kilometres
+    pub fn distance_km(&self, other: &Airport) -> f64 {
+        haversine_km(self.latitude, self.longitude, other.latitude, other.longitude)
+    }
+
+    /// World region the airport's country belongs to
+    pub fn region(&self) -> Option<Region> {
+        Region::for_country(&self.country)
+    }
+}
+
+/// A named group of airports serving the same metropolitan area (e.g. "NYC")
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetroArea {
+    pub code: String,
+    pub name: String,
+    pub airports: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AirportData {
+    airports: Vec<Airport>,
+    metros: Vec<MetroArea>,
+}
+
+/// Offline airport database with lookups by code, name, city and position
+pub struct AirportDb {
+    airports: Vec<Airport>,
+    metros: Vec<MetroArea>,
+    by_iata: HashMap<String, usize>,
+    by_icao: HashMap<String, usize>,
+}
+
+/// Global airport database - loaded once and shared across all callers
+static AIRPORT_DB: OnceLock<AirportDb> = OnceLock::new();
+
+impl AirportDb {
+    /// The dataset compiled into the crate
+    pub fn bundled() -> &'static AirportDb {
+        AIRPORT_DB.get_or_init(|| {
+            AirportDb::from_json(include_str!("airports.json")).unwrap_or_else(|e| {
+                eprintln!("Warning: Failed to load airport data: {}. Using empty dataset.", e);
+                AirportDb::new(Vec::new(), Vec::new())
+            })
+        })
+    }
+
+    /// Build a database from JSON in the bundled `{"airports": [...], "metros": [...]}` layout
+    pub fn from_json(json: &str) -> Result<AirportDb, FlightError> {
+        let data: AirportData = serde_json::from_str(json)
+            .map_err(|e| FlightError::ParseError(format!("Invalid airport data: {}", e)))?;
+        Ok(AirportDb::new(data.airports, data.metros))
+    }
+
+    pub fn new(airports: Vec<Airport>, metros: Vec<MetroArea>) -> AirportDb {
+        let by_iata = airports.iter()
+            .enumerate()
+            .map(|(i, airport)| (airport.iata.to_uppercase(), i))
+            .collect();
+        let by_icao = airports.iter()
+            .enumerate()
+            .map(|(i, airport)| (airport.icao.to_uppercase(), i))
+            .collect();
+
+        AirportDb { airports, metros, by_iata, by_icao }
+    }
+
+    pub fn airports(&self) -> &[Airport] {
+        &self.airports
+    }
+
+    pub fn metro_areas(&self) -> &[MetroArea] {
+        &self.metros
+    }
+
+    /// Look up an airport by IATA ("LHR") or ICAO ("EGLL") code, case-insensitively
+    pub fn by_code(&self, code: &str) -> Option<&Airport> {
+        let code = code.trim().to_uppercase();
+        self.by_iata.get(&code)
+            .or_else(|| self.by_icao.get(&code))
+            .map(|&i| &self.airports[i])
+    }
+
+    /// Airports whose name, city or codes match `query`, best match first.
+    ///
+    /// Every word of the query has to match a word of the airport, exactly, as a
+    /// prefix, or with a small typo ("heathrow", "charles de gaule", "narita tokyo").
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&Airport> {
+        let query_words = words(query);
+        if query_words.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scored: Vec<(f64, &Airport)> = self.airports.iter()
+            .filter_map(|airport| {
+                let airport_words = words(&format!("{} {} {} {}", airport.iata, airport.icao, airport.name, airport.city));
+                let mut total = 0.0;
+                for word in &query_words {
+                    let best = airport_words.iter().map(|w| word_score(word, w)).fold(0.0, f64::max);
+                    if best == 0.0 {
+                        return None;
+                    }
+                    total += best;
+                }
+                let mut score = total / query_words.len() as f64;
+                // Prefer airports whose city or code is exactly what was typed
+                let query_text = query_words.join(" ");
+                if words(&airport.city).join(" ") == query_text
+                    || airport.iata.eq_ignore_ascii_case(&query_text)
+                    || airport.icao.eq_ignore_ascii_case(&query_text)
+                {
+                    score += 1.0;
+                }
+                Some((score, airport))
+            })
+            .collect();
+        // Stable sort keeps dataset order (larger airports first) between equal scores
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(limit).map(|(_, airport)| airport).collect()
+    }
+
+    /// The `limit` airports nearest to a coordinate, with their distance in km
+    pub fn nearest(&self, latitude: f64, longitude: f64, limit: usize) -> Vec<(&Airport, f64)> {
+        let mut by_distance: Vec<(&Airport, f64)> = self.airports.iter()
+            .map(|airport| (airport, haversine_km(latitude, longitude, airport.latitude, airport.longitude)))
+            .collect();
+        by_distance.sort_by(|a, b| a.1.total_cmp(&b.1));
+        by_distance.truncate(limit);
+        by_distance
+    }
+
+    /// Airports serving a city, main airport first.
+    ///
+    /// Matches the airport's city case-insensitively, plus the members of a metro
+    /// area with that name ("London" includes every London airport).
+    pub fn in_city(&self, city: &str) -> Vec<&Airport> {
+        let city = words(city).join(" ");
+        let mut airports: Vec<&Airport> = self.airports.iter()
+            .filter(|airport| words(&airport.city).join(" ") == city)
+            .collect();
+        if let Some(metro) = self.metros.iter().find(|m| words(&m.name).join(" ") == city) {
+            for code in &metro.airports {
+                if let Some(airport) = self.by_code(code) {
+                    if !airports.contains(&airport) {
+                        airports.push(airport);
+                    }
+                }
+            }
+        }
+        airports
+    }
+
+    /// Look up a metro area by its code ("LON") or name ("London"), case-insensitively
+    pub fn metro_area(&self, code_or_name: &str) -> Option<&MetroArea> {
+        let needle = code_or_name.trim();
+        self.metros.iter().find(|metro| {
+            metro.code.eq_ignore_ascii_case(needle) || metro.name.eq_ignore_ascii_case(needle)
+        })
+    }
+
+    /// Find the metro area an airport belongs to, if any
+    pub fn metro_area_for_airport(&self, code: &str) -> Option<&MetroArea> {
+        let code = code.trim().to_uppercase();
+        self.metros.iter().find(|metro| metro.airports.contains(&code))
+    }
+
+    /// All airports within `radius_km` of the airport `code`, nearest first.
+    ///
+    /// The airport itself is included at distance 0. Returns an empty list when the
+    /// code is not in the database.
+    pub fn within(&self, code: &str, radius_km: f64) -> Vec<(&Airport, f64)> {
+        let Some(center) = self.by_code(code) else {
+            return Vec::new();
+        };
+
+        let mut nearby: Vec<(&Airport, f64)> = self.airports.iter()
+            .map(|airport| (airport, center.distance_km(airport)))
+            .filter(|(_, distance)| *distance <= radius_km)
+            .collect();
+        nearby.sort_by(|a, b| a.1.total_cmp(&b.1));
+        nearby
+    }
+}
+
+/// All airports in the bundled dataset
+pub fn all_airports() -> &'static [Airport] {
+    AirportDb::bundled().airports()
+}
+
+/// All metro-area groupings in the bundled dataset
+pub fn all_metro_areas() -> &'static [MetroArea] {
+    AirportDb::bundled().metro_areas()
+}
+
+/// Look up an airport by IATA or ICAO code (case-insensitive)
+pub fn find_airport(code: &str) -> Option<&'static Airport> {
+    AirportDb::bundled().by_code(code)
+}
+
+/// Look up a metro area by its code ("LON") or name ("London"), case-insensitively
+pub fn metro_area(code_or_name: &str) -> Option<&'static MetroArea> {
+    AirportDb::bundled().metro_area(code_or_name)
+}
+
+/// Find the metro area an airport belongs to, if any
+pub fn metro_area_for_airport(code: &str) -> Option<&'static MetroArea> {
+    AirportDb::bundled().metro_area_for_airport(code)
+}
+
+/// All airports within `radius_km` of the airport `code`, nearest first.
+///
+/// The airport itself is included at distance 0. Returns an empty list when the
+/// code is not in the bundled dataset.
+pub fn airports_within(code: &str, radius_km: f64) -> Vec<(&'static Airport, f64)> {
+    AirportDb::bundled().within(code, radius_km)
+}
+
+/// Great-circle distance between two coordinates in kilometres
+pub fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// Lowercase alphanumeric words of a name, with accents folded ("Zürich" → "zurich")
+fn words(text: &str) -> Vec<String> {
+    fold_diacritics(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// How well a query word matches a name word, from 0 (no match) to 1 (exact)
+fn word_score(query: &str, word: &str) -> f64 {
+    if query == word {
+        return 1.0;
+    }
+    if query.len() >= 3 && word.starts_with(query) {
+        return 0.8;
+    }
+    let allowed = match query.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    };
+    if allowed > 0 && edit_distance(query, word) <= allowed {
+        return 0.6;
+    }
+    0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset_is_consistent() {
+        assert!(all_airports().len() > 200);
+        for metro in all_metro_areas() {
+            for code in &metro.airports {
+                assert!(find_airport(code).is_some(), "{} lists unknown airport {}", metro.code, code);
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_airport() {
+        let sfo = find_airport("sfo").unwrap();
+        assert_eq!(sfo.city, "San Francisco");
+        assert_eq!(sfo.country, "US");
+        assert!(find_airport("XXX").is_none());
+    }
+
+    #[test]
+    fn test_haversine_km() {
+        let lhr = find_airport("LHR").unwrap();
+        let jfk = find_airport("JFK").unwrap();
+        let distance = lhr.distance_km(jfk);
+        assert!((5500.0..5600.0).contains(&distance), "LHR-JFK was {}", distance);
+        assert_eq!(haversine_km(10.0, 20.0, 10.0, 20.0), 0.0);
+    }
+
+    #[test]
+    fn test_airports_within() {
+        let codes: Vec<&str> = airports_within("SFO", 60.0).iter().map(|(a, _)| a.iata.as_str()).collect();
+        assert_eq!(codes[0], "SFO");
+        assert!(codes.contains(&"OAK"));
+        assert!(codes.contains(&"SJC"));
+        assert!(!codes.contains(&"LAX"));
+        assert!(airports_within("XXX", 100.0).is_empty());
+    }
+
+    #[test]
+    fn test_regions() {
+        for airport in all_airports() {
+            assert!(airport.region().is_some(), "{} ({}) has no region", airport.iata, airport.country);
+        }
+        assert_eq!(find_airport("NRT").unwrap().region(), Some(Region::Asia));
+        assert_eq!("south-america".parse::<Region>().unwrap(), Region::SouthAmerica);
+        assert_eq!("Middle East".parse::<Region>().unwrap(), Region::MiddleEast);
+        assert!("atlantis".parse::<Region>().is_err());
+    }
+
+    #[test]
+    fn test_lookup_by_code() {
+        let db = AirportDb::bundled();
+        let heathrow = db.by_code("egll").unwrap();
+        assert_eq!(heathrow.iata, "LHR");
+        assert_eq!(heathrow.timezone, "Europe/London");
+        assert_eq!(db.by_code("NRT").unwrap().icao, "RJAA");
+        assert!(db.by_code("ZZZZ").is_none());
+    }
+
+    #[test]
+    fn test_search() {
+        let db = AirportDb::bundled();
+        assert_eq!(db.search("heathrow", 5)[0].iata, "LHR");
+        assert_eq!(db.search("Charles de Gaule", 5)[0].iata, "CDG");
+        assert_eq!(db.search("KJFK", 5)[0].iata, "JFK");
+        let tokyo: Vec<&str> = db.search("tokyo", 5).iter().map(|a| a.iata.as_str()).collect();
+        assert!(tokyo.contains(&"HND") && tokyo.contains(&"NRT"));
+        assert!(db.search("xyzzy", 5).is_empty());
+        assert!(db.search("", 5).is_empty());
+    }
+
+    #[test]
+    fn test_nearest() {
+        // Times Square
+        let nearest = AirportDb::bundled().nearest(40.758, -73.9855, 3);
+        assert_eq!(nearest.len(), 3);
+        assert_eq!(nearest[0].0.iata, "LGA");
+        assert!(nearest[0].1 < nearest[1].1);
+    }
+
+    #[test]
+    fn test_in_city() {
+        let db = AirportDb::bundled();
+        let london: Vec<&str> = db.in_city("london").iter().map(|a| a.iata.as_str()).collect();
+        assert_eq!(london[0], "LHR");
+        assert!(london.contains(&"LCY"));
+        assert_eq!(db.in_city("Manchester")[0].iata, "MAN");
+        assert_eq!(db.in_city("Sao Paulo")[0].iata, "GRU");
+        assert_eq!(db.in_city("DUSSELDORF")[0].iata, "DUS");
+        assert!(db.in_city("Atlantis").is_empty());
+    }
+
+    #[test]
+    fn test_metro_area() {
+        assert_eq!(metro_area("nyc").unwrap().airports, vec!["JFK", "LGA", "EWR"]);
+        assert_eq!(metro_area("London").unwrap().code, "LON");
+        assert_eq!(metro_area_for_airport("ORY").unwrap().code, "PAR");
+        assert!(metro_area_for_airport("DEN").is_none());
+    }
+}
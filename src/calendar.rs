@@ -0,0 +1,393 @@
+//! iCalendar (RFC 5545) export of flights and itineraries.
+//!
+//! Each leg of a flight becomes a VEVENT starting and ending in its airports'
+//! local time zones. A VTIMEZONE is generated for every zone used, covering the
+//! daylight saving transitions of the years the events fall in, so calendar
+//! clients show the right times without relying on their own zone tables.
+
+use crate::airports::AirportDb;
+use crate::dates::parse_date;
+use crate::itinerary::PricedItinerary;
+use crate::timezones::{FlightTimes, Tz};
+use crate::{Flight, FlightError};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName};
+use std::collections::BTreeMap;
+
+/// Product identifier written to every calendar
+const PRODUCT_ID: &str = concat!("-//rust-flights//rust-flights ", env!("CARGO_PKG_VERSION"), "//EN");
+
+/// Longest content line in octets before it is folded (RFC 5545 §3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+/// One calendar entry: a single flight leg
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub summary: String,
+    pub description: String,
+    pub location: String,
+    /// Departure, in the departure airport's zone
+    pub start: DateTime<Tz>,
+    /// Arrival, in the arrival airport's zone
+    pub end: DateTime<Tz>,
+}
+
+impl CalendarEvent {
+    /// One event per leg of `flight`, found by a search on `search_date`.
+    ///
+    /// Legs are split at the layover airports using the estimated layover times
+    /// from [`Flight::times`]. When those cannot be placed, the whole journey is a
+    /// single event listing its stops.
+    pub fn from_flight(flight: &Flight, search_date: NaiveDate) -> Result<Vec<CalendarEvent>, FlightError> {
+        let times = flight.times(search_date).ok_or_else(|| unplaceable(flight))?;
+        Ok(Self::from_times(flight, &times))
+    }
+
+    /// Like [`CalendarEvent::from_flight`], for a flight known to fly `origin` → `destination`
+    pub fn from_flight_between(
+        flight: &Flight,
+        origin: &str,
+        destination: &str,
+        search_date: NaiveDate,
+    ) -> Result<Vec<CalendarEvent>, FlightError> {
+        let times = flight.times_between(origin, destination, search_date).ok_or_else(|| unplaceable(flight))?;
+        Ok(Self::from_times(flight, &times))
+    }
+
+    fn from_times(flight: &Flight, times: &FlightTimes) -> Vec<CalendarEvent> {
+        let airports: Vec<&str> = std::iter::once(times.origin.as_str())
+            .chain(times.layovers.iter().map(|layover| layover.airport.as_str()))
+            .chain(std::iter::once(times.destination.as_str()))
+            .collect();
+        let segments = airports.len() - 1;
+        let legs = flight.flight_legs.as_deref().unwrap_or_default();
+        let legs = if legs.len() == segments { legs } else { &[] };
+
+        (0..segments)
+            .map(|i| {
+                let (from, to) = (airports[i], airports[i + 1]);
+                let start = if i == 0 { times.departure } else { times.layovers[i - 1].departure };
+                let end = if i + 1 == segments { times.arrival } else { times.layovers[i].arrival };
+                let flight_number = legs.get(i).map(|leg| format!("{}{}", leg.airline_code, leg.flight_number));
+
+                let summary = match &flight_number {
+                    Some(number) => format!("{} {} → {}", number, from, to),
+                    None => format!("{} {} → {}", flight.name, from, to),
+                };
+                let mut description = vec![format!("{} · {}", flight.name, flight.price)];
+                if segments > 1 {
+                    description.push(format!("Leg {} of {}", i + 1, segments));
+                }
+                if let Some(layover) = times.layovers.get(i) {
+                    description.push(format!("Layover at {}: {} min (estimated times)", layover.airport, layover.minutes));
+                } else if segments == 1 && flight.stops > 0 {
+                    description.push(flight.layover_description.clone().unwrap_or_else(|| format!("{} stop(s)", flight.stops)));
+                }
+
+                CalendarEvent {
+                    uid: format!(
+                        "{}-{}-{}-{}@rust-flights",
+                        start.with_timezone(&Utc).format("%Y%m%dT%H%MZ"),
+                        from,
+                        to,
+                        flight_number.as_deref().unwrap_or("flight"),
+                    ),
+                    summary,
+                    description: description.join("\n"),
+                    location: airport_label(from),
+                    start,
+                    end,
+                }
+            })
+            .collect()
+    }
+}
+
+fn unplaceable(flight: &Flight) -> FlightError {
+    FlightError::ParseError(format!(
+        "Cannot place '{}' ({} → {}) in time: unknown airport or unparseable times",
+        flight.name, flight.departure, flight.arrival
+    ))
+}
+
+/// "Los Angeles International Airport (LAX)", or just the code for unknown airports
+fn airport_label(code: &str) -> String {
+    match AirportDb::bundled().by_code(code) {
+        Some(airport) => format!("{} ({})", airport.name, code),
+        None => code.to_string(),
+    }
+}
+
+/// A calendar of flight events, rendered as an RFC 5545 `.ics` document
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    events: Vec<CalendarEvent>,
+    stamp: DateTime<Utc>,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Calendar {
+    pub fn new() -> Self {
+        Self { events: Vec::new(), stamp: Utc::now() }
+    }
+
+    /// Set the DTSTAMP written to every event (defaults to now)
+    pub fn with_stamp(mut self, stamp: DateTime<Utc>) -> Self {
+        self.stamp = stamp;
+        self
+    }
+
+    pub fn with_events(mut self, events: impl IntoIterator<Item = CalendarEvent>) -> Self {
+        self.events.extend(events);
+        self
+    }
+
+    /// Add every leg of `flight`, found by a search on `search_date`
+    pub fn with_flight(self, flight: &Flight, search_date: NaiveDate) -> Result<Self, FlightError> {
+        let events = CalendarEvent::from_flight(flight, search_date)?;
+        Ok(self.with_events(events))
+    }
+
+    pub fn events(&self) -> &[CalendarEvent] {
+        &self.events
+    }
+
+    /// The `.ics` document, with CRLF line endings and folded long lines
+    pub fn to_ics(&self) -> String {
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            format!("PRODID:{}", PRODUCT_ID),
+            "CALSCALE:GREGORIAN".to_string(),
+            "METHOD:PUBLISH".to_string(),
+        ];
+
+        // Years each zone is used in, so its transitions can be listed
+        let mut zones: BTreeMap<&str, (Tz, i32, i32)> = BTreeMap::new();
+        for time in self.events.iter().flat_map(|event| [&event.start, &event.end]) {
+            let tz = time.timezone();
+            let year = time.year();
+            let entry = zones.entry(tz.name()).or_insert((tz, year, year));
+            entry.1 = entry.1.min(year);
+            entry.2 = entry.2.max(year);
+        }
+        for (tz, first_year, last_year) in zones.values() {
+            lines.extend(vtimezone(*tz, *first_year, *last_year));
+        }
+
+        let stamp = self.stamp.format("%Y%m%dT%H%M%SZ");
+        for event in &self.events {
+            lines.extend([
+                "BEGIN:VEVENT".to_string(),
+                format!("UID:{}", escape_text(&event.uid)),
+                format!("DTSTAMP:{}", stamp),
+                format!("DTSTART;TZID={}:{}", event.start.timezone().name(), event.start.format("%Y%m%dT%H%M%S")),
+                format!("DTEND;TZID={}:{}", event.end.timezone().name(), event.end.format("%Y%m%dT%H%M%S")),
+                format!("SUMMARY:{}", escape_text(&event.summary)),
+                format!("DESCRIPTION:{}", escape_text(&event.description)),
+                format!("LOCATION:{}", escape_text(&event.location)),
+                "TRANSP:OPAQUE".to_string(),
+                "END:VEVENT".to_string(),
+            ]);
+        }
+        lines.push("END:VCALENDAR".to_string());
+
+        lines.iter().map(|line| fold_line(line)).collect::<Vec<_>>().join("")
+    }
+}
+
+/// Escape a TEXT value (RFC 5545 §3.3.11)
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Split a content line into CRLF-terminated chunks of at most 75 octets,
+/// continuation lines starting with a space
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// "+0530" / "-0800"
+fn format_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    format!("{}{:02}{:02}", sign, seconds / 3600, seconds % 3600 / 60)
+}
+
+/// A VTIMEZONE for `tz` listing its offset at the start of `first_year` and every
+/// transition up to the end of `last_year`
+fn vtimezone(tz: Tz, first_year: i32, last_year: i32) -> Vec<String> {
+    let offset_at = |instant: DateTime<Utc>| tz.offset_from_utc_datetime(&instant.naive_utc());
+    let seconds_at = |instant: DateTime<Utc>| offset_at(instant).fix().local_minus_utc();
+    let year_start = |year: i32| Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).single();
+
+    let (Some(start), Some(end)) = (year_start(first_year), year_start(last_year + 1)) else {
+        return Vec::new();
+    };
+
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", tz.name())];
+    let mut component = |instant: DateTime<Utc>, from: i32| {
+        let offset = offset_at(instant);
+        let kind = if offset.dst_offset().is_zero() { "STANDARD" } else { "DAYLIGHT" };
+        // DTSTART is the local time of the change in the offset being left
+        let local = instant.naive_utc() + Duration::seconds(from as i64);
+        lines.extend([
+            format!("BEGIN:{}", kind),
+            format!("DTSTART:{}", local.format("%Y%m%dT%H%M%S")),
+            format!("TZOFFSETFROM:{}", format_offset(from)),
+            format!("TZOFFSETTO:{}", format_offset(offset.fix().local_minus_utc())),
+            format!("TZNAME:{}", offset.abbreviation().unwrap_or(tz.name())),
+            format!("END:{}", kind),
+        ]);
+    };
+
+    component(start, seconds_at(start));
+    let mut day = start;
+    while day < end {
+        let next = day + Duration::days(1);
+        let before = seconds_at(day);
+        if seconds_at(next) != before {
+            // Narrow the change down to the minute
+            let (mut low, mut high) = (0, 24 * 60);
+            while high - low > 1 {
+                let middle = (low + high) / 2;
+                if seconds_at(day + Duration::minutes(middle)) == before { low = middle } else { high = middle }
+            }
+            component(day + Duration::minutes(high), before);
+        }
+        day = next;
+    }
+
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+}
+
+/// An `.ics` document with one event per leg of `flight`
+pub fn flight_to_ics(flight: &Flight, search_date: NaiveDate) -> Result<String, FlightError> {
+    Ok(Calendar::new().with_flight(flight, search_date)?.to_ics())
+}
+
+impl PricedItinerary {
+    /// The cheapest one-way flight of every leg as a calendar. Fails if a leg has
+    /// no flight or its times cannot be placed.
+    pub fn to_calendar(&self) -> Result<Calendar, FlightError> {
+        let mut calendar = Calendar::new();
+        for quote in &self.legs {
+            let flight = quote.cheapest.as_ref().ok_or_else(|| {
+                FlightError::ParseError(format!("No flight found for {} → {}", quote.leg.from_airport, quote.leg.to_airport))
+            })?;
+            let date = parse_date(&quote.leg.date)?;
+            calendar = calendar.with_events(CalendarEvent::from_flight_between(
+                flight,
+                &quote.leg.from_airport,
+                &quote.leg.to_airport,
+                date,
+            )?);
+        }
+        Ok(calendar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connecting_flight() -> Flight {
+        Flight::test("United", 320)
+            .best()
+            .schedule("6:00 AM on Fri, Aug 15", "5:30 PM on Fri, Aug 15", "8 hr 30 min")
+            .route("SFO", "JFK")
+            .legs(&[("UA", "100"), ("UA", "200")])
+            .layovers(&["DEN"], "Layover (1 of 1) is a 1 hr layover at Denver International Airport in Denver.")
+    }
+
+    #[test]
+    fn test_one_event_per_leg() {
+        let events = CalendarEvent::from_flight(&connecting_flight(), parse_date("2025-08-15").unwrap()).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].summary, "UA100 SFO → DEN");
+        assert_eq!(events[1].summary, "UA200 DEN → JFK");
+        assert_eq!(events[0].start.timezone(), chrono_tz::America::Los_Angeles);
+        assert_eq!(events[0].end.timezone(), chrono_tz::America::Denver);
+        assert_eq!(events[1].end.format("%H:%M").to_string(), "17:30");
+        assert_eq!((events[1].start - events[0].end).num_minutes(), 60);
+        assert!(events[0].location.contains("(SFO)"));
+
+        // Without layover details the journey is one event
+        let mut direct = connecting_flight();
+        direct.layovers = None;
+        let events = CalendarEvent::from_flight(&direct, parse_date("2025-08-15").unwrap()).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, "United SFO → JFK");
+        assert!(events[0].description.contains("Denver"));
+    }
+
+    #[test]
+    fn test_ics_document() {
+        let stamp = Utc.with_ymd_and_hms(2025, 8, 1, 12, 0, 0).unwrap();
+        let ics = Calendar::new()
+            .with_stamp(stamp)
+            .with_flight(&connecting_flight(), parse_date("2025-08-15").unwrap())
+            .unwrap()
+            .to_ics();
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("DTSTART;TZID=America/Los_Angeles:20250815T060000\r\n"));
+        assert!(ics.contains("DTEND;TZID=America/New_York:20250815T173000\r\n"));
+        assert!(ics.contains("DTSTAMP:20250801T120000Z\r\n"));
+        assert!(ics.contains("DESCRIPTION:United · $320\\nLeg 1 of 2"));
+
+        // One VTIMEZONE per zone, with 2025's DST changes
+        assert_eq!(ics.matches("BEGIN:VTIMEZONE").count(), 3);
+        assert!(ics.contains("TZID:America/New_York\r\n"));
+        assert!(ics.contains("BEGIN:DAYLIGHT\r\nDTSTART:20250309T020000\r\nTZOFFSETFROM:-0500\r\nTZOFFSETTO:-0400\r\nTZNAME:EDT\r\n"));
+        assert!(ics.contains("BEGIN:STANDARD\r\nDTSTART:20251102T020000\r\nTZOFFSETFROM:-0400\r\nTZOFFSETTO:-0500\r\nTZNAME:EST\r\n"));
+
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+    }
+
+    #[test]
+    fn test_zone_without_dst() {
+        let lines = vtimezone(chrono_tz::Asia::Kolkata, 2025, 2025);
+        assert_eq!(lines.iter().filter(|line| line.starts_with("BEGIN:STANDARD")).count(), 1);
+        assert!(lines.contains(&"TZOFFSETTO:+0530".to_string()));
+    }
+
+    #[test]
+    fn test_text_escaping_and_folding() {
+        assert_eq!(escape_text("a, b; c\\d\ne"), "a\\, b\\; c\\\\d\\ne");
+        let folded = fold_line(&format!("SUMMARY:{}", "✈".repeat(40)));
+        assert!(folded.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert_eq!(folded.replace("\r\n ", "").trim_end(), format!("SUMMARY:{}", "✈".repeat(40)));
+    }
+
+    #[test]
+    fn test_unplaceable_flight() {
+        let mut flight = connecting_flight();
+        flight.origin_airport = Some("ZZZ".to_string());
+        assert!(flight_to_ics(&flight, parse_date("2025-08-15").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! Integrity checks for the bundled city cache (`city_cache.json`) and a
+//! reviewable diff against freshly resolved Wikidata IDs.
+
+use crate::wikidata::{bundled_city_aliases, bundled_city_cache, is_city_name_match};
+use crate::{WikidataClient, WikidataError};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A problem found in a city cache
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum CacheIssue {
+    /// Different cities share one Freebase ID (name variants like "New York" and
+    /// "New York City" are allowed)
+    DuplicateId { freebase_id: String, names: Vec<String> },
+    /// The value is not a Freebase ID ("/m/..." or "/g/...")
+    MalformedId { name: String, freebase_id: String },
+    /// The entry has an empty or padded name
+    BadName { name: String },
+    /// Wikidata resolves the name to a different ID
+    Mismatch { name: String, cached: String, wikidata: String },
+    /// Wikidata could not resolve the name
+    Unresolved { name: String },
+}
+
+/// Changes a regenerated cache would make, for review before applying
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheDiff {
+    pub added: BTreeMap<String, String>,
+    /// name → (cached ID, regenerated ID)
+    pub changed: BTreeMap<String, (String, String)>,
+    /// Names kept as they are because Wikidata could not resolve them
+    pub unresolved: Vec<String>,
+}
+
+impl CacheDiff {
+    /// Diff `old` against `new`. Names in `old` missing from `new` are reported as
+    /// unresolved rather than removed, since a failed lookup is no reason to drop them.
+    pub fn between(old: &HashMap<String, String>, new: &HashMap<String, String>) -> Self {
+        let mut diff = CacheDiff::default();
+        for (name, old_id) in old {
+            match new.get(name) {
+                Some(new_id) if new_id != old_id => {
+                    diff.changed.insert(name.clone(), (old_id.clone(), new_id.clone()));
+                }
+                Some(_) => {}
+                None => diff.unresolved.push(name.clone()),
+            }
+        }
+        for (name, new_id) in new {
+            if !old.contains_key(name) {
+                diff.added.insert(name.clone(), new_id.clone());
+            }
+        }
+        diff.unresolved.sort();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.unresolved.is_empty()
+    }
+
+    /// `cache` with the additions and changes applied
+    pub fn apply(&self, cache: &HashMap<String, String>) -> BTreeMap<String, String> {
+        let mut updated: BTreeMap<String, String> = cache.iter()
+            .map(|(name, id)| (name.clone(), id.clone()))
+            .collect();
+        updated.extend(self.added.clone());
+        for (name, (_, new_id)) in &self.changed {
+            updated.insert(name.clone(), new_id.clone());
+        }
+        updated
+    }
+}
+
+/// Result of auditing a city cache
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheAudit {
+    pub checked: usize,
+    pub issues: Vec<CacheIssue>,
+    /// Only set by [`audit_city_cache_online`]
+    pub diff: Option<CacheDiff>,
+}
+
+/// Offline checks: duplicate IDs across different cities, malformed IDs and bad names
+pub fn validate_city_cache(cache: &HashMap<String, String>) -> Vec<CacheIssue> {
+    let mut issues = Vec::new();
+
+    let mut names: Vec<&String> = cache.keys().collect();
+    names.sort();
+    for name in &names {
+        let freebase_id = &cache[*name];
+        if name.trim().is_empty() || name.trim() != name.as_str() {
+            issues.push(CacheIssue::BadName { name: name.to_string() });
+        }
+        if !is_freebase_id(freebase_id) {
+            issues.push(CacheIssue::MalformedId { name: name.to_string(), freebase_id: freebase_id.clone() });
+        }
+    }
+
+    let mut by_id: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for name in &names {
+        by_id.entry(cache[*name].as_str()).or_default().push(name.to_string());
+    }
+    for (freebase_id, names) in by_id {
+        if names.len() > 1 && !all_name_variants(&names) {
+            issues.push(CacheIssue::DuplicateId { freebase_id: freebase_id.to_string(), names });
+        }
+    }
+
+    issues
+}
+
+/// Validate the bundled `city_cache.json`
+pub fn validate_bundled_city_cache() -> CacheAudit {
+    let cache = bundled_city_cache();
+    CacheAudit { checked: cache.len(), issues: validate_city_cache(cache), diff: None }
+}
+
+/// Offline checks plus a cross-check against Wikidata: every name is re-resolved
+/// with [`WikidataClient::populate_cache_from_cities`] and the differences are
+/// returned as a diff. Nothing is written.
+pub async fn audit_city_cache_online(
+    client: &WikidataClient,
+    cache: &HashMap<String, String>,
+) -> Result<CacheAudit, WikidataError> {
+    let mut issues = validate_city_cache(cache);
+
+    let mut names: Vec<&str> = cache.keys().map(|name| name.as_str()).collect();
+    names.sort();
+    let regenerated = client.populate_cache_from_cities(names).await?;
+    let diff = CacheDiff::between(cache, &regenerated);
+
+    for (name, (cached, wikidata)) in &diff.changed {
+        issues.push(CacheIssue::Mismatch { name: name.clone(), cached: cached.clone(), wikidata: wikidata.clone() });
+    }
+    for name in &diff.unresolved {
+        issues.push(CacheIssue::Unresolved { name: name.clone() });
+    }
+
+    Ok(CacheAudit { checked: cache.len(), issues, diff: Some(diff) })
+}
+
+/// Whether `value` looks like a Freebase machine ID ("/m/01914") or Knowledge Graph ID ("/g/11b...")
+fn is_freebase_id(value: &str) -> bool {
+    let Some(rest) = value.strip_prefix("/m/").or_else(|| value.strip_prefix("/g/")) else {
+        return false;
+    };
+    !rest.is_empty() && rest.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Whether all names are spellings of the same city: they match after folding
+/// ("St. Louis", "Saint Louis") or the alias table maps them to one city
+/// ("New York", "New York City")
+fn all_name_variants(names: &[String]) -> bool {
+    let first = canonical_name(&names[0]);
+    names[1..].iter().all(|name| is_city_name_match(first, canonical_name(name)))
+}
+
+/// The cached name a bundled alias points at, or the name itself
+fn canonical_name(name: &str) -> &str {
+    bundled_city_aliases()
+        .iter()
+        .find(|(alias, _)| is_city_name_match(alias, name))
+        .map(|(_, target)| target.as_str())
+        .unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries.iter().map(|(name, id)| (name.to_string(), id.to_string())).collect()
+    }
+
+    #[test]
+    fn test_bundled_cache_is_valid() {
+        let audit = validate_bundled_city_cache();
+        assert!(audit.checked > 200);
+        assert_eq!(audit.issues, Vec::new());
+    }
+
+    #[test]
+    fn test_validate_city_cache() {
+        let issues = validate_city_cache(&cache(&[
+            ("Beijing", "/m/01914"),
+            ("Chongqing", "/m/01914"),
+            ("New York", "/m/02_286"),
+            ("New York City", "/m/02_286"),
+            ("Lyon", "Q456"),
+            (" Paris", "/m/05qtj"),
+        ]));
+        assert_eq!(issues, vec![
+            CacheIssue::BadName { name: " Paris".to_string() },
+            CacheIssue::MalformedId { name: "Lyon".to_string(), freebase_id: "Q456".to_string() },
+            CacheIssue::DuplicateId {
+                freebase_id: "/m/01914".to_string(),
+                names: vec!["Beijing".to_string(), "Chongqing".to_string()],
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_cache_diff() {
+        let old = cache(&[("Beijing", "/m/01914"), ("Chongqing", "/m/01914"), ("Atlantis", "/m/000")]);
+        let new = cache(&[("Beijing", "/m/01914"), ("Chongqing", "/m/017236"), ("Lyon", "/m/0dprg")]);
+        let diff = CacheDiff::between(&old, &new);
+
+        assert_eq!(diff.changed["Chongqing"], ("/m/01914".to_string(), "/m/017236".to_string()));
+        assert_eq!(diff.added["Lyon"], "/m/0dprg");
+        assert_eq!(diff.unresolved, vec!["Atlantis".to_string()]);
+
+        let applied = diff.apply(&old);
+        assert_eq!(applied["Chongqing"], "/m/017236");
+        assert_eq!(applied["Atlantis"], "/m/000");
+        assert_eq!(applied.len(), 4);
+        assert!(CacheDiff::between(&old, &old).is_empty());
+    }
+}
@@ -0,0 +1,317 @@
+//! Persistent city cache backed by sled.
+//!
+//! Every city resolved through Wikidata is written to a local sled database so
+//! later runs skip the SPARQL round trip. Entries older than the store's TTL are
+//! refreshed from Wikidata on the next lookup; if that refresh fails the stale
+//! entry is still used.
+
+use crate::names::fold_name;
+use crate::{CityInfo, WikidataError};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Default time before a stored city is refreshed from Wikidata
+pub const DEFAULT_CITY_TTL_DAYS: i64 = 30;
+
+/// Environment variable overriding the default store location
+pub const CITY_STORE_PATH_ENV: &str = "RUST_FLIGHTS_CITY_STORE";
+
+const CITIES_TREE: &str = "cities";
+const ALIASES_TREE: &str = "aliases";
+
+/// A resolved city as persisted in the store
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredCity {
+    pub name: String,
+    /// Other names this city was looked up by
+    pub aliases: Vec<String>,
+    pub freebase_id: String,
+    pub wikidata_id: Option<String>,
+    pub country: Option<String>,
+    pub coordinates: Option<(f64, f64)>, // (latitude, longitude)
+    /// When the city was last resolved from Wikidata
+    pub resolved_at: DateTime<Utc>,
+}
+
+impl StoredCity {
+    pub fn new(name: &str, freebase_id: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            aliases: Vec::new(),
+            freebase_id: freebase_id.to_string(),
+            wikidata_id: None,
+            country: None,
+            coordinates: None,
+            resolved_at: Utc::now(),
+        }
+    }
+
+    /// Build a stored city from a Wikidata result; `None` if it has no Freebase ID.
+    /// `query` is recorded as an alias when it differs from the Wikidata label.
+    pub fn from_city_info(query: &str, info: &CityInfo) -> Option<Self> {
+        let mut city = Self::new(&info.name, info.freebase_id.as_deref()?);
+        city.wikidata_id = Some(info.wikidata_id.clone()).filter(|id| !id.is_empty());
+        city.country = info.country.clone().or_else(|| info.country_code.clone());
+        city.coordinates = info.coordinates;
+        if store_key(query) != store_key(&info.name) {
+            city.aliases.push(query.trim().to_string());
+        }
+        Some(city)
+    }
+}
+
+/// sled-backed store of resolved cities, keyed case-insensitively by name and alias
+#[derive(Clone)]
+pub struct CityStore {
+    db: sled::Db,
+    cities: sled::Tree,
+    aliases: sled::Tree,
+    ttl: Duration,
+}
+
+impl CityStore {
+    /// Open (or create) a store at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, WikidataError> {
+        Self::from_db(sled::open(path).map_err(store_error)?)
+    }
+
+    /// Open an in-memory store that is discarded when dropped (useful for tests)
+    pub fn temporary() -> Result<Self, WikidataError> {
+        Self::from_db(sled::Config::new().temporary(true).open().map_err(store_error)?)
+    }
+
+    /// The process-wide store at [`default_store_path`], opened on first use.
+    ///
+    /// sled allows one open handle per database, so every `WikidataClient` shares
+    /// this one. Returns `None` (after a warning) if it cannot be opened. Unit
+    /// tests get a temporary store instead, so they never read or write the
+    /// developer's cache.
+    pub fn shared() -> Option<CityStore> {
+        static SHARED: OnceLock<Option<CityStore>> = OnceLock::new();
+        SHARED.get_or_init(open_shared).clone()
+    }
+
+    fn from_db(db: sled::Db) -> Result<Self, WikidataError> {
+        Ok(Self {
+            cities: db.open_tree(CITIES_TREE).map_err(store_error)?,
+            aliases: db.open_tree(ALIASES_TREE).map_err(store_error)?,
+            db,
+            ttl: Duration::days(DEFAULT_CITY_TTL_DAYS),
+        })
+    }
+
+    /// Set how long entries stay fresh
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Whether `city` is older than the TTL
+    pub fn is_stale(&self, city: &StoredCity) -> bool {
+        Utc::now() - city.resolved_at > self.ttl
+    }
+
+    /// Look up a city by name or alias, only if it is still fresh
+    pub fn get(&self, name: &str) -> Result<Option<StoredCity>, WikidataError> {
+        Ok(self.get_any(name)?.filter(|city| !self.is_stale(city)))
+    }
+
+    /// Look up a city by name or alias, fresh or stale
+    pub fn get_any(&self, name: &str) -> Result<Option<StoredCity>, WikidataError> {
+        let key = store_key(name);
+        let canonical = match self.aliases.get(key.as_bytes()).map_err(store_error)? {
+            Some(target) => target.to_vec(),
+            None => key.into_bytes(),
+        };
+        match self.cities.get(canonical).map_err(store_error)? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store a city, merging aliases with any existing entry of the same name
+    pub fn insert(&self, city: &StoredCity) -> Result<(), WikidataError> {
+        let key = store_key(&city.name);
+        let mut city = city.clone();
+        if let Some(existing) = self.get_any(&city.name)? {
+            for alias in existing.aliases {
+                if !city.aliases.iter().any(|a| store_key(a) == store_key(&alias)) {
+                    city.aliases.push(alias);
+                }
+            }
+        }
+
+        self.cities
+            .insert(key.as_bytes(), serde_json::to_vec(&city)?)
+            .map_err(store_error)?;
+        for alias in &city.aliases {
+            let alias_key = store_key(alias);
+            if alias_key != key {
+                self.aliases.insert(alias_key.as_bytes(), key.as_bytes()).map_err(store_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove a city and its aliases; returns whether it was stored
+    pub fn remove(&self, name: &str) -> Result<bool, WikidataError> {
+        let Some(city) = self.get_any(name)? else {
+            return Ok(false);
+        };
+        self.cities.remove(store_key(&city.name).as_bytes()).map_err(store_error)?;
+        for alias in &city.aliases {
+            self.aliases.remove(store_key(alias).as_bytes()).map_err(store_error)?;
+        }
+        Ok(true)
+    }
+
+    /// All stored cities, fresh or stale
+    pub fn cities(&self) -> Result<Vec<StoredCity>, WikidataError> {
+        self.cities
+            .iter()
+            .values()
+            .map(|bytes| Ok(serde_json::from_slice(&bytes.map_err(store_error)?)?))
+            .collect()
+    }
+
+    /// Number of stored cities (aliases not counted)
+    pub fn len(&self) -> usize {
+        self.cities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cities.is_empty()
+    }
+
+    /// Flush pending writes to disk
+    pub fn flush(&self) -> Result<(), WikidataError> {
+        self.db.flush().map_err(store_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(not(test))]
+fn open_shared() -> Option<CityStore> {
+    let path = default_store_path();
+    match CityStore::open(&path) {
+        Ok(store) => Some(store),
+        Err(e) => {
+            eprintln!("⚠️  Warning: City store at {} unavailable: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+fn open_shared() -> Option<CityStore> {
+    CityStore::temporary().ok()
+}
+
+/// Where the shared store lives: `$RUST_FLIGHTS_CITY_STORE`, otherwise
+/// `rust-flights/cities` under `$XDG_CACHE_HOME`, `~/.cache` or the temp dir
+pub fn default_store_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(CITY_STORE_PATH_ENV) {
+        return PathBuf::from(path);
+    }
+    let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir);
+    cache_dir.join("rust-flights").join("cities")
+}
+
+/// Case-, accent- and whitespace-insensitive key for a city name
+fn store_key(name: &str) -> String {
+    fold_name(name)
+}
+
+fn store_error(e: sled::Error) -> WikidataError {
+    WikidataError::StoreError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lyon() -> StoredCity {
+        let mut city = StoredCity::new("Lyon", "/m/0dprg");
+        city.wikidata_id = Some("Q456".to_string());
+        city.country = Some("France".to_string());
+        city.coordinates = Some((45.76, 4.84));
+        city.aliases = vec!["Lyons".to_string()];
+        city
+    }
+
+    #[test]
+    fn test_insert_and_lookup() {
+        let store = CityStore::temporary().unwrap();
+        assert!(store.is_empty());
+        let city = lyon();
+        store.insert(&city).unwrap();
+
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.get("Lyon").unwrap(), Some(city));
+        assert_eq!(store.get("  LYONS ").unwrap().map(|c| c.freebase_id), Some("/m/0dprg".to_string()));
+        assert_eq!(store.get("Lyön").unwrap().map(|c| c.name), Some("Lyon".to_string()));
+        assert_eq!(store.get("Marseille").unwrap(), None);
+    }
+
+    #[test]
+    fn test_aliases_merge_and_remove() {
+        let store = CityStore::temporary().unwrap();
+        store.insert(&lyon()).unwrap();
+
+        let mut refreshed = StoredCity::new("Lyon", "/m/0dprg");
+        refreshed.aliases = vec!["Lugdunum".to_string()];
+        store.insert(&refreshed).unwrap();
+
+        let stored = store.get("lugdunum").unwrap().unwrap();
+        assert_eq!(stored.aliases, vec!["Lugdunum".to_string(), "Lyons".to_string()]);
+        assert_eq!(store.len(), 1);
+
+        assert!(store.remove("Lyons").unwrap());
+        assert_eq!(store.get_any("Lyon").unwrap(), None);
+        assert_eq!(store.get_any("Lugdunum").unwrap(), None);
+        assert!(!store.remove("Lyon").unwrap());
+    }
+
+    #[test]
+    fn test_ttl() {
+        let store = CityStore::temporary().unwrap().with_ttl(Duration::days(7));
+        let mut city = lyon();
+        city.resolved_at = Utc::now() - Duration::days(8);
+        store.insert(&city).unwrap();
+
+        assert!(store.is_stale(&city));
+        assert_eq!(store.get("Lyon").unwrap(), None);
+        assert_eq!(store.get_any("Lyon").unwrap(), Some(city));
+    }
+
+    #[test]
+    fn test_from_city_info() {
+        let info = CityInfo {
+            name: "Munich".to_string(),
+            freebase_id: Some("/m/02h6_6p".to_string()),
+            country: Some("Germany".to_string()),
+            country_code: Some("DE".to_string()),
+            wikidata_id: "Q1726".to_string(),
+            population: None,
+            coordinates: Some((48.14, 11.58)),
+            aliases: Vec::new(),
+            regions: Vec::new(),
+        };
+        let city = StoredCity::from_city_info("München", &info).unwrap();
+        assert_eq!(city.aliases, vec!["München".to_string()]);
+        assert_eq!(city.wikidata_id.as_deref(), Some("Q1726"));
+        assert!(StoredCity::from_city_info("Munich", &info).unwrap().aliases.is_empty());
+
+        let no_freebase = CityInfo { freebase_id: None, ..info };
+        assert!(StoredCity::from_city_info("Munich", &no_freebase).is_none());
+    }
+}
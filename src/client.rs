@@ -29,8 +29,6 @@ impl FlightClient {
         // Encode to base64
         let encoded = encode_to_base64(&info)?;
         
-        println!("Encoded: {}", encoded);
-
         // Build URL
         let url = format!("https://www.google.com/travel/flights?tfs={}", encoded);
         
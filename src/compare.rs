@@ -0,0 +1,161 @@
+//! Round trip versus two one-way tickets price comparison.
+
+use crate::dates::cheapest_flight;
+use crate::{Flight, FlightClient, FlightError, FlightSearchRequest, TripType};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Which way of booking is cheaper
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CheaperOption {
+    RoundTrip,
+    OneWays,
+    Same,
+}
+
+/// A round-trip fare compared with booking the outbound and return separately
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TripComparison {
+    /// Cheapest round-trip option; its price is the fare for both directions
+    pub round_trip: Option<Flight>,
+    pub round_trip_error: Option<String>,
+    /// Cheapest one-way outbound flight
+    pub outbound: Option<Flight>,
+    pub outbound_error: Option<String>,
+    /// Cheapest one-way return flight
+    pub return_flight: Option<Flight>,
+    pub return_error: Option<String>,
+}
+
+impl TripComparison {
+    /// Round-trip fare, if found
+    pub fn round_trip_price(&self) -> Option<i32> {
+        self.round_trip.as_ref().map(|f| f.price.amount)
+    }
+
+    /// Combined fare of the two one-ways, if both directions were found
+    pub fn one_way_total(&self) -> Option<i32> {
+        Some(self.outbound.as_ref()?.price.amount + self.return_flight.as_ref()?.price.amount)
+    }
+
+    /// The cheaper way to book, if both options were priced
+    pub fn cheaper(&self) -> Option<CheaperOption> {
+        let round_trip = self.round_trip_price()?;
+        let one_ways = self.one_way_total()?;
+        Some(match round_trip.cmp(&one_ways) {
+            std::cmp::Ordering::Less => CheaperOption::RoundTrip,
+            std::cmp::Ordering::Greater => CheaperOption::OneWays,
+            std::cmp::Ordering::Equal => CheaperOption::Same,
+        })
+    }
+
+    /// Price difference between the two options
+    pub fn savings(&self) -> Option<i32> {
+        Some((self.round_trip_price()? - self.one_way_total()?).abs())
+    }
+}
+
+/// Split a two-leg round-trip request into its outbound and return one-way requests
+pub fn one_way_requests(request: &FlightSearchRequest) -> Result<(FlightSearchRequest, FlightSearchRequest), FlightError> {
+    if !matches!(request.trip_type, TripType::RoundTrip) || request.flights.len() != 2 {
+        return Err(FlightError::ParseError(
+            "Comparison needs a round-trip request with exactly two legs".to_string(),
+        ));
+    }
+
+    let one_way = |leg: usize| FlightSearchRequest {
+        flights: vec![request.flights[leg].clone()],
+        trip_type: TripType::OneWay,
+        passengers: request.passengers.clone(),
+        seat_class: request.seat_class.clone(),
+    };
+    Ok((one_way(0), one_way(1)))
+}
+
+impl FlightClient {
+    /// Search a round trip and its outbound and return as separate one-ways, and
+    /// report the cheapest flight for each.
+    ///
+    /// A failed search is recorded in the comparison rather than aborting it.
+    pub async fn compare_round_trip(
+        &self,
+        request: FlightSearchRequest,
+        request_delay: Duration,
+    ) -> Result<TripComparison, FlightError> {
+        let (outbound_request, return_request) = one_way_requests(&request)?;
+
+        let (round_trip, round_trip_error) = self.cheapest_or_error(request, "Round-trip").await;
+        tokio::time::sleep(request_delay).await;
+        let (outbound, outbound_error) = self.cheapest_or_error(outbound_request, "Outbound").await;
+        tokio::time::sleep(request_delay).await;
+        let (return_flight, return_error) = self.cheapest_or_error(return_request, "Return").await;
+
+        Ok(TripComparison {
+            round_trip,
+            round_trip_error,
+            outbound,
+            outbound_error,
+            return_flight,
+            return_error,
+        })
+    }
+
+    /// Cheapest flight for `request`, or the search error as a string
+    async fn cheapest_or_error(&self, request: FlightSearchRequest, label: &str) -> (Option<Flight>, Option<String>) {
+        match self.get_flights(request).await {
+            Ok(result) => (cheapest_flight(&result.flights).cloned(), None),
+            Err(e) => {
+                eprintln!("⚠️  Warning: {} search failed: {}", label, e);
+                (None, Some(e.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SearchOptions;
+
+    fn flight(amount: i32) -> Flight {
+        Flight::test("Alaska", amount).schedule("7:00 AM", "3:30 PM", "5 hr 30 min")
+    }
+
+    fn comparison(round_trip: Option<i32>, outbound: Option<i32>, return_flight: Option<i32>) -> TripComparison {
+        TripComparison {
+            round_trip: round_trip.map(flight),
+            round_trip_error: None,
+            outbound: outbound.map(flight),
+            outbound_error: None,
+            return_flight: return_flight.map(flight),
+            return_error: None,
+        }
+    }
+
+    #[test]
+    fn test_cheaper_option() {
+        let c = comparison(Some(500), Some(180), Some(220));
+        assert_eq!(c.one_way_total(), Some(400));
+        assert_eq!(c.cheaper(), Some(CheaperOption::OneWays));
+        assert_eq!(c.savings(), Some(100));
+
+        assert_eq!(comparison(Some(350), Some(180), Some(220)).cheaper(), Some(CheaperOption::RoundTrip));
+        assert_eq!(comparison(Some(400), Some(180), Some(220)).cheaper(), Some(CheaperOption::Same));
+        assert_eq!(comparison(Some(400), Some(180), None).cheaper(), None);
+    }
+
+    #[test]
+    fn test_one_way_requests() {
+        let request = SearchOptions::default().round_trip_request("SEA", "JFK", "2025-09-01", "2025-09-08");
+        let (outbound, return_request) = one_way_requests(&request).unwrap();
+        assert!(matches!(outbound.trip_type, TripType::OneWay));
+        assert_eq!(outbound.flights.len(), 1);
+        assert_eq!(outbound.flights[0].from_airport, "SEA");
+        assert_eq!(return_request.flights[0].from_airport, "JFK");
+        assert_eq!(return_request.flights[0].date, "2025-09-08");
+
+        let one_way = SearchOptions::default().one_way_request("SEA", "JFK", "2025-09-01");
+        assert!(one_way_requests(&one_way).is_err());
+    }
+}
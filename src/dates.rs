@@ -0,0 +1,511 @@
+//! Flexible date searches
+//!
+//! Google Flights answers one date per request, so finding the cheapest day to
+//! fly means fanning out one search per candidate date and comparing results.
+//! The same applies to round trips, where every departure/return pair is a
+//! separate search that together form a price calendar.
+
+use crate::{Flight, FlightClient, FlightError, FlightPrice, FlightResult, SearchOptions};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Upper bound on the number of dates searched in one call, to avoid hammering Google
+pub const MAX_DATE_RANGE_DAYS: usize = 62;
+
+/// Upper bound on the number of departure/return combinations in one price matrix
+pub const MAX_PRICE_MATRIX_CELLS: usize = 120;
+
+/// Summary of the one-way search for a single date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaySummary {
+    pub date: String,
+    pub current_price: String,          // "low", "typical", "high" as reported for that date
+    pub flight_count: usize,
+    pub cheapest_price: Option<FlightPrice>,
+    pub cheapest_flight: Option<Flight>,
+    pub best_flight: Option<Flight>,    // Google's top pick for that date
+    pub error: Option<String>,          // Set when the search for this date failed
+}
+
+impl DaySummary {
+    /// Summarise a successful search result for `date`
+    pub fn from_result(date: &str, result: &FlightResult) -> Self {
+        let cheapest_flight = cheapest_flight(&result.flights).cloned();
+        let best_flight = result.flights.iter()
+            .find(|f| f.is_best)
+            .or_else(|| result.flights.first())
+            .cloned();
+
+        Self {
+            date: date.to_string(),
+            current_price: result.current_price.clone(),
+            flight_count: result.flights.len(),
+            cheapest_price: cheapest_flight.as_ref().map(|f| f.price.clone()),
+            cheapest_flight,
+            best_flight,
+            error: None,
+        }
+    }
+
+    /// Record a failed search for `date`
+    pub fn from_error(date: &str, error: &FlightError) -> Self {
+        Self {
+            date: date.to_string(),
+            current_price: "unknown".to_string(),
+            flight_count: 0,
+            cheapest_price: None,
+            cheapest_flight: None,
+            best_flight: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Per-day summaries for a date range search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DateRangeResult {
+    pub origin: String,
+    pub destination: String,
+    pub days: Vec<DaySummary>,
+    pub cheapest_date: Option<String>,
+}
+
+impl DateRangeResult {
+    /// Build a result from per-day summaries, working out the overall cheapest date
+    pub fn new(origin: &str, destination: &str, days: Vec<DaySummary>) -> Self {
+        let cheapest_date = days.iter()
+            .filter_map(|day| day.cheapest_price.as_ref().map(|price| (price.amount, &day.date)))
+            .min_by_key(|(amount, _)| *amount)
+            .map(|(_, date)| date.clone());
+
+        Self {
+            origin: origin.to_string(),
+            destination: destination.to_string(),
+            days,
+            cheapest_date,
+        }
+    }
+
+    /// The day with the overall cheapest fare
+    pub fn cheapest_day(&self) -> Option<&DaySummary> {
+        let date = self.cheapest_date.as_ref()?;
+        self.days.iter().find(|day| &day.date == date)
+    }
+
+    /// Summary for a specific date (YYYY-MM-DD)
+    pub fn day(&self, date: &str) -> Option<&DaySummary> {
+        self.days.iter().find(|day| day.date == date)
+    }
+}
+
+/// Cheapest round-trip fare for one departure/return date pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceCell {
+    pub departure_date: String,
+    pub return_date: String,
+    pub nights: i64,
+    pub price: Option<FlightPrice>,
+    pub current_price: String,          // "low", "typical", "high" as reported for this pair
+    pub cheapest_flight: Option<Flight>,
+    pub error: Option<String>,          // Set when the search for this pair failed
+}
+
+impl PriceCell {
+    /// Amount of the cheapest fare, if one was found
+    pub fn amount(&self) -> Option<i32> {
+        self.price.as_ref().map(|p| p.amount)
+    }
+}
+
+/// Relative price level of a cell within its matrix, used for colour coding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceTier {
+    Cheap,      // Bottom third of the price range
+    Moderate,   // Middle third
+    Expensive,  // Top third
+}
+
+/// Departure date × return date matrix of cheapest round-trip prices
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceMatrix {
+    pub origin: String,
+    pub destination: String,
+    pub departure_dates: Vec<String>,   // Row labels, ascending
+    pub return_dates: Vec<String>,      // Column labels, ascending
+    pub cells: Vec<PriceCell>,          // Only the combinations allowed by the trip length
+}
+
+impl PriceMatrix {
+    /// Build a matrix from searched cells, deriving the sorted row and column labels
+    pub fn new(origin: &str, destination: &str, cells: Vec<PriceCell>) -> Self {
+        let mut departure_dates: Vec<String> = cells.iter().map(|c| c.departure_date.clone()).collect();
+        departure_dates.sort();
+        departure_dates.dedup();
+
+        let mut return_dates: Vec<String> = cells.iter().map(|c| c.return_date.clone()).collect();
+        return_dates.sort();
+        return_dates.dedup();
+
+        Self {
+            origin: origin.to_string(),
+            destination: destination.to_string(),
+            departure_dates,
+            return_dates,
+            cells,
+        }
+    }
+
+    /// Cell for a departure/return pair (None if that combination was not searched)
+    pub fn get(&self, departure_date: &str, return_date: &str) -> Option<&PriceCell> {
+        self.cells.iter()
+            .find(|c| c.departure_date == departure_date && c.return_date == return_date)
+    }
+
+    /// Cheapest fare amount for a departure/return pair
+    pub fn price(&self, departure_date: &str, return_date: &str) -> Option<i32> {
+        self.get(departure_date, return_date).and_then(PriceCell::amount)
+    }
+
+    /// Overall cheapest combination
+    pub fn cheapest(&self) -> Option<&PriceCell> {
+        Self::cheapest_of(self.cells.iter())
+    }
+
+    /// Cheapest return option for a given departure date
+    pub fn cheapest_for_departure(&self, departure_date: &str) -> Option<&PriceCell> {
+        Self::cheapest_of(self.cells.iter().filter(|c| c.departure_date == departure_date))
+    }
+
+    /// Cheapest departure option for a given return date
+    pub fn cheapest_for_return(&self, return_date: &str) -> Option<&PriceCell> {
+        Self::cheapest_of(self.cells.iter().filter(|c| c.return_date == return_date))
+    }
+
+    /// Cheapest combination for a given trip length
+    pub fn cheapest_for_nights(&self, nights: i64) -> Option<&PriceCell> {
+        Self::cheapest_of(self.cells.iter().filter(|c| c.nights == nights))
+    }
+
+    /// Lowest and highest known prices in the matrix
+    pub fn price_range(&self) -> Option<(i32, i32)> {
+        let amounts = self.cells.iter().filter_map(PriceCell::amount);
+        let (min, max) = amounts.fold((i32::MAX, i32::MIN), |(min, max), a| (min.min(a), max.max(a)));
+        if min > max { None } else { Some((min, max)) }
+    }
+
+    /// Classify an amount relative to the other prices in this matrix
+    pub fn tier(&self, amount: i32) -> Option<PriceTier> {
+        let (min, max) = self.price_range()?;
+        let span = (max - min) as f64;
+        if span == 0.0 {
+            return Some(PriceTier::Cheap);
+        }
+
+        let position = (amount - min) as f64 / span;
+        Some(if position < 1.0 / 3.0 {
+            PriceTier::Cheap
+        } else if position < 2.0 / 3.0 {
+            PriceTier::Moderate
+        } else {
+            PriceTier::Expensive
+        })
+    }
+
+    fn cheapest_of<'a>(cells: impl Iterator<Item = &'a PriceCell>) -> Option<&'a PriceCell> {
+        cells.filter(|c| c.amount().is_some())
+            .min_by_key(|c| c.amount().unwrap_or(i32::MAX))
+    }
+}
+
+impl FlightClient {
+    /// Search one-way flights for every date between `start_date` and `end_date` (inclusive).
+    ///
+    /// A failed search for one date is recorded in that day's summary rather than
+    /// aborting the whole range.
+    pub async fn search_date_range(
+        &self,
+        origin: &str,
+        destination: &str,
+        start_date: &str,
+        end_date: &str,
+        options: &SearchOptions,
+    ) -> Result<DateRangeResult, FlightError> {
+        let dates = dates_between(start_date, end_date)?;
+        let mut days = Vec::with_capacity(dates.len());
+
+        for (i, date) in dates.iter().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(options.request_delay).await;
+            }
+
+            let date = format_date(*date);
+            let request = options.one_way_request(origin, destination, &date);
+            let summary = match self.get_flights(request).await {
+                Ok(result) => DaySummary::from_result(&date, &result),
+                Err(e) => {
+                    eprintln!("⚠️  Warning: Search for {} failed: {}", date, e);
+                    DaySummary::from_error(&date, &e)
+                }
+            };
+            days.push(summary);
+        }
+
+        Ok(DateRangeResult::new(origin, destination, days))
+    }
+
+    /// Build a round-trip price matrix for departures between `departure_start` and
+    /// `departure_end` (inclusive) and stays of `nights` nights.
+    ///
+    /// Every allowed departure/return combination is a separate round-trip search,
+    /// so the number of combinations is capped at `MAX_PRICE_MATRIX_CELLS`.
+    pub async fn search_price_matrix(
+        &self,
+        origin: &str,
+        destination: &str,
+        departure_start: &str,
+        departure_end: &str,
+        nights: RangeInclusive<u32>,
+        options: &SearchOptions,
+    ) -> Result<PriceMatrix, FlightError> {
+        let pairs = date_pairs(departure_start, departure_end, nights)?;
+        let mut cells = Vec::with_capacity(pairs.len());
+
+        for (i, (departure, return_date)) in pairs.iter().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(options.request_delay).await;
+            }
+
+            let departure_date = format_date(*departure);
+            let return_date_str = format_date(*return_date);
+            let nights = (*return_date - *departure).num_days();
+            let request = options.round_trip_request(origin, destination, &departure_date, &return_date_str);
+
+            let cell = match self.get_flights(request).await {
+                Ok(result) => {
+                    let cheapest = cheapest_flight(&result.flights).cloned();
+                    PriceCell {
+                        departure_date,
+                        return_date: return_date_str,
+                        nights,
+                        price: cheapest.as_ref().map(|f| f.price.clone()),
+                        current_price: result.current_price,
+                        cheapest_flight: cheapest,
+                        error: None,
+                    }
+                }
+                Err(e) => {
+                    eprintln!("⚠️  Warning: Search for {} → {} failed: {}", departure_date, return_date_str, e);
+                    PriceCell {
+                        departure_date,
+                        return_date: return_date_str,
+                        nights,
+                        price: None,
+                        current_price: "unknown".to_string(),
+                        cheapest_flight: None,
+                        error: Some(e.to_string()),
+                    }
+                }
+            };
+            cells.push(cell);
+        }
+
+        Ok(PriceMatrix::new(origin, destination, cells))
+    }
+}
+
+/// Every (departure, return) pair with a departure in the given range and a stay
+/// of `nights` nights
+pub fn date_pairs(
+    departure_start: &str,
+    departure_end: &str,
+    nights: RangeInclusive<u32>,
+) -> Result<Vec<(NaiveDate, NaiveDate)>, FlightError> {
+    if nights.is_empty() {
+        return Err(FlightError::DateParseError(
+            format!("Invalid trip length: {}-{} nights", nights.start(), nights.end())
+        ));
+    }
+
+    let mut pairs = Vec::new();
+    for departure in dates_between(departure_start, departure_end)? {
+        for night_count in nights.clone() {
+            let return_date = departure + chrono::Duration::days(i64::from(night_count));
+            pairs.push((departure, return_date));
+        }
+    }
+
+    if pairs.len() > MAX_PRICE_MATRIX_CELLS {
+        return Err(FlightError::DateParseError(format!(
+            "Price matrix would need {} searches, the limit is {}",
+            pairs.len(),
+            MAX_PRICE_MATRIX_CELLS
+        )));
+    }
+
+    Ok(pairs)
+}
+
+/// Parse a YYYY-MM-DD date
+pub fn parse_date(date: &str) -> Result<NaiveDate, FlightError> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| FlightError::DateParseError(format!("Date must be in YYYY-MM-DD format, got {}", date)))
+}
+
+/// Format a date as YYYY-MM-DD, the format used by `FlightData::date`
+pub fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Every date from `start_date` to `end_date` inclusive
+pub fn dates_between(start_date: &str, end_date: &str) -> Result<Vec<NaiveDate>, FlightError> {
+    let start = parse_date(start_date)?;
+    let end = parse_date(end_date)?;
+
+    if end < start {
+        return Err(FlightError::DateParseError(
+            format!("End date {} is before start date {}", end_date, start_date)
+        ));
+    }
+
+    let dates: Vec<NaiveDate> = start.iter_days().take_while(|d| *d <= end).take(MAX_DATE_RANGE_DAYS + 1).collect();
+    if dates.len() > MAX_DATE_RANGE_DAYS {
+        return Err(FlightError::DateParseError(
+            format!("Date range is limited to {} days", MAX_DATE_RANGE_DAYS)
+        ));
+    }
+
+    Ok(dates)
+}
+
+/// Cheapest flight with a known (non-zero) price; ties keep Google's order
+pub(crate) fn cheapest_flight(flights: &[Flight]) -> Option<&Flight> {
+    flights.iter()
+        .filter(|f| f.price.amount > 0)
+        .min_by_key(|f| f.price.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_flight(name: &str, price: i32, is_best: bool) -> Flight {
+        let flight = Flight::test(name, price).schedule("8:00 AM", "4:30 PM", "5 hr 30 min").route("LAX", "JFK");
+        if is_best { flight.best() } else { flight }
+    }
+
+    #[test]
+    fn test_dates_between() {
+        let dates = dates_between("2025-09-08", "2025-09-14").unwrap();
+        assert_eq!(dates.len(), 7);
+        assert_eq!(format_date(dates[0]), "2025-09-08");
+        assert_eq!(format_date(dates[6]), "2025-09-14");
+
+        // Crosses a month boundary
+        let dates = dates_between("2025-08-30", "2025-09-02").unwrap();
+        assert_eq!(dates.iter().map(|d| format_date(*d)).collect::<Vec<_>>(),
+            vec!["2025-08-30", "2025-08-31", "2025-09-01", "2025-09-02"]);
+
+        assert_eq!(dates_between("2025-09-08", "2025-09-08").unwrap().len(), 1);
+        assert!(dates_between("2025-09-14", "2025-09-08").is_err());
+        assert!(dates_between("2025-09-08", "not-a-date").is_err());
+        assert!(dates_between("2025-01-01", "2025-12-31").is_err());
+    }
+
+    fn make_cell(departure: &str, return_date: &str, nights: i64, price: Option<i32>) -> PriceCell {
+        PriceCell {
+            departure_date: departure.to_string(),
+            return_date: return_date.to_string(),
+            nights,
+            price: price.map(|amount| FlightPrice { amount, currency: "$".to_string() }),
+            current_price: "typical".to_string(),
+            cheapest_flight: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_date_pairs() {
+        let pairs = date_pairs("2025-09-01", "2025-09-03", 5..=7).unwrap();
+        assert_eq!(pairs.len(), 9);
+        assert_eq!(format_date(pairs[0].0), "2025-09-01");
+        assert_eq!(format_date(pairs[0].1), "2025-09-06");
+        assert_eq!(format_date(pairs[8].0), "2025-09-03");
+        assert_eq!(format_date(pairs[8].1), "2025-09-10");
+
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 9..=5;
+        assert!(date_pairs("2025-09-01", "2025-09-03", empty).is_err());
+        assert!(date_pairs("2025-09-01", "2025-10-30", 1..=14).is_err());
+    }
+
+    #[test]
+    fn test_price_matrix_lookups() {
+        let matrix = PriceMatrix::new("SFO", "HND", vec![
+            make_cell("2025-09-02", "2025-09-07", 5, Some(900)),
+            make_cell("2025-09-01", "2025-09-06", 5, Some(1100)),
+            make_cell("2025-09-01", "2025-09-07", 6, Some(750)),
+            make_cell("2025-09-02", "2025-09-08", 6, None),
+        ]);
+
+        assert_eq!(matrix.departure_dates, vec!["2025-09-01", "2025-09-02"]);
+        assert_eq!(matrix.return_dates, vec!["2025-09-06", "2025-09-07", "2025-09-08"]);
+
+        assert_eq!(matrix.price("2025-09-01", "2025-09-07"), Some(750));
+        assert_eq!(matrix.price("2025-09-02", "2025-09-08"), None);
+        assert!(matrix.get("2025-09-02", "2025-09-06").is_none());
+
+        let cheapest = matrix.cheapest().unwrap();
+        assert_eq!((cheapest.departure_date.as_str(), cheapest.return_date.as_str()), ("2025-09-01", "2025-09-07"));
+        assert_eq!(matrix.cheapest_for_departure("2025-09-02").unwrap().amount(), Some(900));
+        assert_eq!(matrix.cheapest_for_return("2025-09-06").unwrap().amount(), Some(1100));
+        assert_eq!(matrix.cheapest_for_nights(5).unwrap().amount(), Some(900));
+        assert!(matrix.cheapest_for_nights(9).is_none());
+
+        assert_eq!(matrix.price_range(), Some((750, 1100)));
+        assert_eq!(matrix.tier(750), Some(PriceTier::Cheap));
+        assert_eq!(matrix.tier(900), Some(PriceTier::Moderate));
+        assert_eq!(matrix.tier(1100), Some(PriceTier::Expensive));
+    }
+
+    #[test]
+    fn test_day_summary_from_result() {
+        let result = FlightResult {
+            current_price: "low".to_string(),
+            flights: vec![
+                make_flight("American", 320, true),
+                make_flight("Spirit", 0, false),
+                make_flight("Delta", 180, false),
+            ],
+        };
+
+        let summary = DaySummary::from_result("2025-09-10", &result);
+        assert_eq!(summary.flight_count, 3);
+        assert_eq!(summary.current_price, "low");
+        assert_eq!(summary.cheapest_price.unwrap().amount, 180);
+        assert_eq!(summary.cheapest_flight.unwrap().name, "Delta");
+        assert_eq!(summary.best_flight.unwrap().name, "American");
+        assert!(summary.error.is_none());
+    }
+
+    #[test]
+    fn test_date_range_result_cheapest_day() {
+        let day = |date: &str, price: i32| DaySummary::from_result(date, &FlightResult {
+            current_price: "typical".to_string(),
+            flights: vec![make_flight("Delta", price, true)],
+        });
+
+        let failed = DaySummary::from_error("2025-09-09", &FlightError::ParseError("No flights found in response".to_string()));
+        let result = DateRangeResult::new("LAX", "JFK", vec![
+            day("2025-09-08", 240),
+            failed,
+            day("2025-09-10", 199),
+            day("2025-09-11", 199),
+        ]);
+
+        assert_eq!(result.cheapest_date.as_deref(), Some("2025-09-10"));
+        assert_eq!(result.cheapest_day().unwrap().cheapest_price.as_ref().unwrap().amount, 199);
+        assert!(result.day("2025-09-09").unwrap().error.is_some());
+        assert!(result.day("2025-09-12").is_none());
+    }
+}
@@ -0,0 +1,379 @@
+//! "Explore anywhere": search one origin against many candidate destinations and
+//! rank the destinations by their cheapest fare.
+
+use crate::airports::{all_airports, Region};
+use crate::dates::{cheapest_flight, dates_between, format_date, parse_date};
+use crate::{Flight, FlightClient, FlightError, FlightPrice, SearchOptions, WikidataClient};
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of searches a single explore request may make
+pub const MAX_EXPLORE_SEARCHES: usize = 300;
+
+/// Which destinations to consider
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExploreDestinations {
+    /// Explicit airport codes ("LIS") or city names ("Lisbon")
+    List(Vec<String>),
+    /// Every city in the bundled city cache
+    CachedCities,
+    /// Every city in the bundled airport dataset within a region
+    Region(Region),
+}
+
+/// Parameters for an explore search
+#[derive(Debug, Clone)]
+pub struct ExploreRequest {
+    /// Origin airport code or city name
+    pub origin: String,
+    pub destinations: ExploreDestinations,
+    pub start_date: String,
+    pub end_date: String,
+    /// Fly out on `start_date` and back on `end_date` (one search per destination).
+    /// Otherwise search one-way on every date from `start_date` to `end_date`.
+    pub round_trip: bool,
+    pub options: SearchOptions,
+}
+
+/// A destination to search: a display name and the airport code or Freebase ID
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExploreCandidate {
+    pub name: String,
+    pub location: String,
+}
+
+/// Cheapest fare found for one destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DestinationSummary {
+    pub destination: String,
+    pub location: String,
+    pub cheapest_price: Option<FlightPrice>,
+    pub cheapest_date: Option<String>,
+    pub best_flight: Option<Flight>,
+    pub error: Option<String>,
+}
+
+impl DestinationSummary {
+    fn amount(&self) -> Option<i32> {
+        self.cheapest_price.as_ref().map(|p| p.amount).filter(|amount| *amount > 0)
+    }
+}
+
+/// Destinations ranked by cheapest fare (destinations without a price last)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExploreResult {
+    pub origin: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub round_trip: bool,
+    pub destinations: Vec<DestinationSummary>,
+}
+
+impl ExploreResult {
+    /// Rank `destinations` cheapest first; ties and unpriced entries keep their order
+    pub fn new(request: &ExploreRequest, mut destinations: Vec<DestinationSummary>) -> Self {
+        destinations.sort_by_key(|d| d.amount().map_or((1, 0), |amount| (0, amount)));
+        Self {
+            origin: request.origin.clone(),
+            start_date: request.start_date.clone(),
+            end_date: request.end_date.clone(),
+            round_trip: request.round_trip,
+            destinations,
+        }
+    }
+
+    /// The cheapest destination, if any search returned a price
+    pub fn cheapest(&self) -> Option<&DestinationSummary> {
+        self.destinations.first().filter(|d| d.amount().is_some())
+    }
+}
+
+/// Whether `value` looks like an IATA airport code rather than a city name
+fn is_airport_code(value: &str) -> bool {
+    value.len() == 3 && value.chars().all(|c| c.is_ascii_uppercase())
+}
+
+/// Resolve an airport code or city name to something Google Flights accepts
+pub(crate) async fn resolve_location(wikidata: &WikidataClient, value: &str) -> Result<String, FlightError> {
+    let value = value.trim();
+    if is_airport_code(value) {
+        return Ok(value.to_string());
+    }
+    crate::resolve_city(wikidata, value).await
+}
+
+/// Cities in the bundled airport dataset that belong to `region`, using the cached
+/// Freebase ID when available (covering every airport in the city) and the city's
+/// first airport code otherwise.
+pub fn region_candidates(region: Region, wikidata: &WikidataClient) -> Vec<(String, Option<String>)> {
+    let mut cities: Vec<(String, Option<String>)> = Vec::new();
+    for airport in all_airports().iter().filter(|a| a.region() == Some(region)) {
+        if cities.iter().any(|(city, _)| *city == airport.city) {
+            continue;
+        }
+        let code = if wikidata.is_city_cached(&airport.city) { None } else { Some(airport.iata.clone()) };
+        cities.push((airport.city.clone(), code));
+    }
+    cities
+}
+
+/// Departure dates to search for each destination
+fn explore_dates(request: &ExploreRequest) -> Result<Vec<String>, FlightError> {
+    if !request.round_trip {
+        return Ok(dates_between(&request.start_date, &request.end_date)?.into_iter().map(format_date).collect());
+    }
+
+    let departure = parse_date(&request.start_date)?;
+    let return_date = parse_date(&request.end_date)?;
+    if return_date < departure {
+        return Err(FlightError::DateParseError(format!(
+            "Return date {} is before departure date {}",
+            request.end_date, request.start_date
+        )));
+    }
+    Ok(vec![format_date(departure)])
+}
+
+/// Names of the candidate destinations, with an airport code for those that
+/// need no resolving. Known before anything is resolved, so it bounds the
+/// number of searches.
+pub fn candidate_names(wikidata: &WikidataClient, destinations: &ExploreDestinations) -> Vec<(String, Option<String>)> {
+    match destinations {
+        ExploreDestinations::List(names) => names.iter().map(|n| (n.trim().to_string(), None)).collect(),
+        ExploreDestinations::CachedCities => {
+            let (_, mut cities) = wikidata.get_cache_stats();
+            cities.sort();
+            cities.into_iter().map(|city| (city, None)).collect()
+        }
+        ExploreDestinations::Region(region) => region_candidates(*region, wikidata),
+    }
+}
+
+/// Fail if searching `candidates` destinations on each of `dates` dates would exceed [`MAX_EXPLORE_SEARCHES`]
+fn check_search_count(candidates: usize, dates: usize) -> Result<(), FlightError> {
+    let total = candidates * dates;
+    if total > MAX_EXPLORE_SEARCHES {
+        return Err(FlightError::ParseError(format!(
+            "Explore would make {} searches (maximum is {}); narrow the destinations or dates",
+            total, MAX_EXPLORE_SEARCHES
+        )));
+    }
+    Ok(())
+}
+
+/// Resolve the candidate destinations for an explore request.
+///
+/// Candidates that cannot be resolved are skipped with a warning, and
+/// candidates resolving to the same location as the origin or an earlier
+/// candidate are dropped.
+pub async fn explore_candidates(
+    wikidata: &WikidataClient,
+    origin_location: &str,
+    destinations: &ExploreDestinations,
+) -> Result<Vec<ExploreCandidate>, FlightError> {
+    resolve_candidates(wikidata, origin_location, candidate_names(wikidata, destinations)).await
+}
+
+async fn resolve_candidates(
+    wikidata: &WikidataClient,
+    origin_location: &str,
+    names: Vec<(String, Option<String>)>,
+) -> Result<Vec<ExploreCandidate>, FlightError> {
+    let mut candidates: Vec<ExploreCandidate> = Vec::new();
+    for (name, code) in names {
+        let location = match code {
+            Some(code) => code,
+            None => match resolve_location(wikidata, &name).await {
+                Ok(location) => location,
+                Err(e) => {
+                    eprintln!("⚠️  Warning: Skipping destination {}: {}", name, e);
+                    continue;
+                }
+            },
+        };
+        if location != origin_location && !candidates.iter().any(|c| c.location == location) {
+            candidates.push(ExploreCandidate { name, location });
+        }
+    }
+
+    if candidates.is_empty() {
+        return Err(FlightError::NoDestinations("none of the candidates could be resolved".to_string()));
+    }
+    Ok(candidates)
+}
+
+impl FlightClient {
+    /// Search every candidate destination from `request.origin` and rank them by
+    /// cheapest fare, with the best flight for each.
+    ///
+    /// A failed search is recorded in that destination's summary rather than
+    /// aborting the whole request.
+    pub async fn explore(&self, request: &ExploreRequest) -> Result<ExploreResult, FlightError> {
+        let dates = explore_dates(request)?;
+
+        let wikidata = WikidataClient::new()?;
+        // Check the cap before resolving anything; resolving can only drop candidates
+        let names = candidate_names(&wikidata, &request.destinations);
+        if names.is_empty() {
+            return Err(FlightError::NoDestinations("the destination list is empty".to_string()));
+        }
+        check_search_count(names.len(), dates.len())?;
+
+        let origin = resolve_location(&wikidata, &request.origin).await?;
+        let candidates = resolve_candidates(&wikidata, &origin, names).await?;
+
+        let mut summaries = Vec::with_capacity(candidates.len());
+        let mut searches = 0;
+        for candidate in candidates {
+            let mut summary = DestinationSummary {
+                destination: candidate.name,
+                location: candidate.location,
+                cheapest_price: None,
+                cheapest_date: None,
+                best_flight: None,
+                error: None,
+            };
+
+            for date in &dates {
+                if searches > 0 {
+                    tokio::time::sleep(request.options.request_delay).await;
+                }
+                searches += 1;
+
+                let search = if request.round_trip {
+                    request.options.round_trip_request(&origin, &summary.location, date, &request.end_date)
+                } else {
+                    request.options.one_way_request(&origin, &summary.location, date)
+                };
+
+                match self.get_flights(search).await {
+                    Ok(result) => {
+                        let Some(flight) = cheapest_flight(&result.flights) else {
+                            continue;
+                        };
+                        if summary.amount().is_none_or(|best| flight.price.amount < best) {
+                            summary.cheapest_price = Some(flight.price.clone());
+                            summary.cheapest_date = Some(date.clone());
+                            summary.best_flight = Some(flight.clone());
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("⚠️  Warning: Search to {} on {} failed: {}", summary.destination, date, e);
+                        summary.error.get_or_insert(e.to_string());
+                    }
+                }
+            }
+
+            if summary.cheapest_price.is_some() {
+                summary.error = None;
+            }
+            summaries.push(summary);
+        }
+
+        Ok(ExploreResult::new(request, summaries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(destination: &str, amount: Option<i32>) -> DestinationSummary {
+        DestinationSummary {
+            destination: destination.to_string(),
+            location: destination.to_string(),
+            cheapest_price: amount.map(|amount| FlightPrice { amount, currency: "$".to_string() }),
+            cheapest_date: amount.map(|_| "2025-09-01".to_string()),
+            best_flight: None,
+            error: None,
+        }
+    }
+
+    fn request() -> ExploreRequest {
+        ExploreRequest {
+            origin: "SEA".to_string(),
+            destinations: ExploreDestinations::CachedCities,
+            start_date: "2025-09-01".to_string(),
+            end_date: "2025-09-05".to_string(),
+            round_trip: true,
+            options: SearchOptions::default(),
+        }
+    }
+
+    #[test]
+    fn test_explore_result_ranking() {
+        let result = ExploreResult::new(&request(), vec![
+            summary("Denver", None),
+            summary("Lisbon", Some(640)),
+            summary("Austin", Some(210)),
+            summary("Zero", Some(0)),
+            summary("Boston", Some(210)),
+        ]);
+        let order: Vec<&str> = result.destinations.iter().map(|d| d.destination.as_str()).collect();
+        assert_eq!(order, vec!["Austin", "Boston", "Lisbon", "Denver", "Zero"]);
+        assert_eq!(result.cheapest().unwrap().destination, "Austin");
+
+        let empty = ExploreResult::new(&request(), vec![summary("Denver", None)]);
+        assert!(empty.cheapest().is_none());
+    }
+
+    #[test]
+    fn test_explore_dates() {
+        assert_eq!(explore_dates(&request()).unwrap(), vec!["2025-09-01"]);
+
+        let one_way = ExploreRequest { round_trip: false, ..request() };
+        assert_eq!(explore_dates(&one_way).unwrap().len(), 5);
+
+        let backwards = ExploreRequest { end_date: "2025-08-30".to_string(), ..request() };
+        assert!(explore_dates(&backwards).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_search_cap_checked_before_resolving() {
+        // 61 destinations over 5 one-way dates is 305 searches
+        let codes: Vec<String> = (0..61u8).map(|i| format!("A{}{}", (b'A' + i / 26) as char, (b'A' + i % 26) as char)).collect();
+        let request = ExploreRequest {
+            // Resolving this origin would need Wikidata, so only an early cap check can fail first
+            origin: "Nowhere Unresolvable".to_string(),
+            destinations: ExploreDestinations::List(codes),
+            round_trip: false,
+            ..request()
+        };
+        let error = FlightClient::new().await.unwrap().explore(&request).await.unwrap_err().to_string();
+        assert!(error.contains("305 searches") && error.contains("maximum is 300"), "{}", error);
+
+        assert!(check_search_count(60, 5).is_ok());
+        let wikidata = WikidataClient::builder().without_store().build().unwrap();
+        let list = ExploreDestinations::List(vec!["LIS".to_string(), "Porto".to_string()]);
+        assert_eq!(candidate_names(&wikidata, &list).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_destinations_error() {
+        let wikidata = WikidataClient::builder().without_store().build().unwrap();
+        let error = resolve_candidates(&wikidata, "SEA", vec![("SEA".to_string(), Some("SEA".to_string()))]).await.unwrap_err();
+        assert!(matches!(error, FlightError::NoDestinations(_)));
+        assert_eq!(error.to_string(), "No destinations to explore: none of the candidates could be resolved");
+    }
+
+    #[test]
+    fn test_is_airport_code() {
+        assert!(is_airport_code("LIS"));
+        assert!(!is_airport_code("Rio"));
+        assert!(!is_airport_code("Lisbon"));
+    }
+
+    #[test]
+    fn test_region_candidates() {
+        let wikidata = WikidataClient::builder().without_store().build().unwrap();
+        let candidates = region_candidates(Region::Oceania, &wikidata);
+        let cities: Vec<&str> = candidates.iter().map(|(city, _)| city.as_str()).collect();
+        assert!(cities.contains(&"Sydney"));
+        assert!(cities.contains(&"Auckland"));
+        assert!(!cities.contains(&"Tokyo"));
+        assert_eq!(cities.iter().filter(|c| **c == "Melbourne").count(), 1);
+
+        // Uncached cities fall back to an airport code
+        let nadi = candidates.iter().find(|(city, _)| city == "Nadi").unwrap();
+        assert_eq!(nadi.1.as_deref(), Some("NAN"));
+    }
+}
@@ -0,0 +1,415 @@
+//! Flat, columnar export of search results as CSV or newline-delimited JSON.
+//!
+//! Every row repeats the search metadata, so files from many searches can be
+//! concatenated and loaded into a spreadsheet, dataframe or Parquet table
+//! as-is. Rows are either one per flight or one per leg (flight segment).
+
+use crate::dates::parse_date;
+use crate::timezones::FlightTimes;
+use crate::{Flight, FlightError, FlightResult};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// File format of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+}
+
+impl FromStr for ExportFormat {
+    type Err = FlightError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "ndjson" | "jsonl" => Ok(ExportFormat::Ndjson),
+            _ => Err(FlightError::ParseError(format!("Invalid export format: {}", s))),
+        }
+    }
+}
+
+/// Whether an export has a row per flight or per leg
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportRows {
+    #[default]
+    Flights,
+    Legs,
+}
+
+impl FromStr for ExportRows {
+    type Err = FlightError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "flight" | "flights" => Ok(ExportRows::Flights),
+            "leg" | "legs" => Ok(ExportRows::Legs),
+            _ => Err(FlightError::ParseError(format!("Invalid export rows: {} (use flights or legs)", s))),
+        }
+    }
+}
+
+/// The search that produced a result, repeated on every exported row
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchMetadata {
+    pub origin: String,
+    pub destination: String,
+    /// Departure date searched (YYYY-MM-DD)
+    pub date: String,
+    pub return_date: Option<String>,
+    pub trip_type: String,
+    pub seat_class: String,
+    pub adults: i32,
+    pub children: i32,
+    /// When the search ran (RFC 3339, UTC)
+    pub searched_at: String,
+}
+
+impl SearchMetadata {
+    /// A one-way economy search for one adult, run now
+    pub fn new(origin: &str, destination: &str, date: &str) -> Self {
+        Self {
+            origin: origin.to_string(),
+            destination: destination.to_string(),
+            date: date.to_string(),
+            return_date: None,
+            trip_type: "one-way".to_string(),
+            seat_class: "economy".to_string(),
+            adults: 1,
+            children: 0,
+            searched_at: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        }
+    }
+
+    fn search_date(&self) -> Option<NaiveDate> {
+        parse_date(&self.date).ok()
+    }
+}
+
+/// One flight of a result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlightRow {
+    pub searched_at: String,
+    pub search_origin: String,
+    pub search_destination: String,
+    pub search_date: String,
+    pub return_date: Option<String>,
+    pub trip_type: String,
+    pub seat_class: String,
+    pub adults: i32,
+    pub children: i32,
+    pub current_price: String,
+    /// Position in the results, from 1
+    pub rank: usize,
+    pub is_best: bool,
+    pub airline: String,
+    pub origin: Option<String>,
+    pub destination: Option<String>,
+    /// Local wall-clock times at each airport (ISO 8601, no offset)
+    pub departure_local: Option<String>,
+    pub arrival_local: Option<String>,
+    /// The same instants in UTC, when the airports' time zones are known
+    pub departure_utc: Option<String>,
+    pub arrival_utc: Option<String>,
+    pub duration_minutes: Option<i32>,
+    pub stops: i32,
+    /// Layover airports, "|"-separated
+    pub layovers: String,
+    pub layover_minutes: Option<i32>,
+    /// Flight numbers of each leg, "|"-separated ("UA100|UA200")
+    pub flight_numbers: String,
+    pub price: i32,
+    pub currency: String,
+}
+
+/// One leg (flight segment) of a flight
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegRow {
+    pub searched_at: String,
+    pub search_origin: String,
+    pub search_destination: String,
+    pub search_date: String,
+    pub return_date: Option<String>,
+    pub trip_type: String,
+    pub seat_class: String,
+    pub adults: i32,
+    pub children: i32,
+    pub current_price: String,
+    /// Position of the flight in the results, from 1
+    pub rank: usize,
+    /// Position of the leg within the flight, from 1
+    pub leg: usize,
+    pub legs: usize,
+    pub airline: String,
+    pub airline_code: Option<String>,
+    pub flight_number: Option<String>,
+    pub from_airport: Option<String>,
+    pub to_airport: Option<String>,
+    /// Local wall-clock times; intermediate legs are estimated from layover lengths
+    pub departure_local: Option<String>,
+    pub arrival_local: Option<String>,
+    pub departure_utc: Option<String>,
+    pub arrival_utc: Option<String>,
+    /// Price and duration of the whole flight the leg belongs to
+    pub flight_price: i32,
+    pub currency: String,
+    pub flight_duration_minutes: Option<i32>,
+}
+
+fn local(time: NaiveDateTime) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+fn utc<Tz: chrono::TimeZone>(time: &DateTime<Tz>) -> String {
+    time.with_timezone(&Utc).to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// Aware times for `flight`, falling back to the searched airports
+fn flight_times(flight: &Flight, metadata: &SearchMetadata, search_date: Option<NaiveDate>) -> Option<FlightTimes> {
+    let date = search_date?;
+    flight.times(date).or_else(|| flight.times_between(&metadata.origin, &metadata.destination, date))
+}
+
+/// One row per flight in `result`
+pub fn flight_rows(result: &FlightResult, metadata: &SearchMetadata) -> Vec<FlightRow> {
+    let search_date = metadata.search_date();
+    result.flights.iter()
+        .enumerate()
+        .map(|(i, flight)| {
+            let times = flight_times(flight, metadata, search_date);
+            let layover_minutes = flight.layover_minutes();
+            FlightRow {
+                searched_at: metadata.searched_at.clone(),
+                search_origin: metadata.origin.clone(),
+                search_destination: metadata.destination.clone(),
+                search_date: metadata.date.clone(),
+                return_date: metadata.return_date.clone(),
+                trip_type: metadata.trip_type.clone(),
+                seat_class: metadata.seat_class.clone(),
+                adults: metadata.adults,
+                children: metadata.children,
+                current_price: result.current_price.clone(),
+                rank: i + 1,
+                is_best: flight.is_best,
+                airline: flight.name.clone(),
+                origin: flight.origin_airport.clone(),
+                destination: flight.destination_airport.clone(),
+                departure_local: search_date.and_then(|date| flight.departure_datetime(date)).map(local),
+                arrival_local: search_date.and_then(|date| flight.arrival_datetime(date)).map(local),
+                departure_utc: times.as_ref().map(|t| utc(&t.departure)),
+                arrival_utc: times.as_ref().map(|t| utc(&t.arrival)),
+                duration_minutes: flight.duration_minutes(),
+                stops: flight.stops,
+                layovers: flight.layovers.as_deref().unwrap_or_default().join("|"),
+                layover_minutes: (!layover_minutes.is_empty()).then(|| layover_minutes.iter().sum()),
+                flight_numbers: flight.flight_legs.as_deref().unwrap_or_default()
+                    .iter()
+                    .map(|leg| format!("{}{}", leg.airline_code, leg.flight_number))
+                    .collect::<Vec<_>>()
+                    .join("|"),
+                price: flight.price.amount,
+                currency: flight.price.currency.clone(),
+            }
+        })
+        .collect()
+}
+
+/// One row per leg of every flight in `result`.
+///
+/// Legs are split at the reported layover airports, or by the reported flight
+/// numbers when the airports are missing.
+pub fn leg_rows(result: &FlightResult, metadata: &SearchMetadata) -> Vec<LegRow> {
+    let search_date = metadata.search_date();
+    let mut rows = Vec::new();
+    for (i, flight) in result.flights.iter().enumerate() {
+        let times = flight_times(flight, metadata, search_date);
+        let flight_legs = flight.flight_legs.as_deref().unwrap_or_default();
+        let airports: Option<Vec<String>> = flight.layovers.as_ref().map(|layovers| {
+            std::iter::once(flight.origin_airport.clone().unwrap_or_else(|| metadata.origin.clone()))
+                .chain(layovers.iter().cloned())
+                .chain(std::iter::once(flight.destination_airport.clone().unwrap_or_else(|| metadata.destination.clone())))
+                .collect()
+        });
+        let count = match &airports {
+            Some(airports) => airports.len() - 1,
+            None if flight.stops == 0 => 1,
+            None => flight_legs.len().max(1),
+        };
+        let flight_legs = if flight_legs.len() == count { flight_legs } else { &[] };
+        // Layover times line up with the legs only when every layover was placed
+        let times = times.filter(|t| t.layovers.len() + 1 == count);
+
+        for leg in 0..count {
+            let (from_airport, to_airport) = match &airports {
+                Some(airports) => (Some(airports[leg].clone()), Some(airports[leg + 1].clone())),
+                None => (
+                    (leg == 0).then(|| flight.origin_airport.clone().unwrap_or_else(|| metadata.origin.clone())),
+                    (leg + 1 == count).then(|| flight.destination_airport.clone().unwrap_or_else(|| metadata.destination.clone())),
+                ),
+            };
+            let departure = times.as_ref().map(|t| if leg == 0 { t.departure } else { t.layovers[leg - 1].departure });
+            let arrival = times.as_ref().map(|t| if leg + 1 == count { t.arrival } else { t.layovers[leg].arrival });
+            // Without aware times, the flight's own local times still bound the first and last legs
+            let departure_local = departure.map(|d| local(d.naive_local())).or_else(|| {
+                (leg == 0).then(|| search_date.and_then(|date| flight.departure_datetime(date)).map(local)).flatten()
+            });
+            let arrival_local = arrival.map(|a| local(a.naive_local())).or_else(|| {
+                (leg + 1 == count).then(|| search_date.and_then(|date| flight.arrival_datetime(date)).map(local)).flatten()
+            });
+
+            rows.push(LegRow {
+                searched_at: metadata.searched_at.clone(),
+                search_origin: metadata.origin.clone(),
+                search_destination: metadata.destination.clone(),
+                search_date: metadata.date.clone(),
+                return_date: metadata.return_date.clone(),
+                trip_type: metadata.trip_type.clone(),
+                seat_class: metadata.seat_class.clone(),
+                adults: metadata.adults,
+                children: metadata.children,
+                current_price: result.current_price.clone(),
+                rank: i + 1,
+                leg: leg + 1,
+                legs: count,
+                airline: flight.name.clone(),
+                airline_code: flight_legs.get(leg).map(|l| l.airline_code.clone()),
+                flight_number: flight_legs.get(leg).map(|l| l.flight_number.clone()),
+                from_airport,
+                to_airport,
+                departure_local,
+                arrival_local,
+                departure_utc: departure.as_ref().map(utc),
+                arrival_utc: arrival.as_ref().map(utc),
+                flight_price: flight.price.amount,
+                currency: flight.price.currency.clone(),
+                flight_duration_minutes: flight.duration_minutes(),
+            });
+        }
+    }
+    rows
+}
+
+/// Rows as CSV with a header line
+pub fn to_csv<T: Serialize>(rows: &[T]) -> Result<String, FlightError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        writer.serialize(row).map_err(|e| FlightError::ParseError(format!("CSV export failed: {}", e)))?;
+    }
+    let bytes = writer.into_inner().map_err(|e| FlightError::ParseError(format!("CSV export failed: {}", e)))?;
+    String::from_utf8(bytes).map_err(|e| FlightError::ParseError(format!("CSV export failed: {}", e)))
+}
+
+/// Rows as newline-delimited JSON, one object per line
+pub fn to_ndjson<T: Serialize>(rows: &[T]) -> Result<String, FlightError> {
+    let mut out = String::new();
+    for row in rows {
+        let line = serde_json::to_string(row).map_err(|e| FlightError::ParseError(format!("NDJSON export failed: {}", e)))?;
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Export `result` in `format` with a row per flight or per leg
+pub fn export_results(
+    result: &FlightResult,
+    metadata: &SearchMetadata,
+    format: ExportFormat,
+    rows: ExportRows,
+) -> Result<String, FlightError> {
+    match (format, rows) {
+        (ExportFormat::Csv, ExportRows::Flights) => to_csv(&flight_rows(result, metadata)),
+        (ExportFormat::Csv, ExportRows::Legs) => to_csv(&leg_rows(result, metadata)),
+        (ExportFormat::Ndjson, ExportRows::Flights) => to_ndjson(&flight_rows(result, metadata)),
+        (ExportFormat::Ndjson, ExportRows::Legs) => to_ndjson(&leg_rows(result, metadata)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result() -> FlightResult {
+        let connecting = Flight::test("United", 320)
+            .best()
+            .schedule("6:00 AM on Fri, Aug 15", "5:30 PM on Fri, Aug 15", "8 hr 30 min")
+            .route("SFO", "JFK")
+            .legs(&[("UA", "100"), ("UA", "200")])
+            .layovers(&["DEN"], "Layover (1 of 1) is a 1 hr layover at Denver International Airport in Denver.");
+        let nonstop = Flight::test("JetBlue, Inc.", 289).schedule("9:00 AM", "5:35 PM", "5 hr 35 min");
+        FlightResult { current_price: "low".to_string(), flights: vec![connecting, nonstop] }
+    }
+
+    fn metadata() -> SearchMetadata {
+        SearchMetadata { searched_at: "2025-08-01T12:00:00Z".to_string(), ..SearchMetadata::new("SFO", "JFK", "2025-08-15") }
+    }
+
+    #[test]
+    fn test_flight_rows() {
+        let rows = flight_rows(&result(), &metadata());
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].rank, 1);
+        assert_eq!(rows[0].duration_minutes, Some(510));
+        assert_eq!(rows[0].layovers, "DEN");
+        assert_eq!(rows[0].layover_minutes, Some(60));
+        assert_eq!(rows[0].flight_numbers, "UA100|UA200");
+        assert_eq!(rows[0].departure_local.as_deref(), Some("2025-08-15T06:00:00"));
+        assert_eq!(rows[0].departure_utc.as_deref(), Some("2025-08-15T13:00:00Z"));
+        // Airports missing from the flight stay empty, but the search's airports still place its times
+        assert_eq!(rows[1].origin, None);
+        assert_eq!(rows[1].arrival_utc.as_deref(), Some("2025-08-15T21:35:00Z"));
+        assert_eq!(rows[1].layover_minutes, None);
+    }
+
+    #[test]
+    fn test_leg_rows() {
+        let rows = leg_rows(&result(), &metadata());
+        assert_eq!(rows.len(), 3);
+        assert_eq!((rows[0].leg, rows[0].legs), (1, 2));
+        assert_eq!(rows[0].flight_number.as_deref(), Some("100"));
+        assert_eq!(rows[0].to_airport.as_deref(), Some("DEN"));
+        assert_eq!(rows[1].from_airport.as_deref(), Some("DEN"));
+        assert_eq!(rows[1].arrival_local.as_deref(), Some("2025-08-15T17:30:00"));
+        assert!(rows[0].arrival_utc.is_some() && rows[1].departure_utc.is_some());
+        assert_eq!(rows[2].rank, 2);
+        assert_eq!(rows[2].from_airport.as_deref(), Some("SFO"));
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let csv = export_results(&result(), &metadata(), ExportFormat::Csv, ExportRows::Flights).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("searched_at,search_origin,search_destination,search_date,return_date,"));
+        assert!(lines[0].ends_with(",flight_numbers,price,currency"));
+        // Commas in values are quoted
+        assert!(lines[2].contains("\"JetBlue, Inc.\""));
+
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let parsed: Vec<FlightRow> = reader.deserialize().collect::<Result<_, _>>().unwrap();
+        assert_eq!(parsed, flight_rows(&result(), &metadata()));
+    }
+
+    #[test]
+    fn test_ndjson_export() {
+        let ndjson = export_results(&result(), &metadata(), ExportFormat::Ndjson, ExportRows::Legs).unwrap();
+        let lines: Vec<&str> = ndjson.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["airline_code"], "UA");
+        assert_eq!(first["search_date"], "2025-08-15");
+        assert_eq!(first["trip_type"], "one-way");
+        assert_eq!(first["seat_class"], "economy");
+        assert_eq!(first["adults"], 1);
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        assert_eq!("CSV".parse::<ExportFormat>().unwrap(), ExportFormat::Csv);
+        assert_eq!("jsonl".parse::<ExportFormat>().unwrap(), ExportFormat::Ndjson);
+        assert!("parquet".parse::<ExportFormat>().is_err());
+        assert_eq!("legs".parse::<ExportRows>().unwrap(), ExportRows::Legs);
+    }
+}
@@ -0,0 +1,429 @@
+//! Client-side filtering and sorting of flight search results
+//!
+//! Google Flights only supports a handful of server-side filters (stops, airlines,
+//! time windows). This module adds filters and orderings that are applied to an
+//! already parsed `FlightResult`, based on the values parsed from each `Flight`.
+
+use crate::{Flight, FlightError, FlightResult};
+use chrono::NaiveTime;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// Composable post-search filter for `FlightResult`
+///
+/// Every criterion is optional; a flight must satisfy all criteria that are set.
+/// Flights whose relevant field cannot be parsed are rejected by that criterion.
+///
+/// # Example
+/// ```rust
+/// use rust_flights::FlightFilter;
+///
+/// let filter = FlightFilter::new()
+///     .max_price(450)
+///     .max_duration_minutes(8 * 60)
+///     .exclude_airport("EWR")
+///     .require_airlines(["AA", "DL"]);
+/// # let _ = filter;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FlightFilter {
+    pub min_price: Option<i32>,
+    pub max_price: Option<i32>,
+    pub max_duration_minutes: Option<i32>,
+    pub max_layover_minutes: Option<i32>,
+    pub departure_after: Option<NaiveTime>,
+    pub departure_before: Option<NaiveTime>,
+    pub arrival_after: Option<NaiveTime>,
+    pub arrival_before: Option<NaiveTime>,
+    pub excluded_airports: Vec<String>,   // Origin, destination or layover airport codes
+    pub required_airlines: Vec<String>,   // Every leg must be operated by one of these codes
+}
+
+impl FlightFilter {
+    /// Create an empty filter that accepts every flight
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn min_price(mut self, amount: i32) -> Self {
+        self.min_price = Some(amount);
+        self
+    }
+
+    pub fn max_price(mut self, amount: i32) -> Self {
+        self.max_price = Some(amount);
+        self
+    }
+
+    pub fn max_duration_minutes(mut self, minutes: i32) -> Self {
+        self.max_duration_minutes = Some(minutes);
+        self
+    }
+
+    pub fn max_layover_minutes(mut self, minutes: i32) -> Self {
+        self.max_layover_minutes = Some(minutes);
+        self
+    }
+
+    pub fn departure_after(mut self, time: NaiveTime) -> Self {
+        self.departure_after = Some(time);
+        self
+    }
+
+    pub fn departure_before(mut self, time: NaiveTime) -> Self {
+        self.departure_before = Some(time);
+        self
+    }
+
+    pub fn arrival_after(mut self, time: NaiveTime) -> Self {
+        self.arrival_after = Some(time);
+        self
+    }
+
+    pub fn arrival_before(mut self, time: NaiveTime) -> Self {
+        self.arrival_before = Some(time);
+        self
+    }
+
+    pub fn exclude_airport(mut self, code: impl Into<String>) -> Self {
+        self.excluded_airports.push(code.into().to_uppercase());
+        self
+    }
+
+    pub fn require_airlines<I, S>(mut self, codes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.required_airlines
+            .extend(codes.into_iter().map(|c| c.into().to_uppercase()));
+        self
+    }
+
+    /// Returns true if no criterion is set
+    pub fn is_empty(&self) -> bool {
+        self.min_price.is_none()
+            && self.max_price.is_none()
+            && self.max_duration_minutes.is_none()
+            && self.max_layover_minutes.is_none()
+            && self.departure_after.is_none()
+            && self.departure_before.is_none()
+            && self.arrival_after.is_none()
+            && self.arrival_before.is_none()
+            && self.excluded_airports.is_empty()
+            && self.required_airlines.is_empty()
+    }
+
+    /// Check whether a single flight satisfies every criterion of this filter
+    pub fn matches(&self, flight: &Flight) -> bool {
+        if let Some(min_price) = self.min_price {
+            if flight.price.amount < min_price {
+                return false;
+            }
+        }
+        if let Some(max_price) = self.max_price {
+            if flight.price.amount > max_price {
+                return false;
+            }
+        }
+
+        if let Some(max_duration) = self.max_duration_minutes {
+            match flight.duration_minutes() {
+                Some(minutes) if minutes <= max_duration => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(max_layover) = self.max_layover_minutes {
+            let layovers = flight.layover_minutes();
+            if flight.stops != 0 && layovers.is_empty() {
+                return false;
+            }
+            if layovers.iter().any(|&minutes| minutes > max_layover) {
+                return false;
+            }
+        }
+
+        if !Self::time_in_range(flight.departure_time_of_day(), self.departure_after, self.departure_before) {
+            return false;
+        }
+        if !Self::time_in_range(flight.arrival_time_of_day(), self.arrival_after, self.arrival_before) {
+            return false;
+        }
+
+        if !self.excluded_airports.is_empty() {
+            let excluded = |code: &str| self.excluded_airports.iter().any(|e| e.eq_ignore_ascii_case(code));
+            let touches_excluded = flight.origin_airport.iter()
+                .chain(flight.destination_airport.iter())
+                .chain(flight.layovers.iter().flatten())
+                .any(|code| excluded(code));
+            if touches_excluded {
+                return false;
+            }
+        }
+
+        if !self.required_airlines.is_empty() {
+            let codes = flight.airline_codes();
+            if codes.is_empty() {
+                return false;
+            }
+            let all_allowed = codes.iter()
+                .all(|code| self.required_airlines.iter().any(|r| r.eq_ignore_ascii_case(code)));
+            if !all_allowed {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Apply this filter to a search result, keeping only matching flights
+    pub fn apply(&self, result: FlightResult) -> FlightResult {
+        FlightResult {
+            current_price: result.current_price,
+            flights: result.flights.into_iter().filter(|f| self.matches(f)).collect(),
+        }
+    }
+
+    fn time_in_range(time: Option<NaiveTime>, after: Option<NaiveTime>, before: Option<NaiveTime>) -> bool {
+        if after.is_none() && before.is_none() {
+            return true;
+        }
+        let time = match time {
+            Some(time) => time,
+            None => return false,
+        };
+        after.is_none_or(|after| time >= after) && before.is_none_or(|before| time <= before)
+    }
+}
+
+/// Weights used by `SortKey::Value` to trade price off against travel time
+///
+/// The score is `price * amount + duration * minutes + stop * stops`; lower is better.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueWeights {
+    pub price: f64,     // Per currency unit
+    pub duration: f64,  // Per minute of travel time
+    pub stop: f64,      // Per stop
+}
+
+impl Default for ValueWeights {
+    fn default() -> Self {
+        // Roughly: an hour of travel time is worth 30 currency units, a stop 25
+        Self {
+            price: 1.0,
+            duration: 0.5,
+            stop: 25.0,
+        }
+    }
+}
+
+/// Ordering applied to flights after filtering
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortKey {
+    Price,
+    Duration,
+    Departure,
+    Arrival,
+    Value(ValueWeights),
+}
+
+impl FromStr for SortKey {
+    type Err = FlightError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "price" | "cheapest" => Ok(SortKey::Price),
+            "duration" | "fastest" => Ok(SortKey::Duration),
+            "departure" | "departure-time" => Ok(SortKey::Departure),
+            "arrival" | "arrival-time" => Ok(SortKey::Arrival),
+            "value" | "best-value" => Ok(SortKey::Value(ValueWeights::default())),
+            _ => Err(FlightError::ParseError(format!("Invalid sort key: {}", s))),
+        }
+    }
+}
+
+impl SortKey {
+    /// Sort flights in place, ascending. The sort is stable and flights whose
+    /// sort value cannot be determined are placed last.
+    pub fn sort(&self, flights: &mut Vec<Flight>) {
+        let mut keyed: Vec<(Option<f64>, Flight)> = flights
+            .drain(..)
+            .map(|flight| (self.sort_value(&flight), flight))
+            .collect();
+
+        keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+            (Some(a), Some(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+
+        flights.extend(keyed.into_iter().map(|(_, flight)| flight));
+    }
+
+    /// Sort value of a single flight (lower sorts first)
+    pub fn sort_value(&self, flight: &Flight) -> Option<f64> {
+        match self {
+            SortKey::Price => known_price(flight).map(f64::from),
+            SortKey::Duration => flight.duration_minutes().map(f64::from),
+            SortKey::Departure => flight.departure_time_of_day().map(minutes_since_midnight),
+            SortKey::Arrival => flight.arrival_time_of_day().map(minutes_since_midnight),
+            SortKey::Value(weights) => {
+                let price = known_price(flight)?;
+                let duration = flight.duration_minutes()?;
+                let stops = flight.stops.max(0);
+                Some(
+                    weights.price * f64::from(price)
+                        + weights.duration * f64::from(duration)
+                        + weights.stop * f64::from(stops),
+                )
+            }
+        }
+    }
+}
+
+impl FlightResult {
+    /// Keep only the flights matching `filter`
+    pub fn filtered(self, filter: &FlightFilter) -> Self {
+        filter.apply(self)
+    }
+
+    /// Sort the flights by `key`
+    pub fn sorted_by(mut self, key: &SortKey) -> Self {
+        key.sort(&mut self.flights);
+        self
+    }
+}
+
+/// Parse a 24-hour "HH:MM" clock time as used by the filter options
+pub fn parse_clock_time(value: &str) -> Result<NaiveTime, FlightError> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| FlightError::TimeParseError(format!("Time must be in HH:MM format, got {}", value)))
+}
+
+/// A price of 0 means the parser could not find one
+fn known_price(flight: &Flight) -> Option<i32> {
+    if flight.price.amount > 0 { Some(flight.price.amount) } else { None }
+}
+
+fn minutes_since_midnight(time: NaiveTime) -> f64 {
+    use chrono::Timelike;
+    f64::from(time.hour() * 60 + time.minute())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_flight(name: &str, price: i32, duration: &str, departure: &str, arrival: &str) -> Flight {
+        Flight::test(name, price).schedule(departure, arrival, duration).route("LAX", "JFK")
+    }
+
+    fn make_result() -> FlightResult {
+        let connecting = make_flight("Delta", 180, "9 hr 10 min", "6:00 AM on Fri, Aug 15", "6:10 PM on Fri, Aug 15")
+            .layovers(&["ATL"], "Layover (1 of 1) is a 2 hr 5 min layover at Hartsfield-Jackson Atlanta International Airport in Atlanta.")
+            .legs(&[("DL", "100"), ("DL", "200")]);
+
+        let nonstop = make_flight("American", 320, "5 hr 30 min", "10:15 AM on Fri, Aug 15", "6:45 PM on Fri, Aug 15")
+            .legs(&[("AA", "274")]);
+
+        let red_eye = make_flight("JetBlue", 250, "5 hr 20 min", "11:30 PM on Fri, Aug 15", "7:50 AM on Sat, Aug 16");
+
+        FlightResult {
+            current_price: "typical".to_string(),
+            flights: vec![nonstop, connecting, red_eye],
+        }
+    }
+
+    fn names(result: &FlightResult) -> Vec<&str> {
+        result.flights.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_empty_filter_keeps_everything() {
+        let filter = FlightFilter::new();
+        assert!(filter.is_empty());
+        assert_eq!(make_result().filtered(&filter).flights.len(), 3);
+    }
+
+    #[test]
+    fn test_price_and_duration_filters() {
+        let result = make_result().filtered(&FlightFilter::new().max_price(300));
+        assert_eq!(names(&result), vec!["Delta", "JetBlue"]);
+
+        let result = make_result().filtered(&FlightFilter::new().min_price(200).max_duration_minutes(6 * 60));
+        assert_eq!(names(&result), vec!["American", "JetBlue"]);
+    }
+
+    #[test]
+    fn test_layover_filter() {
+        let result = make_result().filtered(&FlightFilter::new().max_layover_minutes(120));
+        assert_eq!(names(&result), vec!["American", "JetBlue"]);
+
+        let result = make_result().filtered(&FlightFilter::new().max_layover_minutes(180));
+        assert_eq!(result.flights.len(), 3);
+    }
+
+    #[test]
+    fn test_time_filters() {
+        let filter = FlightFilter::new()
+            .departure_after(parse_clock_time("08:00").unwrap())
+            .departure_before(parse_clock_time("23:59").unwrap());
+        assert_eq!(names(&make_result().filtered(&filter)), vec!["American", "JetBlue"]);
+
+        let filter = FlightFilter::new().arrival_before(parse_clock_time("12:00").unwrap());
+        assert_eq!(names(&make_result().filtered(&filter)), vec!["JetBlue"]);
+    }
+
+    #[test]
+    fn test_airport_and_airline_filters() {
+        let result = make_result().filtered(&FlightFilter::new().exclude_airport("atl"));
+        assert_eq!(names(&result), vec!["American", "JetBlue"]);
+
+        // Flights without leg information cannot satisfy an airline requirement
+        let result = make_result().filtered(&FlightFilter::new().require_airlines(["DL"]));
+        assert_eq!(names(&result), vec!["Delta"]);
+
+        let result = make_result().filtered(&FlightFilter::new().require_airlines(["AA", "DL"]));
+        assert_eq!(names(&result), vec!["American", "Delta"]);
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        assert_eq!(names(&make_result().sorted_by(&SortKey::Price)), vec!["Delta", "JetBlue", "American"]);
+        assert_eq!(names(&make_result().sorted_by(&SortKey::Duration)), vec!["JetBlue", "American", "Delta"]);
+        assert_eq!(names(&make_result().sorted_by(&SortKey::Departure)), vec!["Delta", "American", "JetBlue"]);
+        assert_eq!(names(&make_result().sorted_by(&SortKey::Arrival)), vec!["JetBlue", "Delta", "American"]);
+
+        // JetBlue: 250 + 160 = 410, Delta: 180 + 275 + 25 = 480, American: 320 + 165 = 485
+        let value = "value".parse::<SortKey>().unwrap();
+        assert_eq!(names(&make_result().sorted_by(&value)), vec!["JetBlue", "Delta", "American"]);
+    }
+
+    #[test]
+    fn test_unknown_values_sort_last() {
+        let mut result = make_result();
+        result.flights[1].price.amount = 0;
+        result.flights[1].duration = "Unknown".to_string();
+
+        assert_eq!(names(&result.clone().sorted_by(&SortKey::Price)), vec!["JetBlue", "American", "Delta"]);
+        assert_eq!(names(&result.sorted_by(&SortKey::Duration)), vec!["JetBlue", "American", "Delta"]);
+    }
+
+    #[test]
+    fn test_sort_key_parsing() {
+        assert_eq!("price".parse::<SortKey>().unwrap(), SortKey::Price);
+        assert_eq!("Duration".parse::<SortKey>().unwrap(), SortKey::Duration);
+        assert_eq!("departure".parse::<SortKey>().unwrap(), SortKey::Departure);
+        assert_eq!("arrival".parse::<SortKey>().unwrap(), SortKey::Arrival);
+        assert!(matches!("value".parse::<SortKey>(), Ok(SortKey::Value(_))));
+        assert!("invalid".parse::<SortKey>().is_err());
+    }
+
+    #[test]
+    fn test_parse_clock_time() {
+        assert_eq!(parse_clock_time("06:30").unwrap(), NaiveTime::from_hms_opt(6, 30, 0).unwrap());
+        assert!(parse_clock_time("6pm").is_err());
+    }
+}
@@ -0,0 +1,377 @@
+//! Group meeting-point finder: travellers from several origins, ranked candidate
+//! destinations by total cost, worst-case travel time or fairness.
+
+use crate::dates::{cheapest_flight, format_date, parse_date};
+use crate::explore::resolve_location;
+use crate::{Flight, FlightClient, FlightError, Passengers, SearchOptions, WikidataClient};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Maximum number of searches a single group search may make
+pub const MAX_GROUP_SEARCHES: usize = 200;
+
+/// One group of travellers flying from the same place
+#[derive(Debug, Clone)]
+pub struct GroupOrigin {
+    /// Airport code or city name
+    pub origin: String,
+    pub passengers: Passengers,
+}
+
+impl GroupOrigin {
+    pub fn new(origin: impl Into<String>, adults: i32) -> Self {
+        Self {
+            origin: origin.into(),
+            passengers: Passengers { adults, ..Passengers::default() },
+        }
+    }
+
+    /// Number of seated travellers (lap infants are not counted)
+    pub fn travellers(&self) -> i32 {
+        self.passengers.adults + self.passengers.children + self.passengers.infants_in_seat
+    }
+}
+
+/// How to rank candidate meeting points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GroupRanking {
+    /// Lowest combined fare for everyone
+    TotalCost,
+    /// Shortest longest journey of any origin
+    WorstCaseTime,
+    /// Lowest highest per-person fare of any origin
+    Fairness,
+}
+
+impl FromStr for GroupRanking {
+    type Err = FlightError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "total-cost" | "total_cost" | "cost" => Ok(GroupRanking::TotalCost),
+            "worst-case-time" | "worst_case_time" | "time" => Ok(GroupRanking::WorstCaseTime),
+            "fairness" | "fair" => Ok(GroupRanking::Fairness),
+            _ => Err(FlightError::ParseError(format!("Invalid group ranking: {}", s))),
+        }
+    }
+}
+
+/// Parameters for a group meeting-point search
+#[derive(Debug, Clone)]
+pub struct GroupSearchRequest {
+    pub origins: Vec<GroupOrigin>,
+    /// Candidate destinations as airport codes or city names
+    pub destinations: Vec<String>,
+    /// Candidate departure dates (YYYY-MM-DD); every origin flies out on the same date,
+    /// so overnight flights arrive the day after
+    pub dates: Vec<String>,
+    /// Stay length for round trips; one-way when `None`
+    pub nights: Option<u32>,
+    pub ranking: GroupRanking,
+    /// Shared search preferences; passengers are taken from each origin instead
+    pub options: SearchOptions,
+}
+
+/// The flight chosen for one origin at a candidate meeting point
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupLeg {
+    pub origin: String,
+    pub travellers: i32,
+    pub flight: Option<Flight>,
+    /// Fare for the whole party from this origin
+    pub total_price: Option<i32>,
+    pub per_person_price: Option<i32>,
+    pub duration_minutes: Option<i32>,
+    pub error: Option<String>,
+}
+
+impl GroupLeg {
+    /// A party that already lives at the meeting point
+    fn local(origin: &GroupOrigin) -> Self {
+        Self {
+            origin: origin.origin.clone(),
+            travellers: origin.travellers(),
+            flight: None,
+            total_price: Some(0),
+            per_person_price: Some(0),
+            duration_minutes: Some(0),
+            error: None,
+        }
+    }
+
+    fn from_flight(origin: &GroupOrigin, flight: Option<&Flight>) -> Self {
+        let travellers = origin.travellers();
+        let total_price = flight.map(|f| f.price.amount).filter(|amount| *amount > 0);
+        Self {
+            origin: origin.origin.clone(),
+            travellers,
+            flight: flight.cloned(),
+            total_price,
+            per_person_price: total_price.map(|amount| amount / travellers.max(1)),
+            duration_minutes: flight.and_then(Flight::duration_minutes),
+            error: if flight.is_none() { Some("No flights found".to_string()) } else { None },
+        }
+    }
+
+    fn from_error(origin: &GroupOrigin, error: &FlightError) -> Self {
+        Self {
+            origin: origin.origin.clone(),
+            travellers: origin.travellers(),
+            flight: None,
+            total_price: None,
+            per_person_price: None,
+            duration_minutes: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// A candidate destination and date with the chosen flight for every origin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupOption {
+    pub destination: String,
+    pub date: String,
+    pub return_date: Option<String>,
+    pub currency: String,
+    pub legs: Vec<GroupLeg>,
+    pub total_cost: Option<i32>,
+    pub worst_case_minutes: Option<i32>,
+    pub max_individual_cost: Option<i32>,
+}
+
+impl GroupOption {
+    /// Aggregate the per-origin legs; totals are only set when every origin has a flight
+    pub fn new(destination: &str, date: &str, return_date: Option<String>, legs: Vec<GroupLeg>) -> Self {
+        let currency = legs.iter()
+            .filter_map(|leg| leg.flight.as_ref())
+            .map(|f| f.price.currency.clone())
+            .find(|c| !c.is_empty())
+            .unwrap_or_default();
+
+        let total_cost = legs.iter().map(|leg| leg.total_price).sum::<Option<i32>>();
+        let worst_case_minutes = legs.iter()
+            .map(|leg| leg.duration_minutes)
+            .collect::<Option<Vec<i32>>>()
+            .and_then(|d| d.into_iter().max());
+        let max_individual_cost = legs.iter()
+            .map(|leg| leg.per_person_price)
+            .collect::<Option<Vec<i32>>>()
+            .and_then(|p| p.into_iter().max());
+
+        Self {
+            destination: destination.to_string(),
+            date: date.to_string(),
+            return_date,
+            currency,
+            legs,
+            total_cost,
+            worst_case_minutes,
+            max_individual_cost,
+        }
+    }
+
+    /// The value `ranking` sorts by (lower is better)
+    pub fn score(&self, ranking: GroupRanking) -> Option<i32> {
+        match ranking {
+            GroupRanking::TotalCost => self.total_cost,
+            GroupRanking::WorstCaseTime => self.worst_case_minutes,
+            GroupRanking::Fairness => self.max_individual_cost,
+        }
+    }
+}
+
+/// Candidate meeting points, best first according to `ranking`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupSearchResult {
+    pub ranking: GroupRanking,
+    pub options: Vec<GroupOption>,
+}
+
+impl GroupSearchResult {
+    /// Rank options by `ranking`, breaking ties on total cost; options missing a
+    /// flight for any origin go last
+    pub fn new(ranking: GroupRanking, mut options: Vec<GroupOption>) -> Self {
+        options.sort_by_key(|option| match option.score(ranking) {
+            Some(score) => (0, score, option.total_cost.unwrap_or(i32::MAX)),
+            None => (1, 0, 0),
+        });
+        Self { ranking, options }
+    }
+
+    /// The best meeting point, if any destination works for every origin
+    pub fn best(&self) -> Option<&GroupOption> {
+        self.options.first().filter(|option| option.score(self.ranking).is_some())
+    }
+}
+
+/// The flight to pick for one origin: cheapest for cost rankings, fastest for time
+fn choose_flight(flights: &[Flight], ranking: GroupRanking) -> Option<&Flight> {
+    match ranking {
+        GroupRanking::WorstCaseTime => flights.iter()
+            .filter_map(|f| f.duration_minutes().map(|d| (d, f)))
+            .min_by_key(|(d, _)| *d)
+            .map(|(_, f)| f),
+        GroupRanking::TotalCost | GroupRanking::Fairness => cheapest_flight(flights),
+    }
+}
+
+impl FlightClient {
+    /// Search every origin to every candidate destination and date and rank the
+    /// meeting points by `request.ranking`.
+    ///
+    /// Origins and destinations may be airport codes or city names; city names are
+    /// resolved to Freebase IDs the same way `get_flights_by_city` does. A failed
+    /// search is recorded on its leg rather than aborting the whole request.
+    pub async fn find_meeting_point(&self, request: &GroupSearchRequest) -> Result<GroupSearchResult, FlightError> {
+        if request.origins.is_empty() || request.destinations.is_empty() || request.dates.is_empty() {
+            return Err(FlightError::ParseError(
+                "A group search needs at least one origin, destination and date".to_string(),
+            ));
+        }
+
+        let total = request.origins.len() * request.destinations.len() * request.dates.len();
+        if total > MAX_GROUP_SEARCHES {
+            return Err(FlightError::ParseError(format!(
+                "Group search would make {} searches (maximum is {}); narrow the destinations or dates",
+                total, MAX_GROUP_SEARCHES
+            )));
+        }
+
+        let mut trips = Vec::with_capacity(request.dates.len());
+        for date in &request.dates {
+            let departure = parse_date(date)?;
+            let return_date = request.nights.map(|n| format_date(departure + chrono::Duration::days(n as i64)));
+            trips.push((format_date(departure), return_date));
+        }
+
+        let wikidata = WikidataClient::new()?;
+        let mut origins = Vec::with_capacity(request.origins.len());
+        for origin in &request.origins {
+            origins.push(resolve_location(&wikidata, &origin.origin).await?);
+        }
+
+        let mut options = Vec::new();
+        let mut searches = 0;
+        for destination in &request.destinations {
+            let location = match resolve_location(&wikidata, destination).await {
+                Ok(location) => location,
+                Err(e) => {
+                    eprintln!("⚠️  Warning: Skipping destination {}: {}", destination, e);
+                    continue;
+                }
+            };
+
+            for (date, return_date) in &trips {
+                let mut legs = Vec::with_capacity(origins.len());
+                for (origin, origin_location) in request.origins.iter().zip(&origins) {
+                    if *origin_location == location {
+                        legs.push(GroupLeg::local(origin));
+                        continue;
+                    }
+
+                    if searches > 0 {
+                        tokio::time::sleep(request.options.request_delay).await;
+                    }
+                    searches += 1;
+
+                    let options = SearchOptions { passengers: origin.passengers.clone(), ..request.options.clone() };
+                    let search = match return_date {
+                        Some(return_date) => options.round_trip_request(origin_location, &location, date, return_date),
+                        None => options.one_way_request(origin_location, &location, date),
+                    };
+
+                    let leg = match self.get_flights(search).await {
+                        Ok(result) => GroupLeg::from_flight(origin, choose_flight(&result.flights, request.ranking)),
+                        Err(e) => {
+                            eprintln!("⚠️  Warning: Search {} → {} on {} failed: {}", origin.origin, destination, date, e);
+                            GroupLeg::from_error(origin, &e)
+                        }
+                    };
+                    legs.push(leg);
+                }
+                options.push(GroupOption::new(destination, date, return_date.clone(), legs));
+            }
+        }
+
+        Ok(GroupSearchResult::new(request.ranking, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flight(amount: i32, duration: &str) -> Flight {
+        Flight::test("Test Air", amount).schedule("8:00 AM on Mon, Sep 1", "4:00 PM on Mon, Sep 1", duration)
+    }
+
+    fn option(destination: &str, legs: Vec<(i32, i32, &str)>) -> GroupOption {
+        let legs = legs.into_iter().map(|(adults, amount, duration)| {
+            GroupLeg::from_flight(&GroupOrigin::new("SEA", adults), Some(&flight(amount, duration)))
+        }).collect();
+        GroupOption::new(destination, "2025-09-01", None, legs)
+    }
+
+    #[test]
+    fn test_group_option_aggregates() {
+        let opt = option("DEN", vec![(1, 300, "3 hr"), (2, 800, "6 hr 30 min")]);
+        assert_eq!(opt.total_cost, Some(1100));
+        assert_eq!(opt.worst_case_minutes, Some(390));
+        assert_eq!(opt.max_individual_cost, Some(400));
+        assert_eq!(opt.currency, "$");
+
+        let missing = GroupOption::new("DEN", "2025-09-01", None, vec![
+            GroupLeg::from_flight(&GroupOrigin::new("SEA", 1), Some(&flight(300, "3 hr"))),
+            GroupLeg::from_flight(&GroupOrigin::new("LON", 1), None),
+        ]);
+        assert_eq!(missing.total_cost, None);
+        assert_eq!(missing.worst_case_minutes, None);
+    }
+
+    #[test]
+    fn test_local_leg_costs_nothing() {
+        let opt = GroupOption::new("SEA", "2025-09-01", None, vec![
+            GroupLeg::local(&GroupOrigin::new("SEA", 3)),
+            GroupLeg::from_flight(&GroupOrigin::new("NYC", 1), Some(&flight(450, "5 hr 45 min"))),
+        ]);
+        assert_eq!(opt.total_cost, Some(450));
+        assert_eq!(opt.worst_case_minutes, Some(345));
+    }
+
+    #[test]
+    fn test_group_ranking() {
+        let cheap_but_unfair = option("DEN", vec![(1, 100, "2 hr"), (1, 700, "9 hr")]);
+        let fair = option("ORD", vec![(1, 420, "4 hr"), (1, 430, "4 hr 10 min")]);
+        let incomplete = GroupOption::new("BER", "2025-09-01", None, vec![
+            GroupLeg::from_flight(&GroupOrigin::new("SEA", 1), None),
+        ]);
+        let all = vec![incomplete, fair, cheap_but_unfair];
+
+        let by_cost = GroupSearchResult::new(GroupRanking::TotalCost, all.clone());
+        let order: Vec<&str> = by_cost.options.iter().map(|o| o.destination.as_str()).collect();
+        assert_eq!(order, vec!["DEN", "ORD", "BER"]);
+
+        let by_fairness = GroupSearchResult::new(GroupRanking::Fairness, all.clone());
+        assert_eq!(by_fairness.best().unwrap().destination, "ORD");
+
+        let by_time = GroupSearchResult::new(GroupRanking::WorstCaseTime, all);
+        assert_eq!(by_time.best().unwrap().destination, "ORD");
+    }
+
+    #[test]
+    fn test_choose_flight() {
+        let flights = vec![flight(500, "3 hr"), flight(200, "7 hr"), flight(0, "1 hr")];
+        assert_eq!(choose_flight(&flights, GroupRanking::TotalCost).unwrap().price.amount, 200);
+        assert_eq!(choose_flight(&flights, GroupRanking::WorstCaseTime).unwrap().price.amount, 0);
+        assert!(choose_flight(&[], GroupRanking::Fairness).is_none());
+    }
+
+    #[test]
+    fn test_group_ranking_from_str() {
+        assert_eq!("total-cost".parse::<GroupRanking>().unwrap(), GroupRanking::TotalCost);
+        assert_eq!("time".parse::<GroupRanking>().unwrap(), GroupRanking::WorstCaseTime);
+        assert_eq!("Fairness".parse::<GroupRanking>().unwrap(), GroupRanking::Fairness);
+        assert!("random".parse::<GroupRanking>().is_err());
+    }
+}
@@ -0,0 +1,321 @@
+//! Open-jaw and stopover itineraries: build the multi-city request for each
+//! shape, check that its legs fit together, and price it.
+
+use crate::dates::{cheapest_flight, format_date, parse_date};
+use crate::{Flight, FlightClient, FlightError, FlightSearchRequest, SearchOptions, TripType};
+use serde::{Deserialize, Serialize};
+
+/// The kind of multi-leg trip an itinerary describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ItineraryShape {
+    /// Out to one airport, home from another (or home to a different airport)
+    OpenJaw,
+    /// A chain of legs with at least one night at each intermediate stop
+    Stopover,
+}
+
+/// One leg of an itinerary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItineraryLeg {
+    pub from_airport: String,
+    pub to_airport: String,
+    pub date: String,
+}
+
+impl ItineraryLeg {
+    pub fn new(from_airport: &str, to_airport: &str, date: &str) -> Self {
+        Self {
+            from_airport: from_airport.to_string(),
+            to_airport: to_airport.to_string(),
+            date: date.to_string(),
+        }
+    }
+}
+
+/// A validated multi-leg itinerary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Itinerary {
+    pub shape: ItineraryShape,
+    pub legs: Vec<ItineraryLeg>,
+}
+
+impl Itinerary {
+    /// Fly `outbound_from` → `outbound_to`, then home `return_from` → `return_to`.
+    ///
+    /// At least one end must differ from a plain round trip, e.g. into LHR and home
+    /// from CDG.
+    pub fn open_jaw(
+        outbound_from: &str,
+        outbound_to: &str,
+        return_from: &str,
+        return_to: &str,
+        departure_date: &str,
+        return_date: &str,
+    ) -> Result<Self, FlightError> {
+        let itinerary = Self {
+            shape: ItineraryShape::OpenJaw,
+            legs: vec![
+                ItineraryLeg::new(outbound_from, outbound_to, departure_date),
+                ItineraryLeg::new(return_from, return_to, return_date),
+            ],
+        };
+        itinerary.validate()?;
+        Ok(itinerary)
+    }
+
+    /// Fly `origin` → `stopover`, stay `stopover_days` days, then continue to `destination`
+    pub fn stopover(
+        origin: &str,
+        stopover: &str,
+        destination: &str,
+        departure_date: &str,
+        stopover_days: u32,
+    ) -> Result<Self, FlightError> {
+        let onward_date = parse_date(departure_date)? + chrono::Duration::days(stopover_days as i64);
+        let itinerary = Self {
+            shape: ItineraryShape::Stopover,
+            legs: vec![
+                ItineraryLeg::new(origin, stopover, departure_date),
+                ItineraryLeg::new(stopover, destination, &format_date(onward_date)),
+            ],
+        };
+        itinerary.validate()?;
+        Ok(itinerary)
+    }
+
+    /// Add another stopover leg from the current final airport
+    pub fn then(mut self, to_airport: &str, date: &str) -> Result<Self, FlightError> {
+        let from = self.legs.last().map(|leg| leg.to_airport.clone()).unwrap_or_default();
+        self.legs.push(ItineraryLeg::new(&from, to_airport, date));
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Check the continuity rules for this itinerary's shape.
+    ///
+    /// Every itinerary needs valid, non-decreasing dates and legs between different
+    /// airports. An open jaw has exactly two legs and must not be a plain round trip.
+    /// A stopover's legs must connect end to end, with the onward leg departing at
+    /// least one day after arriving.
+    pub fn validate(&self) -> Result<(), FlightError> {
+        let invalid = |message: String| Err(FlightError::ParseError(message));
+
+        if self.legs.len() < 2 {
+            return invalid("An itinerary needs at least two legs".to_string());
+        }
+
+        let mut previous_date = None;
+        for (i, leg) in self.legs.iter().enumerate() {
+            if leg.from_airport.eq_ignore_ascii_case(&leg.to_airport) {
+                return invalid(format!("Leg {} departs from and arrives at {}", i + 1, leg.from_airport));
+            }
+            let date = parse_date(&leg.date)?;
+            if previous_date.is_some_and(|previous| date < previous) {
+                return invalid(format!("Leg {} on {} departs before the previous leg", i + 1, leg.date));
+            }
+            previous_date = Some(date);
+        }
+
+        match self.shape {
+            ItineraryShape::OpenJaw => {
+                if self.legs.len() != 2 {
+                    return invalid("An open-jaw itinerary has exactly two legs".to_string());
+                }
+                let (outbound, inbound) = (&self.legs[0], &self.legs[1]);
+                if outbound.to_airport.eq_ignore_ascii_case(&inbound.from_airport)
+                    && outbound.from_airport.eq_ignore_ascii_case(&inbound.to_airport)
+                {
+                    return invalid("Both ends match; search this as a round trip instead".to_string());
+                }
+            }
+            ItineraryShape::Stopover => {
+                for (i, pair) in self.legs.windows(2).enumerate() {
+                    if !pair[0].to_airport.eq_ignore_ascii_case(&pair[1].from_airport) {
+                        return invalid(format!(
+                            "Leg {} departs from {} but leg {} arrives at {}",
+                            i + 2, pair[1].from_airport, i + 1, pair[0].to_airport
+                        ));
+                    }
+                    if pair[0].date == pair[1].date {
+                        return invalid(format!(
+                            "The stopover at {} must last at least one day",
+                            pair[0].to_airport
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The multi-city search request for the whole itinerary
+    pub fn to_request(&self, options: &SearchOptions) -> FlightSearchRequest {
+        FlightSearchRequest {
+            flights: self.legs.iter()
+                .map(|leg| options.flight_data(&leg.from_airport, &leg.to_airport, &leg.date))
+                .collect(),
+            trip_type: TripType::MultiCity,
+            passengers: options.passengers.clone(),
+            seat_class: options.seat_class.clone(),
+        }
+    }
+
+    /// One one-way request per leg, for pricing the legs as separate tickets
+    pub fn leg_requests(&self, options: &SearchOptions) -> Vec<FlightSearchRequest> {
+        self.legs.iter()
+            .map(|leg| options.one_way_request(&leg.from_airport, &leg.to_airport, &leg.date))
+            .collect()
+    }
+}
+
+/// The cheapest one-way flight found for one leg
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegQuote {
+    pub leg: ItineraryLeg,
+    pub cheapest: Option<Flight>,
+    pub error: Option<String>,
+}
+
+/// An itinerary priced as one multi-city ticket and as separate one-way tickets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricedItinerary {
+    pub itinerary: Itinerary,
+    /// Cheapest multi-city option; its price covers every leg, its details the first leg
+    pub multi_city: Option<Flight>,
+    pub multi_city_error: Option<String>,
+    /// Cheapest one-way flight for each leg
+    pub legs: Vec<LegQuote>,
+}
+
+impl PricedItinerary {
+    /// Price of the whole trip on one multi-city ticket
+    pub fn multi_city_price(&self) -> Option<i32> {
+        self.multi_city.as_ref().map(|f| f.price.amount)
+    }
+
+    /// Combined price of separate one-way tickets, if every leg was priced
+    pub fn separate_total(&self) -> Option<i32> {
+        self.legs.iter()
+            .map(|quote| quote.cheapest.as_ref().map(|f| f.price.amount))
+            .sum()
+    }
+
+    /// The lower of the multi-city and separate-ticket prices
+    pub fn best_price(&self) -> Option<i32> {
+        match (self.multi_city_price(), self.separate_total()) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+impl FlightClient {
+    /// Price `itinerary` as one multi-city ticket and as one-way tickets per leg.
+    ///
+    /// A failed search is recorded in the result rather than aborting it.
+    pub async fn search_itinerary(
+        &self,
+        itinerary: &Itinerary,
+        options: &SearchOptions,
+    ) -> Result<PricedItinerary, FlightError> {
+        itinerary.validate()?;
+
+        let (multi_city, multi_city_error) = match self.get_flights(itinerary.to_request(options)).await {
+            Ok(result) => (cheapest_flight(&result.flights).cloned(), None),
+            Err(e) => {
+                eprintln!("⚠️  Warning: Multi-city search failed: {}", e);
+                (None, Some(e.to_string()))
+            }
+        };
+
+        let mut legs = Vec::with_capacity(itinerary.legs.len());
+        for (leg, request) in itinerary.legs.iter().zip(itinerary.leg_requests(options)) {
+            tokio::time::sleep(options.request_delay).await;
+            let quote = match self.get_flights(request).await {
+                Ok(result) => LegQuote { leg: leg.clone(), cheapest: cheapest_flight(&result.flights).cloned(), error: None },
+                Err(e) => {
+                    eprintln!("⚠️  Warning: Search {} → {} failed: {}", leg.from_airport, leg.to_airport, e);
+                    LegQuote { leg: leg.clone(), cheapest: None, error: Some(e.to_string()) }
+                }
+            };
+            legs.push(quote);
+        }
+
+        Ok(PricedItinerary {
+            itinerary: itinerary.clone(),
+            multi_city,
+            multi_city_error,
+            legs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_jaw() {
+        let itinerary = Itinerary::open_jaw("SFO", "LHR", "CDG", "SFO", "2025-09-01", "2025-09-10").unwrap();
+        let request = itinerary.to_request(&SearchOptions::default());
+        assert!(matches!(request.trip_type, TripType::MultiCity));
+        assert_eq!(request.flights.len(), 2);
+        assert_eq!(request.flights[1].from_airport, "CDG");
+        assert_eq!(request.flights[1].date, "2025-09-10");
+
+        // Plain round trips, reversed dates and same-airport legs are rejected
+        assert!(Itinerary::open_jaw("SFO", "LHR", "LHR", "SFO", "2025-09-01", "2025-09-10").is_err());
+        assert!(Itinerary::open_jaw("SFO", "LHR", "CDG", "SFO", "2025-09-10", "2025-09-01").is_err());
+        assert!(Itinerary::open_jaw("SFO", "SFO", "CDG", "SFO", "2025-09-01", "2025-09-10").is_err());
+    }
+
+    #[test]
+    fn test_stopover() {
+        let itinerary = Itinerary::stopover("SFO", "HND", "SIN", "2025-09-01", 3).unwrap();
+        assert_eq!(itinerary.legs[1], ItineraryLeg::new("HND", "SIN", "2025-09-04"));
+        assert!(Itinerary::stopover("SFO", "HND", "SIN", "2025-09-01", 0).is_err());
+
+        let home = itinerary.then("SFO", "2025-09-12").unwrap();
+        assert_eq!(home.legs.len(), 3);
+        assert_eq!(home.legs[2].from_airport, "SIN");
+        assert_eq!(home.leg_requests(&SearchOptions::default()).len(), 3);
+    }
+
+    #[test]
+    fn test_stopover_continuity() {
+        let broken = Itinerary {
+            shape: ItineraryShape::Stopover,
+            legs: vec![
+                ItineraryLeg::new("SFO", "HND", "2025-09-01"),
+                ItineraryLeg::new("NRT", "SIN", "2025-09-04"),
+            ],
+        };
+        let error = broken.validate().unwrap_err().to_string();
+        assert!(error.contains("NRT") && error.contains("HND"), "{}", error);
+    }
+
+    #[test]
+    fn test_priced_itinerary() {
+        let flight = |amount: i32| Flight::test("ANA", amount).schedule("11:00 AM", "3:00 PM", "11 hr");
+        let itinerary = Itinerary::stopover("SFO", "HND", "SIN", "2025-09-01", 3).unwrap();
+        let quote = |i: usize, amount: Option<i32>| LegQuote {
+            leg: itinerary.legs[i].clone(),
+            cheapest: amount.map(flight),
+            error: None,
+        };
+
+        let priced = PricedItinerary {
+            itinerary: itinerary.clone(),
+            multi_city: Some(flight(1200)),
+            multi_city_error: None,
+            legs: vec![quote(0, Some(700)), quote(1, Some(300))],
+        };
+        assert_eq!(priced.separate_total(), Some(1000));
+        assert_eq!(priced.best_price(), Some(1000));
+
+        let partial = PricedItinerary { legs: vec![quote(0, Some(700)), quote(1, None)], ..priced };
+        assert_eq!(partial.separate_total(), None);
+        assert_eq!(partial.best_price(), Some(1200));
+    }
+}
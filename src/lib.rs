@@ -4,18 +4,59 @@
 //! This library provides better performance than the existing Python implementation
 //! while maintaining API compatibility.
 
+pub mod airports;
+pub mod calendar;
+pub mod city_audit;
+#[cfg(feature = "city-search")]
+pub mod city_store;
 pub mod client;
+pub mod compare;
+pub mod dates;
+pub mod explore;
+pub mod export;
+pub mod filter;
+pub mod group;
+pub mod itinerary;
+pub mod names;
+pub mod nearby;
 pub mod protobuf;
+pub mod resolver;
+pub mod sparql;
+pub mod split;
+pub mod suggest;
+pub mod timezones;
 pub mod wikidata;
 
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
 use thiserror::Error;
 
 // Re-export main types for convenience
+pub use airports::{Airport, AirportDb, MetroArea, Region};
+pub use calendar::{Calendar, CalendarEvent};
+#[cfg(feature = "city-search")]
+pub use city_store::{CityStore, StoredCity};
+pub use city_audit::{CacheAudit, CacheDiff, CacheIssue};
 pub use client::{FlightClient, FlightResponseParser};
+pub use compare::{CheaperOption, TripComparison};
+pub use dates::{DateRangeResult, DaySummary, PriceCell, PriceMatrix, PriceTier};
+pub use explore::{DestinationSummary, ExploreDestinations, ExploreRequest, ExploreResult};
+pub use export::{ExportFormat, ExportRows, FlightRow, LegRow, SearchMetadata};
+pub use filter::{FlightFilter, SortKey, ValueWeights};
+pub use itinerary::{Itinerary, ItineraryLeg, ItineraryShape, LegQuote, PricedItinerary};
+pub use group::{GroupLeg, GroupOption, GroupOrigin, GroupRanking, GroupSearchRequest, GroupSearchResult};
+pub use nearby::AirportExpansion;
 pub use protobuf::*;
-pub use wikidata::{WikidataClient, CityInfo, WikidataError};
+pub use resolver::{CityResolver, Location, LocationKind, LocationRegistry, ResolverChain};
+pub use split::{SplitItinerary, SplitTicketRequest, SplitTicketResult};
+pub use suggest::CitySuggester;
+pub use timezones::{FlightTimes, LayoverTimes};
+pub use wikidata::{WikidataClient, WikidataClientBuilder, CityBatch, CityHint, CityInfo, WikidataError};
 
 /// Error types for the flights library
 #[derive(Error, Debug)]
@@ -29,8 +70,8 @@ pub enum FlightError {
     #[error("Protobuf encoding failed: {0}")]
     ProtobufError(#[from] prost::EncodeError),
     
-    #[error("City not found: {0}")]
-    CityNotFound(String),
+    #[error("City not found: {name}{}", suggest::did_you_mean(.suggestions))]
+    CityNotFound { name: String, suggestions: Vec<String> },
     
     #[error("Invalid date format: {0}")]
     DateParseError(String),
@@ -40,6 +81,20 @@ pub enum FlightError {
     
     #[error("Invalid time format: {0}")]
     TimeParseError(String),
+    
+    #[error("City '{name}' is ambiguous, {}: {}", wikidata::ambiguity_hint(.name, .candidates), wikidata::describe_candidates(.candidates))]
+    AmbiguousCity { name: String, candidates: Vec<CityInfo> },
+    
+    #[error("No destinations to explore: {0}")]
+    NoDestinations(String),
+}
+
+impl FlightError {
+    /// [`FlightError::CityNotFound`] with "did you mean" suggestions from the
+    /// city cache, city store and airport database
+    pub fn city_not_found(name: &str) -> Self {
+        FlightError::CityNotFound { name: name.to_string(), suggestions: suggest::suggest_cities(name) }
+    }
 }
 
 /// Time window for departure or arrival filtering
@@ -52,12 +107,12 @@ pub struct TimeWindow {
 impl TimeWindow {
     /// Create a new TimeWindow
     pub fn new(earliest_hour: i32, latest_hour: i32) -> Result<Self, FlightError> {
-        if earliest_hour < 0 || earliest_hour > 23 {
+        if !(0..=23).contains(&earliest_hour) {
             return Err(FlightError::TimeParseError(
                 format!("earliest_hour must be 0-23, got {}", earliest_hour)
             ));
         }
-        if latest_hour < 0 || latest_hour > 23 {
+        if !(0..=23).contains(&latest_hour) {
             return Err(FlightError::TimeParseError(
                 format!("latest_hour must be 0-23, got {}", latest_hour)
             ));
@@ -115,6 +170,77 @@ pub struct FlightSearchRequest {
     pub seat_class: SeatClass,
 }
 
+/// Search preferences shared by the higher-level searches that fan out into
+/// many one-way `FlightSearchRequest`s (date ranges, price calendars, ...)
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub passengers: Passengers,
+    pub seat_class: SeatClass,
+    pub max_stops: Option<i32>,
+    pub airlines: Option<Vec<String>>,
+    pub departure_time: Option<TimeWindow>,
+    pub arrival_time: Option<TimeWindow>,
+    pub request_delay: Duration,   // Pause between consecutive requests to Google Flights
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            passengers: Passengers::default(),
+            seat_class: SeatClass::Economy,
+            max_stops: None,
+            airlines: None,
+            departure_time: None,
+            arrival_time: None,
+            request_delay: Duration::from_millis(1000),
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Build a single flight segment using these preferences
+    pub fn flight_data(&self, from_airport: &str, to_airport: &str, date: &str) -> FlightData {
+        FlightData {
+            date: date.to_string(),
+            from_airport: from_airport.to_string(),
+            to_airport: to_airport.to_string(),
+            max_stops: self.max_stops,
+            airlines: self.airlines.clone(),
+            departure_time: self.departure_time.clone(),
+            arrival_time: self.arrival_time.clone(),
+        }
+    }
+
+    /// Build a one-way search request using these preferences
+    pub fn one_way_request(&self, from_airport: &str, to_airport: &str, date: &str) -> FlightSearchRequest {
+        FlightSearchRequest {
+            flights: vec![self.flight_data(from_airport, to_airport, date)],
+            trip_type: TripType::OneWay,
+            passengers: self.passengers.clone(),
+            seat_class: self.seat_class.clone(),
+        }
+    }
+
+    /// Build a round-trip search request using these preferences
+    pub fn round_trip_request(
+        &self,
+        from_airport: &str,
+        to_airport: &str,
+        departure_date: &str,
+        return_date: &str,
+    ) -> FlightSearchRequest {
+        FlightSearchRequest {
+            flights: vec![
+                self.flight_data(from_airport, to_airport, departure_date),
+                self.flight_data(to_airport, from_airport, return_date),
+            ],
+            trip_type: TripType::RoundTrip,
+            passengers: self.passengers.clone(),
+            seat_class: self.seat_class.clone(),
+        }
+    }
+}
+
 /// Passenger configuration
 #[derive(Debug, Clone)]
 pub struct Passengers {
@@ -160,6 +286,155 @@ pub struct Flight {
     pub layover_description: Option<String>,
 }
 
+impl Flight {
+    /// Total travel time in minutes, parsed from `duration` (e.g. "5 hr 30 min")
+    pub fn duration_minutes(&self) -> Option<i32> {
+        parse_duration_minutes(&self.duration)
+    }
+
+    /// Local departure time of day, parsed from `departure` (e.g. "6:00 AM on Mon, Jun 2")
+    pub fn departure_time_of_day(&self) -> Option<NaiveTime> {
+        parse_time_of_day(&self.departure)
+    }
+
+    /// Local arrival time of day, parsed from `arrival`
+    pub fn arrival_time_of_day(&self) -> Option<NaiveTime> {
+        parse_time_of_day(&self.arrival)
+    }
+
+    /// Local departure date and time for a flight found by a search on `search_date`.
+    ///
+    /// Uses the date in `departure` ("on Mon, Jun 2") when present, otherwise `search_date`.
+    pub fn departure_datetime(&self, search_date: NaiveDate) -> Option<NaiveDateTime> {
+        let date = parse_month_day(&self.departure, search_date).unwrap_or(search_date);
+        Some(date.and_time(self.departure_time_of_day()?))
+    }
+
+    /// Local arrival date and time for a flight found by a search on `search_date`.
+    ///
+    /// Uses the date in `arrival` when present, then a "+1" day marker. Failing both,
+    /// the arrival day is the one whose elapsed time across the airports' time zones
+    /// best matches `duration`; without known airports, an arrival earlier in the
+    /// day than the departure is assumed to land the next day.
+    pub fn arrival_datetime(&self, search_date: NaiveDate) -> Option<NaiveDateTime> {
+        let airports = self.origin_airport.as_deref().zip(self.destination_airport.as_deref());
+        self.arrival_datetime_between(airports, search_date)
+    }
+
+    /// Like [`Flight::arrival_datetime`], choosing the arrival day with the time zones
+    /// of `airports` (origin, destination) rather than the flight's own
+    pub(crate) fn arrival_datetime_between(&self, airports: Option<(&str, &str)>, search_date: NaiveDate) -> Option<NaiveDateTime> {
+        let time = self.arrival_time_of_day()?;
+        if let Some(date) = parse_month_day(&self.arrival, search_date) {
+            return Some(date.and_time(time));
+        }
+
+        let departure = self.departure_datetime(search_date)?;
+        static DAY_MARKER: OnceLock<Regex> = OnceLock::new();
+        let day_offset = DAY_MARKER.get_or_init(|| Regex::new(r"\+(\d)").unwrap())
+            .captures(&self.arrival)
+            .and_then(|captures| captures[1].parse::<i64>().ok())
+            .or_else(|| {
+                let (origin, destination) = airports?;
+                timezones::arrival_day_offset(origin, destination, departure, time, self.duration_minutes()?)
+            })
+            .unwrap_or(if time < departure.time() { 1 } else { 0 });
+        Some((departure.date() + chrono::Duration::days(day_offset)).and_time(time))
+    }
+
+    /// Duration of each layover in minutes, parsed from `layover_description`
+    pub fn layover_minutes(&self) -> Vec<i32> {
+        let description = match &self.layover_description {
+            Some(description) => description,
+            None => return Vec::new(),
+        };
+
+        // "Layover (1 of 2) is a 2 hr 15 min layover at ..." / "... is a 4 hr 40 min overnight layover at ..."
+        static LAYOVER: OnceLock<Regex> = OnceLock::new();
+        let re = LAYOVER.get_or_init(|| Regex::new(r"is an? ([^.]*?)(?:overnight )?layover").unwrap());
+        re.captures_iter(description)
+            .filter_map(|captures| captures.get(1))
+            .filter_map(|m| parse_duration_minutes(m.as_str()))
+            .collect()
+    }
+
+    /// Airline codes of each leg (empty if leg information was not found)
+    pub fn airline_codes(&self) -> Vec<&str> {
+        self.flight_legs
+            .as_ref()
+            .map(|legs| legs.iter().map(|leg| leg.airline_code.as_str()).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Parse a Google Flights duration such as "5 hr 30 min", "45 min" or "1 day 2 hr" into minutes
+pub fn parse_duration_minutes(text: &str) -> Option<i32> {
+    static DURATION: OnceLock<Regex> = OnceLock::new();
+    let re = DURATION.get_or_init(|| Regex::new(r"(\d+)\s*(days?|d|hrs?|h|mins?|m)\b").unwrap());
+
+    let mut total = 0;
+    let mut found = false;
+    for captures in re.captures_iter(text) {
+        let value: i32 = captures[1].parse().ok()?;
+        let unit = &captures[2];
+        total += if unit.starts_with('d') {
+            value * 24 * 60
+        } else if unit.starts_with('h') {
+            value * 60
+        } else {
+            value
+        };
+        found = true;
+    }
+
+    if found { Some(total) } else { None }
+}
+
+/// Parse the time of day from a Google Flights time string such as "6:00 AM on Mon, Jun 2" or "18:05"
+pub fn parse_time_of_day(text: &str) -> Option<NaiveTime> {
+    static TIME_OF_DAY: OnceLock<Regex> = OnceLock::new();
+    let re = TIME_OF_DAY.get_or_init(|| Regex::new(r"(?i)(\d{1,2}):(\d{2})\s*([ap]\.?m\.?)?").unwrap());
+    let captures = re.captures(text)?;
+
+    let mut hour: u32 = captures[1].parse().ok()?;
+    let minute: u32 = captures[2].parse().ok()?;
+
+    if let Some(meridiem) = captures.get(3) {
+        let is_pm = meridiem.as_str().to_lowercase().starts_with('p');
+        if hour == 12 {
+            hour = 0;
+        }
+        if is_pm {
+            hour += 12;
+        }
+    }
+
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// Parse a month and day such as "Mon, Jun 2" from a Google Flights time string.
+///
+/// The year is taken from `reference`, rolling over to the next year for dates that
+/// would otherwise be more than a month before it (e.g. a "Jan 1" arrival for a Dec 31 search).
+pub fn parse_month_day(text: &str, reference: NaiveDate) -> Option<NaiveDate> {
+    static MONTH_DAY: OnceLock<Regex> = OnceLock::new();
+    let re = MONTH_DAY.get_or_init(|| Regex::new(r"\b(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)[a-z]*\.?\s+(\d{1,2})\b").unwrap());
+    let captures = re.captures(text)?;
+
+    let month = match &captures[1] {
+        "Jan" => 1, "Feb" => 2, "Mar" => 3, "Apr" => 4, "May" => 5, "Jun" => 6,
+        "Jul" => 7, "Aug" => 8, "Sep" => 9, "Oct" => 10, "Nov" => 11, _ => 12,
+    };
+    let day: u32 = captures[2].parse().ok()?;
+
+    let date = NaiveDate::from_ymd_opt(reference.year(), month, day)?;
+    if date < reference - chrono::Duration::days(31) {
+        NaiveDate::from_ymd_opt(reference.year() + 1, month, day)
+    } else {
+        Some(date)
+    }
+}
+
 /// Price information with amount and currency
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlightPrice {
@@ -167,6 +442,12 @@ pub struct FlightPrice {
     pub currency: String,
 }
 
+impl std::fmt::Display for FlightPrice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.currency, self.amount)
+    }
+}
+
 /// Individual flight leg information (for multi-leg flights)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlightLeg {
@@ -224,6 +505,214 @@ pub async fn get_flights(request: FlightSearchRequest) -> Result<FlightResult, F
     client.get_flights(request).await
 }
 
+/// Search flights from/to every airport near the requested ones and merge the results.
+///
+/// # Example
+/// ```rust,no_run
+/// use rust_flights::{get_flights_nearby, AirportExpansion, SearchOptions};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let request = SearchOptions::default().one_way_request("SFO", "JFK", "2025-09-08");
+/// let result = get_flights_nearby(request, &AirportExpansion::Metro, Duration::from_millis(1000)).await?;
+/// for flight in result.flights.iter().take(5) {
+///     println!("{:?} → {:?}: {}", flight.origin_airport, flight.destination_airport, flight.price);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn get_flights_nearby(
+    request: FlightSearchRequest,
+    expansion: &AirportExpansion,
+    request_delay: Duration,
+) -> Result<FlightResult, FlightError> {
+    let client = FlightClient::new().await?;
+    client.get_flights_nearby(request, expansion, request_delay).await
+}
+
+/// Search many candidate destinations from one origin and rank them by cheapest fare.
+///
+/// # Example
+/// ```rust,no_run
+/// use rust_flights::{explore, ExploreDestinations, ExploreRequest, Region, SearchOptions};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let request = ExploreRequest {
+///     origin: "SEA".to_string(),
+///     destinations: ExploreDestinations::Region(Region::Europe),
+///     start_date: "2025-10-06".to_string(),
+///     end_date: "2025-10-10".to_string(),
+///     round_trip: true,
+///     options: SearchOptions::default(),
+/// };
+/// let result = explore(&request).await?;
+/// for destination in result.destinations.iter().take(5) {
+///     println!("{}: {:?}", destination.destination, destination.cheapest_price);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn explore(request: &ExploreRequest) -> Result<ExploreResult, FlightError> {
+    let client = FlightClient::new().await?;
+    client.explore(request).await
+}
+
+/// Find the best place and date for travellers from several origins to meet.
+///
+/// # Example
+/// ```rust,no_run
+/// use rust_flights::{find_meeting_point, GroupOrigin, GroupRanking, GroupSearchRequest, SearchOptions};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let request = GroupSearchRequest {
+///     origins: vec![
+///         GroupOrigin::new("SEA", 2),
+///         GroupOrigin::new("New York", 3),
+///         GroupOrigin::new("LON", 1),
+///         GroupOrigin::new("BLR", 1),
+///     ],
+///     destinations: vec!["Lisbon".to_string(), "DXB".to_string(), "Reykjavik".to_string()],
+///     dates: vec!["2025-10-06".to_string()],
+///     nights: Some(4),
+///     ranking: GroupRanking::Fairness,
+///     options: SearchOptions::default(),
+/// };
+/// let result = find_meeting_point(&request).await?;
+/// if let Some(best) = result.best() {
+///     println!("Meet in {} on {}: {:?} total", best.destination, best.date, best.total_cost);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn find_meeting_point(request: &GroupSearchRequest) -> Result<GroupSearchResult, FlightError> {
+    let client = FlightClient::new().await?;
+    client.find_meeting_point(request).await
+}
+
+/// Compare the through fare with two self-transfer one-way tickets via each hub.
+///
+/// # Example
+/// ```rust,no_run
+/// use rust_flights::{search_split_tickets, SplitTicketRequest};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let hubs = vec!["DEN".to_string(), "ORD".to_string()];
+/// let result = search_split_tickets(&SplitTicketRequest::new("SEA", "MIA", "2025-09-01", hubs)).await?;
+/// if let Some(split) = result.cheapest() {
+///     println!("Via {} for {} (saves {:?})", split.hub, split.total_price, result.savings());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn search_split_tickets(request: &SplitTicketRequest) -> Result<SplitTicketResult, FlightError> {
+    let client = FlightClient::new().await?;
+    client.search_split_tickets(request).await
+}
+
+/// Price a round-trip request against its outbound and return as two one-way tickets.
+///
+/// # Example
+/// ```rust,no_run
+/// use rust_flights::{compare_round_trip, SearchOptions};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let request = SearchOptions::default().round_trip_request("SEA", "JFK", "2025-09-01", "2025-09-08");
+/// let comparison = compare_round_trip(request, Duration::from_millis(1000)).await?;
+/// println!("{:?} cheaper by {:?}", comparison.cheaper(), comparison.savings());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn compare_round_trip(
+    request: FlightSearchRequest,
+    request_delay: Duration,
+) -> Result<TripComparison, FlightError> {
+    let client = FlightClient::new().await?;
+    client.compare_round_trip(request, request_delay).await
+}
+
+/// Price an open-jaw or stopover itinerary as one multi-city ticket and as separate one-ways.
+///
+/// # Example
+/// ```rust,no_run
+/// use rust_flights::{search_itinerary, Itinerary, SearchOptions};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // SFO → HND, three days in Tokyo, then on to SIN
+/// let itinerary = Itinerary::stopover("SFO", "HND", "SIN", "2025-09-01", 3)?;
+/// let priced = search_itinerary(&itinerary, &SearchOptions::default()).await?;
+/// println!("Multi-city {:?}, separate tickets {:?}", priced.multi_city_price(), priced.separate_total());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn search_itinerary(itinerary: &Itinerary, options: &SearchOptions) -> Result<PricedItinerary, FlightError> {
+    let client = FlightClient::new().await?;
+    client.search_itinerary(itinerary, options).await
+}
+
+/// Search one-way flights for every date between `start_date` and `end_date` (inclusive)
+/// and summarise the cheapest option per day.
+///
+/// Requests are made sequentially, pausing `options.request_delay` between them.
+///
+/// # Example
+/// ```rust,no_run
+/// use rust_flights::{search_date_range, SearchOptions};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let result = search_date_range("LAX", "JFK", "2025-09-08", "2025-09-14", &SearchOptions::default()).await?;
+/// if let Some(day) = result.cheapest_day() {
+///     println!("Cheapest day: {} ({:?})", day.date, day.cheapest_price);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn search_date_range(
+    origin: &str,
+    destination: &str,
+    start_date: &str,
+    end_date: &str,
+    options: &SearchOptions,
+) -> Result<DateRangeResult, FlightError> {
+    let client = FlightClient::new().await?;
+    client.search_date_range(origin, destination, start_date, end_date, options).await
+}
+
+/// Build a departure × return date matrix of the cheapest round-trip prices for
+/// departures between `departure_start` and `departure_end` and stays of `nights` nights.
+///
+/// # Example
+/// ```rust,no_run
+/// use rust_flights::{search_price_matrix, SearchOptions};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let matrix = search_price_matrix("SFO", "HND", "2025-10-01", "2025-10-05", 5..=9, &SearchOptions::default()).await?;
+/// if let Some(cell) = matrix.cheapest() {
+///     println!("Cheapest: {} → {} for {:?}", cell.departure_date, cell.return_date, cell.price);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn search_price_matrix(
+    origin: &str,
+    destination: &str,
+    departure_start: &str,
+    departure_end: &str,
+    nights: std::ops::RangeInclusive<u32>,
+    options: &SearchOptions,
+) -> Result<PriceMatrix, FlightError> {
+    let client = FlightClient::new().await?;
+    client.search_price_matrix(origin, destination, departure_start, departure_end, nights, options).await
+}
+
 /// Legacy API function matching Python interface (deprecated)
 #[deprecated(since = "0.1.0", note = "Use get_flights(FlightSearchRequest) instead")]
 pub async fn get_flights_legacy(
@@ -276,10 +765,11 @@ pub struct CityFlightSearchRequest {
 }
 
 /// Search flights using city names instead of airport codes.
-/// This function automatically resolves city names to Freebase IDs using Wikidata.
+/// City names are resolved with [`ResolverChain::standard`]: the bundled city cache,
+/// the city store, Wikidata, then the bundled airport database.
 /// 
 /// # Example
-/// ```rust
+/// ```rust,no_run
 /// use rust_flights::{get_flights_by_city, CityFlightSearchRequest, CityFlightData, TripType, SeatClass, Passengers};
 /// 
 /// # #[tokio::main]
@@ -305,22 +795,74 @@ pub struct CityFlightSearchRequest {
 /// # }
 /// ```
 pub async fn get_flights_by_city(request: CityFlightSearchRequest) -> Result<FlightResult, FlightError> {
-    let wikidata_client = WikidataClient::new()?;
-    
+    get_flights_by_city_with(request, &ResolverChain::standard()?).await
+}
+
+/// Like [`get_flights_by_city`], but resolving city names with a custom
+/// [`CityResolver`], such as a [`ResolverChain`] with your own office registry in front.
+///
+/// # Example
+/// ```rust,no_run
+/// use rust_flights::get_flights_by_city_with;
+/// use rust_flights::resolver::{Location, LocationRegistry, ResolverChain};
+/// # use rust_flights::{CityFlightSearchRequest, CityFlightData, TripType, SeatClass, Passengers};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let offices = LocationRegistry::new()
+///     .with("HQ", Location::city("San Francisco", "/m/0d6lp"))
+///     .with("Berlin office", Location::airport("Berlin Brandenburg", "BER"));
+/// let resolver = ResolverChain::standard()?.first(offices);
+///
+/// let request = CityFlightSearchRequest {
+///     flights: vec![CityFlightData {
+///         date: "2025-08-15".to_string(),
+///         from_city: "HQ".to_string(),
+///         to_city: "Berlin office".to_string(),
+///         max_stops: Some(1),
+///         airlines: None,
+///         departure_time: None,
+///         arrival_time: None,
+///     }],
+///     trip_type: TripType::OneWay,
+///     passengers: Passengers::default(),
+///     seat_class: SeatClass::Economy,
+/// };
+///
+/// let result = get_flights_by_city_with(request, &resolver).await?;
+/// println!("Found {} flights", result.flights.len());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn get_flights_by_city_with(
+    request: CityFlightSearchRequest,
+    resolver: &dyn CityResolver,
+) -> Result<FlightResult, FlightError> {
+    let mut codes: HashMap<String, String> = HashMap::new();
+    for city in request.flights.iter().flat_map(|f| [&f.from_city, &f.to_city]) {
+        if !codes.contains_key(city) {
+            let location = resolver.resolve(city).await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| FlightError::city_not_found(city))?;
+            codes.insert(city.clone(), location.code);
+        }
+    }
+
+    get_flights(city_request_to_airports(request, |city| Ok(codes[city].clone()))?).await
+}
+
+/// Build an airport search from a city search, mapping each city name through `resolve`
+fn city_request_to_airports(
+    request: CityFlightSearchRequest,
+    mut resolve: impl FnMut(&str) -> Result<String, FlightError>,
+) -> Result<FlightSearchRequest, FlightError> {
     // Convert city names to Freebase IDs
     let mut airport_flights = Vec::new();
     
     for city_flight in request.flights {
-        // Resolve city names to Freebase IDs
-        let from_freebase_id = wikidata_client
-            .get_freebase_id_only(&city_flight.from_city)
-            .await
-            .map_err(|_| FlightError::CityNotFound(city_flight.from_city.clone()))?;
-            
-        let to_freebase_id = wikidata_client
-            .get_freebase_id_only(&city_flight.to_city)
-            .await
-            .map_err(|_| FlightError::CityNotFound(city_flight.to_city.clone()))?;
+        let from_freebase_id = resolve(&city_flight.from_city)?;
+        let to_freebase_id = resolve(&city_flight.to_city)?;
         
         // Create FlightData with Freebase IDs instead of airport codes
         let flight_data = FlightData {
@@ -337,22 +879,49 @@ pub async fn get_flights_by_city(request: CityFlightSearchRequest) -> Result<Fli
     }
     
     // Create regular flight search request with Freebase IDs
-    let airport_request = FlightSearchRequest {
+    Ok(FlightSearchRequest {
         flights: airport_flights,
         trip_type: request.trip_type,
         passengers: request.passengers,
         seat_class: request.seat_class,
-    };
-    
-    // Use existing flight search API
-    get_flights(airport_request).await
+    })
+}
+
+/// Resolve a city name to its Freebase ID, falling back to the city's main airport
+/// from the bundled airport database when Wikidata has no answer or is unreachable.
+///
+/// A city name shared by several places is reported as [`FlightError::AmbiguousCity`].
+pub(crate) async fn resolve_city(wikidata: &WikidataClient, city: &str) -> Result<String, FlightError> {
+    city_or_airport_fallback(city, wikidata.get_freebase_id_only(city).await)
+}
+
+/// Turn a Freebase ID lookup result into a location for Google Flights, applying the
+/// bundled airport fallback described on [`resolve_city`]
+fn city_or_airport_fallback(city: &str, lookup: Result<String, WikidataError>) -> Result<String, FlightError> {
+    match lookup {
+        Ok(freebase_id) => Ok(freebase_id),
+        Err(WikidataError::AmbiguousCity { name, candidates }) => Err(FlightError::AmbiguousCity { name, candidates }),
+        Err(e) => {
+            let (name, hint) = CityHint::parse(city);
+            let fallback = AirportDb::bundled().in_city(&name).into_iter().find(|airport| {
+                hint.allows_country(&airport.country)
+            });
+            match fallback {
+                Some(airport) => {
+                    eprintln!("⚠️  Warning: Wikidata lookup for '{}' failed ({}), using airport {}", city, e, airport.iata);
+                    Ok(airport.iata.clone())
+                }
+                None => Err(FlightError::city_not_found(city)),
+            }
+        }
+    }
 }
 
 /// **Phase 4: CONVENIENCE FUNCTION**
 /// Simple one-way city-based flight search with minimal parameters.
 /// 
 /// # Example
-/// ```rust
+/// ```rust,no_run
 /// use rust_flights::search_flights_between_cities;
 /// 
 /// # #[tokio::main]
@@ -390,6 +959,67 @@ pub async fn search_flights_between_cities(
     get_flights_by_city(request).await
 }
 
+/// Test fixtures: a nonstop flight adjusted with setters, e.g.
+/// `Flight::test("United", 320).route("SFO", "JFK").layovers(&["DEN"], "...")`
+#[cfg(test)]
+impl Flight {
+    pub(crate) fn test(name: &str, price: i32) -> Self {
+        Flight {
+            is_best: false,
+            name: name.to_string(),
+            departure: "6:00 AM on Fri, Aug 15".to_string(),
+            arrival: "2:30 PM on Fri, Aug 15".to_string(),
+            duration: "5 hr 30 min".to_string(),
+            stops: 0,
+            price: FlightPrice { amount: price, currency: "$".to_string() },
+            flight_legs: None,
+            origin_airport: None,
+            destination_airport: None,
+            flight_summary: None,
+            layovers: None,
+            layover_description: None,
+        }
+    }
+
+    pub(crate) fn best(mut self) -> Self {
+        self.is_best = true;
+        self
+    }
+
+    /// Local departure and arrival as Google shows them, and the total duration
+    pub(crate) fn schedule(mut self, departure: &str, arrival: &str, duration: &str) -> Self {
+        self.departure = departure.to_string();
+        self.arrival = arrival.to_string();
+        self.duration = duration.to_string();
+        self
+    }
+
+    pub(crate) fn route(mut self, origin: &str, destination: &str) -> Self {
+        self.origin_airport = Some(origin.to_string());
+        self.destination_airport = Some(destination.to_string());
+        self
+    }
+
+    /// Flight numbers of each leg, e.g. `&[("UA", "100"), ("UA", "200")]`
+    pub(crate) fn legs(mut self, legs: &[(&str, &str)]) -> Self {
+        self.flight_legs = Some(legs.iter()
+            .map(|(airline_code, flight_number)| FlightLeg {
+                airline_code: airline_code.to_string(),
+                flight_number: flight_number.to_string(),
+            })
+            .collect());
+        self
+    }
+
+    /// Layover airports in order with Google's layover description; sets `stops`
+    pub(crate) fn layovers(mut self, airports: &[&str], description: &str) -> Self {
+        self.stops = airports.len() as i32;
+        self.layovers = Some(airports.iter().map(|airport| airport.to_string()).collect());
+        self.layover_description = Some(description.to_string());
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -437,6 +1067,95 @@ mod tests {
         assert!("invalid".parse::<SeatClass>().is_err());
     }
 
+    #[test]
+    fn test_parse_duration_minutes() {
+        assert_eq!(parse_duration_minutes("5 hr 30 min"), Some(330));
+        assert_eq!(parse_duration_minutes("45 min"), Some(45));
+        assert_eq!(parse_duration_minutes("1 day 2 hr"), Some(26 * 60));
+        assert_eq!(parse_duration_minutes("Unknown"), None);
+    }
+
+    #[test]
+    fn test_parse_time_of_day() {
+        assert_eq!(parse_time_of_day("6:00 AM on Mon, Jun 2"), NaiveTime::from_hms_opt(6, 0, 0));
+        assert_eq!(parse_time_of_day("12:15 AM"), NaiveTime::from_hms_opt(0, 15, 0));
+        assert_eq!(parse_time_of_day("12:40 PM"), NaiveTime::from_hms_opt(12, 40, 0));
+        assert_eq!(parse_time_of_day("11:05 PM"), NaiveTime::from_hms_opt(23, 5, 0));
+        assert_eq!(parse_time_of_day("18:05"), NaiveTime::from_hms_opt(18, 5, 0));
+        assert_eq!(parse_time_of_day("Unknown"), None);
+    }
+
+    #[test]
+    fn test_parse_month_day() {
+        let reference = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        assert_eq!(parse_month_day("6:00 AM on Mon, Jun 2", reference), NaiveDate::from_ymd_opt(2025, 6, 2));
+        assert_eq!(parse_month_day("Sept 30", reference), NaiveDate::from_ymd_opt(2025, 9, 30));
+        assert_eq!(parse_month_day("6:00 AM", reference), None);
+
+        let new_year = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
+        assert_eq!(parse_month_day("1:10 AM on Thu, Jan 1", new_year), NaiveDate::from_ymd_opt(2026, 1, 1));
+    }
+
+    #[test]
+    fn test_flight_datetimes() {
+        let date = NaiveDate::from_ymd_opt(2025, 6, 2).unwrap();
+        let mut flight = Flight::test("Delta", 300).schedule("10:30 PM on Mon, Jun 2", "6:45 AM on Tue, Jun 3", "5 hr 15 min");
+        assert_eq!(flight.departure_datetime(date), date.and_hms_opt(22, 30, 0));
+        assert_eq!(flight.arrival_datetime(date), NaiveDate::from_ymd_opt(2025, 6, 3).unwrap().and_hms_opt(6, 45, 0));
+
+        // Without dates, an arrival earlier in the day than the departure is the next day
+        flight.departure = "10:30 PM".to_string();
+        flight.arrival = "6:45 AM".to_string();
+        assert_eq!(flight.arrival_datetime(date), NaiveDate::from_ymd_opt(2025, 6, 3).unwrap().and_hms_opt(6, 45, 0));
+        flight.arrival = "11:50 PM+1".to_string();
+        assert_eq!(flight.arrival_datetime(date), NaiveDate::from_ymd_opt(2025, 6, 3).unwrap().and_hms_opt(23, 50, 0));
+    }
+
+    #[test]
+    fn test_flight_layover_minutes() {
+        let flight = Flight::test("Air France", 900)
+            .schedule("6:00 AM", "11:30 PM", "25 hr 30 min")
+            .layovers(&["CDG", "BLR"], "Layover (1 of 2) is a 2 hr 15 min layover at Paris Charles de Gaulle Airport in Paris. Layover (2 of 2) is a 4 hr 40 min overnight layover at Kempegowda International Airport Bengaluru in Bengaluru.");
+
+        assert_eq!(flight.layover_minutes(), vec![135, 280]);
+        assert_eq!(flight.duration_minutes(), Some(1530));
+        assert!(flight.airline_codes().is_empty());
+    }
+
+    #[test]
+    fn test_search_path_keeps_stdout_clean() {
+        // Searches run under `--format json > flights.json`, so library code on the
+        // search path reports through stderr or return values, never stdout
+        let sources = [
+            ("lib.rs", include_str!("lib.rs")),
+            ("client.rs", include_str!("client.rs")),
+            ("protobuf.rs", include_str!("protobuf.rs")),
+            ("resolver.rs", include_str!("resolver.rs")),
+            ("airports.rs", include_str!("airports.rs")),
+            ("city_store.rs", include_str!("city_store.rs")),
+            ("explore.rs", include_str!("explore.rs")),
+            ("dates.rs", include_str!("dates.rs")),
+            ("compare.rs", include_str!("compare.rs")),
+            ("group.rs", include_str!("group.rs")),
+            ("split.rs", include_str!("split.rs")),
+            ("nearby.rs", include_str!("nearby.rs")),
+            ("itinerary.rs", include_str!("itinerary.rs")),
+        ];
+        for (file, source) in sources {
+            let code = source.split("\n#[cfg(test)]\nmod tests").next().unwrap();
+            for (number, line) in code.lines().enumerate() {
+                if line.trim_start().starts_with("//") {
+                    continue;
+                }
+                let stdout = line.replace("eprintln!(", "").replace("eprint!(", "");
+                assert!(
+                    !stdout.contains("println!(") && !stdout.contains("print!("),
+                    "{}:{} writes to stdout: {}", file, number + 1, line
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_passengers_default() {
         let passengers = Passengers::default();
@@ -1,12 +1,27 @@
 //! CLI interface for rust-flights
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use rust_flights::{
     get_flights, get_flights_by_city, search_flights_between_cities,
     FlightData, FlightSearchRequest, CityFlightData, CityFlightSearchRequest,
-    Passengers, SeatClass, TripType, TimeWindow
+    Passengers, SeatClass, TripType, TimeWindow, FlightFilter, SortKey,
+    search_date_range, search_price_matrix, SearchOptions, PriceMatrix, PriceTier,
+    get_flights_nearby, AirportExpansion, explore, ExploreDestinations, ExploreRequest, Region,
+    search_split_tickets, SplitTicketRequest, compare_round_trip, CheaperOption, FlightError,
+    WikidataClient, CacheAudit, CacheDiff, CacheIssue, SelectedFlight, build_itinerary_slices, itinerary_url,
+    parse_month_day
 };
+use rust_flights::city_audit::{audit_city_cache_online, validate_city_cache};
+use rust_flights::wikidata::{ambiguity_hint, bundled_city_cache};
+use rust_flights::filter::parse_clock_time;
+use rust_flights::calendar::flight_to_ics;
+use rust_flights::dates::parse_date;
+use rust_flights::export::{export_results, ExportFormat, ExportRows, SearchMetadata};
+use rust_flights::timezones::{parse_timezones, Tz};
+use std::collections::HashMap;
 use std::fs;
+use std::io::{IsTerminal, Write};
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "rust-flights")]
@@ -66,13 +81,37 @@ pub enum Commands {
         /// Trip type (one-way, round-trip)
         #[arg(long, default_value = "one-way")]
         trip_type: String,
+        /// Also search every airport within this many kilometres of the origin and destination
+        #[arg(long, value_name = "KM", conflicts_with = "metro")]
+        nearby: Option<f64>,
+        /// Also search the other airports in the origin's and destination's metro area (e.g. NYC, LON)
+        #[arg(long)]
+        metro: bool,
+        /// Also price the outbound and return as two one-way tickets and report which is cheaper
+        #[arg(long, requires = "return_date", conflicts_with_all = ["nearby", "metro"])]
+        compare_one_ways: bool,
+        /// Output format: table, json, csv or ndjson (default: table on a terminal, otherwise json)
+        #[arg(long)]
+        format: Option<String>,
+        /// Show or export at most this many flights
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+        /// Rows for csv/ndjson output: one per flight or per leg (flights, legs)
+        #[arg(long, default_value = "flights")]
+        rows: String,
+        /// Also show the top flights' times in these zones (comma-separated IANA names,
+        /// abbreviations such as PT/ET, or airport codes)
+        #[arg(long, value_name = "ZONES")]
+        timezones: Option<String>,
+        #[command(flatten)]
+        filter: FilterArgs,
     },
     /// Search for flights using city names (with Wikidata integration)
     CitySearch {
-        /// Origin city name (e.g., "London", "New York")
+        /// Origin city name (e.g., "London", "New York", or "Portland, US" to pick a country)
         #[arg(short, long)]
         from_city: String,
-        /// Destination city name (e.g., "Paris", "Tokyo")
+        /// Destination city name (e.g., "Paris", "Tokyo", or "Paris, FR" to pick a country)
         #[arg(short, long)]
         to_city: String,
         /// Departure date (YYYY-MM-DD)
@@ -114,6 +153,187 @@ pub enum Commands {
         /// Trip type (one-way, round-trip)
         #[arg(long, default_value = "one-way")]
         trip_type: String,
+        /// Output format: table, json, csv or ndjson (default: table on a terminal, otherwise json)
+        #[arg(long)]
+        format: Option<String>,
+        /// Show or export at most this many flights
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+        /// Rows for csv/ndjson output: one per flight or per leg (flights, legs)
+        #[arg(long, default_value = "flights")]
+        rows: String,
+        /// Also show the top flights' times in these zones (comma-separated IANA names,
+        /// abbreviations such as PT/ET, or airport codes)
+        #[arg(long, value_name = "ZONES")]
+        timezones: Option<String>,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Find the cheapest day to fly within a date range (one search per date)
+    FlexDates {
+        /// Origin airport code
+        #[arg(short, long)]
+        from: String,
+        /// Destination airport code
+        #[arg(short, long)]
+        to: String,
+        /// First departure date to search (YYYY-MM-DD)
+        #[arg(long)]
+        start: String,
+        /// Last departure date to search (YYYY-MM-DD)
+        #[arg(long)]
+        end: String,
+        /// Number of adults
+        #[arg(long, default_value = "1")]
+        adults: i32,
+        /// Number of children
+        #[arg(long, default_value = "0")]
+        children: i32,
+        /// Seat class (economy, premium-economy, business, first)
+        #[arg(long, default_value = "economy")]
+        class: String,
+        /// Maximum number of stops
+        #[arg(long)]
+        max_stops: Option<i32>,
+        /// Preferred airlines (comma-separated)
+        #[arg(long)]
+        airlines: Option<String>,
+        /// Delay between searches in milliseconds
+        #[arg(long, default_value = "1000")]
+        delay_ms: u64,
+        /// Output file for JSON results
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Show a departure × return date grid of the cheapest round-trip prices
+    PriceCalendar {
+        /// Origin airport code
+        #[arg(short, long)]
+        from: String,
+        /// Destination airport code
+        #[arg(short, long)]
+        to: String,
+        /// First departure date (YYYY-MM-DD)
+        #[arg(long)]
+        depart_start: String,
+        /// Last departure date (YYYY-MM-DD)
+        #[arg(long)]
+        depart_end: String,
+        /// Minimum trip length in nights
+        #[arg(long)]
+        min_nights: u32,
+        /// Maximum trip length in nights
+        #[arg(long)]
+        max_nights: u32,
+        /// Number of adults
+        #[arg(long, default_value = "1")]
+        adults: i32,
+        /// Number of children
+        #[arg(long, default_value = "0")]
+        children: i32,
+        /// Seat class (economy, premium-economy, business, first)
+        #[arg(long, default_value = "economy")]
+        class: String,
+        /// Maximum number of stops
+        #[arg(long)]
+        max_stops: Option<i32>,
+        /// Preferred airlines (comma-separated)
+        #[arg(long)]
+        airlines: Option<String>,
+        /// Delay between searches in milliseconds
+        #[arg(long, default_value = "1000")]
+        delay_ms: u64,
+        /// Disable colour in the grid
+        #[arg(long)]
+        no_color: bool,
+        /// Output file for JSON results
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Rank candidate destinations by their cheapest fare from one origin
+    Explore {
+        /// Origin airport code or city name
+        #[arg(short, long)]
+        from: String,
+        /// Candidate destinations: airport codes or city names (comma-separated)
+        #[arg(short, long, conflicts_with_all = ["all_cities", "region"])]
+        to: Option<String>,
+        /// Consider every city in the bundled city cache
+        #[arg(long, conflicts_with = "region")]
+        all_cities: bool,
+        /// Consider every bundled airport city in a region (north-america, south-america,
+        /// europe, middle-east, africa, asia, oceania)
+        #[arg(long)]
+        region: Option<String>,
+        /// Departure date, or first date of a one-way range (YYYY-MM-DD)
+        #[arg(long)]
+        start: String,
+        /// Return date, or last date of a one-way range (YYYY-MM-DD)
+        #[arg(long)]
+        end: String,
+        /// Search one-way flights on every date from --start to --end instead of a round trip
+        #[arg(long)]
+        one_way: bool,
+        /// Number of adults
+        #[arg(long, default_value = "1")]
+        adults: i32,
+        /// Number of children
+        #[arg(long, default_value = "0")]
+        children: i32,
+        /// Seat class (economy, premium-economy, business, first)
+        #[arg(long, default_value = "economy")]
+        class: String,
+        /// Maximum number of stops
+        #[arg(long)]
+        max_stops: Option<i32>,
+        /// Preferred airlines (comma-separated)
+        #[arg(long)]
+        airlines: Option<String>,
+        /// Delay between searches in milliseconds
+        #[arg(long, default_value = "1000")]
+        delay_ms: u64,
+        /// Output file for JSON results
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Compare the through fare with two self-transfer tickets via hub airports
+    SplitTicket {
+        /// Origin airport code
+        #[arg(short, long)]
+        from: String,
+        /// Destination airport code
+        #[arg(short, long)]
+        to: String,
+        /// Departure date (YYYY-MM-DD)
+        #[arg(short, long)]
+        date: String,
+        /// Hub airports to connect through (comma-separated, e.g. "DEN,ORD")
+        #[arg(long)]
+        via: String,
+        /// Minimum self-transfer time at the hub in minutes
+        #[arg(long, default_value = "180")]
+        min_connection: i64,
+        /// Maximum self-transfer time at the hub in minutes
+        #[arg(long, default_value = "720")]
+        max_connection: i64,
+        /// Number of adults
+        #[arg(long, default_value = "1")]
+        adults: i32,
+        /// Number of children
+        #[arg(long, default_value = "0")]
+        children: i32,
+        /// Seat class (economy, premium-economy, business, first)
+        #[arg(long, default_value = "economy")]
+        class: String,
+        /// Maximum number of stops on each ticket
+        #[arg(long)]
+        max_stops: Option<i32>,
+        /// Delay between searches in milliseconds
+        #[arg(long, default_value = "1000")]
+        delay_ms: u64,
+        /// Output file for JSON results
+        #[arg(short, long)]
+        output: Option<String>,
     },
     /// Quick city-to-city flight search
     QuickCity {
@@ -123,10 +343,185 @@ pub enum Commands {
         to_city: String,
         /// Departure date (YYYY-MM-DD)
         date: String,
-        /// Output file for JSON results
+        /// Output file for results
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Output format: table, json, csv or ndjson (default: table on a terminal, otherwise json)
+        #[arg(long)]
+        format: Option<String>,
+        /// Show or export at most this many flights
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+    },
+    /// Export a flight from saved search results (e.g. as calendar events)
+    Export {
+        /// JSON results file written by `search --output`
+        #[arg(short, long)]
+        input: String,
+        /// Export format: ics (one flight as calendar events), csv or ndjson
+        #[arg(long, default_value = "ics")]
+        format: String,
+        /// Departure date the results were searched for (YYYY-MM-DD)
+        #[arg(short, long)]
+        date: String,
+        /// Which flight to export (1 = first in the results); by default ics exports the
+        /// first flight and csv/ndjson export them all
+        #[arg(long)]
+        pick: Option<usize>,
+        /// Rows for csv/ndjson: one per flight or per leg (flights, legs)
+        #[arg(long, default_value = "flights")]
+        rows: String,
+        /// Output file (default: print to stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Print the Google Flights link for selected flights
+    Link {
+        /// A selected flight as FROM:TO:YYYY-MM-DD:AIRLINE:NUMBER (e.g. LAX:JFK:2025-08-15:AA:100);
+        /// repeat for each leg
+        #[arg(long = "leg", value_name = "LEG", required_unless_present = "from_results", conflicts_with = "from_results")]
+        legs: Vec<String>,
+        /// JSON results file written by `search --output` to link a flight from
+        #[arg(long, value_name = "FILE")]
+        from_results: Option<String>,
+        /// Which flight of --from-results to link (1 = first in the results)
+        #[arg(long, default_value = "1", requires = "from_results")]
+        pick: usize,
+        /// Departure date the results were searched for (YYYY-MM-DD); defaults to the
+        /// dates shown in the results
+        #[arg(short, long, requires = "from_results")]
+        date: Option<String>,
+        /// Number of adults
+        #[arg(long, default_value = "1")]
+        adults: i32,
+        /// Number of children
+        #[arg(long, default_value = "0")]
+        children: i32,
+        /// Number of infants in seat
+        #[arg(long, default_value = "0")]
+        infants_in_seat: i32,
+        /// Number of infants on lap
+        #[arg(long, default_value = "0")]
+        infants_on_lap: i32,
+        /// Seat class (economy, premium-economy, business, first)
+        #[arg(long, default_value = "economy")]
+        class: String,
+        /// Trip type (one-way, round-trip); by default one-way for --from-results, and
+        /// for --leg one-way for one leg or round-trip for two legs back to the first
+        /// origin. Multi-city links are not supported
+        #[arg(long)]
+        trip_type: Option<String>,
+    },
+    /// Check or regenerate the city name → Freebase ID cache
+    CityCache {
+        #[command(subcommand)]
+        action: CityCacheAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CityCacheAction {
+    /// Check for duplicate and malformed IDs (add --online to cross-check Wikidata)
+    Audit {
+        /// Cache JSON file to check instead of the bundled city_cache.json
+        #[arg(long)]
+        cache_file: Option<String>,
+        /// Also re-resolve every city via Wikidata and report mismatches
+        #[arg(long)]
+        online: bool,
+        /// Output file for the JSON audit report
         #[arg(short, long)]
         output: Option<String>,
     },
+    /// Re-resolve cities via Wikidata and write a JSON diff for review; nothing is overwritten
+    Regenerate {
+        /// Cache JSON file to regenerate instead of the bundled city_cache.json
+        #[arg(long)]
+        cache_file: Option<String>,
+        /// Comma-separated cities to add or re-check (default: every cached city)
+        #[arg(long)]
+        cities: Option<String>,
+        /// Output file for the JSON diff
+        #[arg(short, long, default_value = "city_cache.diff.json")]
+        output: String,
+        /// Also write the full regenerated cache to this file
+        #[arg(long)]
+        write_cache: Option<String>,
+    },
+}
+
+/// Client-side filtering and sorting options applied to search results
+#[derive(Args, Debug, Clone, Default)]
+pub struct FilterArgs {
+    /// Minimum price
+    #[arg(long)]
+    min_price: Option<i32>,
+    /// Maximum price
+    #[arg(long)]
+    max_price: Option<i32>,
+    /// Maximum total travel time in minutes
+    #[arg(long)]
+    max_duration: Option<i32>,
+    /// Maximum layover time in minutes
+    #[arg(long)]
+    max_layover: Option<i32>,
+    /// Only flights departing at or after this time (HH:MM)
+    #[arg(long)]
+    depart_after: Option<String>,
+    /// Only flights departing at or before this time (HH:MM)
+    #[arg(long)]
+    depart_before: Option<String>,
+    /// Only flights arriving at or after this time (HH:MM)
+    #[arg(long)]
+    arrive_after: Option<String>,
+    /// Only flights arriving at or before this time (HH:MM)
+    #[arg(long)]
+    arrive_before: Option<String>,
+    /// Airports to avoid as origin, destination or layover (comma-separated)
+    #[arg(long)]
+    exclude_airports: Option<String>,
+    /// Only keep flights operated entirely by these airlines (comma-separated)
+    #[arg(long)]
+    require_airlines: Option<String>,
+    /// Sort results by price, duration, departure, arrival or value
+    #[arg(long)]
+    sort: Option<String>,
+}
+
+impl FilterArgs {
+    /// Build the library filter and sort key from the command line options
+    fn build(&self) -> Result<(FlightFilter, Option<SortKey>), Box<dyn std::error::Error>> {
+        let mut filter = FlightFilter::new();
+        filter.min_price = self.min_price;
+        filter.max_price = self.max_price;
+        filter.max_duration_minutes = self.max_duration;
+        filter.max_layover_minutes = self.max_layover;
+        filter.departure_after = self.depart_after.as_deref().map(parse_clock_time).transpose()?;
+        filter.departure_before = self.depart_before.as_deref().map(parse_clock_time).transpose()?;
+        filter.arrival_after = self.arrive_after.as_deref().map(parse_clock_time).transpose()?;
+        filter.arrival_before = self.arrive_before.as_deref().map(parse_clock_time).transpose()?;
+
+        if let Some(airports) = &self.exclude_airports {
+            for airport in split_codes(airports) {
+                filter = filter.exclude_airport(airport);
+            }
+        }
+        if let Some(airlines) = &self.require_airlines {
+            filter = filter.require_airlines(split_codes(airlines));
+        }
+
+        let sort = self.sort.as_deref().map(str::parse::<SortKey>).transpose()?;
+        Ok((filter, sort))
+    }
+}
+
+/// Split a comma-separated list of codes, dropping empty entries
+fn split_codes(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
 }
 
 /// Common flight search parameters used by both airport and city searches
@@ -144,6 +539,94 @@ struct CommonSearchParams {
     pub arrival_time: Option<String>,
     pub output: Option<String>,
     pub trip_type: String,
+    pub format: Option<String>,
+    pub limit: Option<usize>,
+    pub rows: String,
+    pub timezones: Option<String>,
+    pub filter: FilterArgs,
+}
+
+impl CommonSearchParams {
+    /// Whether CSV/NDJSON rows are written to stdout, so progress and summaries
+    /// must go to stderr to keep the rows parseable
+    fn rows_on_stdout(&self) -> bool {
+        self.output.is_none()
+            && matches!(OutputFormat::parse(self.format.as_deref(), &self.rows, false), Ok(OutputFormat::Rows(..)))
+    }
+    
+    /// Print a progress line where it won't mix with the results
+    fn status(&self, message: &str) {
+        if self.rows_on_stdout() {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+    
+    /// The fixed search `quick-city` runs: one adult, economy, one-way, at most one stop
+    fn quick(date: String, output: Option<String>, format: Option<String>, limit: Option<usize>) -> Self {
+        CommonSearchParams {
+            date,
+            return_date: None,
+            adults: 1,
+            children: 0,
+            infants_in_seat: 0,
+            infants_on_lap: 0,
+            class: "economy".to_string(),
+            max_stops: Some(1),
+            airlines: None,
+            departure_time: None,
+            arrival_time: None,
+            output,
+            trip_type: "one-way".to_string(),
+            format,
+            limit,
+            rows: "flights".to_string(),
+            timezones: None,
+            filter: FilterArgs::default(),
+        }
+    }
+    
+    /// Search metadata repeated on every exported row
+    fn metadata(&self, from: &str, to: &str) -> SearchMetadata {
+        SearchMetadata {
+            return_date: self.return_date.clone(),
+            trip_type: if self.return_date.is_some() { "round-trip".to_string() } else { self.trip_type.clone() },
+            seat_class: self.class.clone(),
+            adults: self.adults,
+            children: self.children,
+            ..SearchMetadata::new(from, to, &self.date)
+        }
+    }
+}
+
+/// How search results are written
+enum OutputFormat {
+    /// An aligned table for reading in a terminal
+    Table,
+    /// The full `FlightResult` as pretty JSON
+    Json,
+    /// Flat rows for spreadsheets and data pipelines
+    Rows(ExportFormat, ExportRows),
+}
+
+impl OutputFormat {
+    /// Parse `--format`; without one, a table when writing to a terminal and JSON otherwise
+    fn parse(format: Option<&str>, rows: &str, to_terminal: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        match format.map(str::to_lowercase).as_deref() {
+            None if to_terminal => Ok(OutputFormat::Table),
+            None | Some("json") => Ok(OutputFormat::Json),
+            Some("table") => Ok(OutputFormat::Table),
+            Some(other) => Ok(OutputFormat::Rows(other.parse()?, rows.parse()?)),
+        }
+    }
+}
+
+/// How an airport search should be run
+enum AirportSearchMode {
+    Standard,
+    Nearby(AirportExpansion),
+    CompareOneWays,
 }
 
 /// Execute a flight search using airport codes
@@ -151,7 +634,9 @@ async fn execute_airport_search(
     from: String,
     to: String,
     params: CommonSearchParams,
+    mode: AirportSearchMode,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let metadata = params.metadata(&from, &to);
     let (departure_time_window, arrival_time_window, parsed_airlines) = parse_common_params(&params)?;
     
     // Build flight data
@@ -191,9 +676,135 @@ async fn execute_airport_search(
         seat_class,
     };
     
-    println!("Searching for flights...");
-    let result = get_flights(request).await?;
-    handle_flight_results(result, params.output).await
+    let result = match mode {
+        AirportSearchMode::CompareOneWays => {
+            return execute_round_trip_comparison(request, params.output).await;
+        }
+        AirportSearchMode::Nearby(expansion) => {
+            let origins = expansion.expand(&request.flights[0].from_airport);
+            let destinations = expansion.expand(&request.flights[0].to_airport);
+            params.status(&format!(
+                "Searching for flights from {} to {}...",
+                origins.join("/"),
+                destinations.join("/")
+            ));
+            get_flights_nearby(request, &expansion, SearchOptions::default().request_delay).await?
+        }
+        AirportSearchMode::Standard => {
+            params.status("Searching for flights...");
+            get_flights(request).await?
+        }
+    };
+    handle_flight_results(result, params, metadata).await
+}
+
+/// Price a round trip against its outbound and return booked as two one-ways
+async fn execute_round_trip_comparison(
+    request: FlightSearchRequest,
+    output: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Comparing the round trip with two one-way tickets...");
+    let comparison = compare_round_trip(request, SearchOptions::default().request_delay).await?;
+    
+    if let Some(output_file) = output {
+        fs::write(&output_file, serde_json::to_string_pretty(&comparison)?)?;
+        println!("Results saved to {}", output_file);
+    }
+    
+    let describe = |flight: &Option<rust_flights::Flight>, error: &Option<String>| match (flight, error) {
+        (Some(flight), _) => format!("{} - {} ({})", flight.price, flight.name, flight.departure),
+        (None, Some(error)) => format!("search failed: {}", error),
+        (None, None) => "no flights found".to_string(),
+    };
+    println!("\nRound trip:  {}", describe(&comparison.round_trip, &comparison.round_trip_error));
+    println!("Outbound:    {}", describe(&comparison.outbound, &comparison.outbound_error));
+    println!("Return:      {}", describe(&comparison.return_flight, &comparison.return_error));
+    
+    let currency = comparison.round_trip.as_ref().map(|f| f.price.currency.as_str()).unwrap_or("");
+    match (comparison.cheaper(), comparison.savings()) {
+        (Some(CheaperOption::RoundTrip), Some(savings)) => println!("\nBook the round trip: {}{} cheaper", currency, savings),
+        (Some(CheaperOption::OneWays), Some(savings)) => println!("\nBook two one-ways: {}{} cheaper", currency, savings),
+        (Some(CheaperOption::Same), _) => println!("\nBoth options cost the same"),
+        _ => println!("\nCould not price both options"),
+    }
+    
+    Ok(())
+}
+
+/// Load a cache JSON file, or the bundled cache
+fn load_city_cache(cache_file: Option<&str>) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+    match cache_file {
+        Some(path) => Ok(serde_json::from_str(&fs::read_to_string(path)?)?),
+        None => Ok(bundled_city_cache().clone()),
+    }
+}
+
+/// Print the issues found by a city cache audit
+fn print_cache_issues(audit: &CacheAudit) {
+    println!("\nChecked {} cities, found {} issue(s)", audit.checked, audit.issues.len());
+    for issue in &audit.issues {
+        match issue {
+            CacheIssue::DuplicateId { freebase_id, names } => println!("  ❌ {} shared by {}", freebase_id, names.join(", ")),
+            CacheIssue::MalformedId { name, freebase_id } => println!("  ❌ {}: malformed ID {:?}", name, freebase_id),
+            CacheIssue::BadName { name } => println!("  ❌ Bad city name {:?}", name),
+            CacheIssue::Mismatch { name, cached, wikidata } => println!("  ⚠️  {}: cached {}, Wikidata {}", name, cached, wikidata),
+            CacheIssue::Unresolved { name } => println!("  ⚠️  {}: not resolved by Wikidata", name),
+        }
+    }
+}
+
+/// Audit or regenerate the city cache
+async fn execute_city_cache(action: CityCacheAction) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        CityCacheAction::Audit { cache_file, online, output } => {
+            let cache = load_city_cache(cache_file.as_deref())?;
+            let audit = if online {
+                audit_city_cache_online(&WikidataClient::new()?, &cache).await?
+            } else {
+                CacheAudit { checked: cache.len(), issues: validate_city_cache(&cache), diff: None }
+            };
+            print_cache_issues(&audit);
+            
+            if let Some(output_file) = output {
+                fs::write(&output_file, serde_json::to_string_pretty(&audit)?)?;
+                println!("Audit report saved to {}", output_file);
+            }
+            if !audit.issues.is_empty() {
+                return Err(format!("{} issue(s) found", audit.issues.len()).into());
+            }
+        }
+        CityCacheAction::Regenerate { cache_file, cities, output, write_cache } => {
+            let cache = load_city_cache(cache_file.as_deref())?;
+            let names: Vec<String> = match cities {
+                Some(cities) => cities.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect(),
+                None => cache.keys().cloned().collect(),
+            };
+            let subset: HashMap<String, String> = cache.iter()
+                .filter(|(name, _)| names.contains(name))
+                .map(|(name, id)| (name.clone(), id.clone()))
+                .collect();
+            
+            let client = WikidataClient::new()?;
+            let regenerated = client.populate_cache_from_cities(names.iter().map(|n| n.as_str()).collect()).await?;
+            let diff = CacheDiff::between(&subset, &regenerated);
+            
+            println!("\n{} added, {} changed, {} unresolved", diff.added.len(), diff.changed.len(), diff.unresolved.len());
+            for (name, (cached, wikidata)) in &diff.changed {
+                println!("  ~ {}: {} → {}", name, cached, wikidata);
+            }
+            for (name, id) in &diff.added {
+                println!("  + {}: {}", name, id);
+            }
+            
+            fs::write(&output, serde_json::to_string_pretty(&diff)?)?;
+            println!("Diff saved to {} for review", output);
+            if let Some(cache_output) = write_cache {
+                fs::write(&cache_output, serde_json::to_string_pretty(&diff.apply(&cache))?)?;
+                println!("Regenerated cache saved to {}", cache_output);
+            }
+        }
+    }
+    Ok(())
 }
 
 /// Execute a flight search using city names
@@ -202,6 +813,7 @@ async fn execute_city_search(
     to_city: String,
     params: CommonSearchParams,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let metadata = params.metadata(&from_city, &to_city);
     let (departure_time_window, arrival_time_window, parsed_airlines) = parse_common_params(&params)?;
     
     // Build city flight data
@@ -241,15 +853,35 @@ async fn execute_city_search(
         seat_class,
     };
     
-    println!("Searching for flights using city names (resolving via Wikidata)...");
-    let result = get_flights_by_city(request).await?;
-    handle_flight_results(result, params.output).await
+    params.status("Searching for flights using city names (resolving via Wikidata)...");
+    let result = match get_flights_by_city(request).await {
+        Err(FlightError::AmbiguousCity { name, candidates }) => {
+            println!("\n\"{}\" matches several cities:", name);
+            for candidate in &candidates {
+                println!("  • {}", candidate.describe());
+            }
+            println!("To pick one, {}", ambiguity_hint(&name, &candidates));
+            return Err(format!("City '{}' is ambiguous", name).into());
+        }
+        Err(FlightError::CityNotFound { name, suggestions }) if !suggestions.is_empty() => {
+            println!("\nCity \"{}\" not found. Did you mean:", name);
+            for suggestion in &suggestions {
+                println!("  • {}", suggestion);
+            }
+            return Err(format!("City '{}' not found", name).into());
+        }
+        result => result?,
+    };
+    handle_flight_results(result, params, metadata).await
 }
 
+/// Parsed departure window, arrival window and airline list
+type ParsedCommonParams = (Option<TimeWindow>, Option<TimeWindow>, Option<Vec<String>>);
+
 /// Parse common parameters shared by both search types
 fn parse_common_params(
     params: &CommonSearchParams,
-) -> Result<(Option<TimeWindow>, Option<TimeWindow>, Option<Vec<String>>), Box<dyn std::error::Error>> {
+) -> Result<ParsedCommonParams, Box<dyn std::error::Error>> {
     // Parse airlines
     let parsed_airlines = params
         .airlines
@@ -282,44 +914,535 @@ fn build_passengers(params: &CommonSearchParams) -> Passengers {
     }
 }
 
-/// Handle flight search results (output and summary)
-async fn handle_flight_results(
-    result: rust_flights::FlightResult,
+/// Search every date in a range and print a per-day price summary
+async fn execute_date_range_search(
+    from: String,
+    to: String,
+    start: String,
+    end: String,
+    options: SearchOptions,
     output: Option<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Output results
-    let json = serde_json::to_string_pretty(&result)?;
+    println!("Searching {} → {} for every date from {} to {}...", from, to, start, end);
+    let result = search_date_range(&from, &to, &start, &end, &options).await?;
     
     if let Some(output_file) = output {
-        fs::write(&output_file, &json)?;
+        fs::write(&output_file, serde_json::to_string_pretty(&result)?)?;
         println!("Results saved to {}", output_file);
+    }
+    
+    println!("\n{:<12} {:>10} {:>8} {:<10} Cheapest flight", "Date", "Price", "Flights", "Level");
+    for day in &result.days {
+        match (&day.cheapest_price, &day.error) {
+            (Some(price), _) => println!(
+                "{:<12} {:>10} {:>8} {:<10} {}",
+                day.date,
+                price.to_string(),
+                day.flight_count,
+                day.current_price,
+                day.cheapest_flight.as_ref().map(|f| f.name.as_str()).unwrap_or(""),
+            ),
+            (None, Some(error)) => println!("{:<12} {:>10} {:>8} {:<10} {}", day.date, "-", 0, "-", error),
+            (None, None) => println!("{:<12} {:>10} {:>8} {:<10}", day.date, "-", day.flight_count, day.current_price),
+        }
+    }
+    
+    match result.cheapest_day() {
+        Some(day) => println!(
+            "\nCheapest day: {} at {}",
+            day.date,
+            day.cheapest_price.as_ref().map(|p| p.to_string()).unwrap_or_default(),
+        ),
+        None => println!("\nNo prices found in this date range"),
+    }
+    
+    Ok(())
+}
+
+/// Search every candidate destination and print them ranked by cheapest fare
+async fn execute_explore(request: ExploreRequest, output: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    if request.round_trip {
+        println!("Exploring round trips from {} ({} → {})...", request.origin, request.start_date, request.end_date);
     } else {
-        println!("{}", json);
+        println!("Exploring one-way flights from {} between {} and {}...", request.origin, request.start_date, request.end_date);
+    }
+    let result = explore(&request).await?;
+    
+    if let Some(output_file) = output {
+        fs::write(&output_file, serde_json::to_string_pretty(&result)?)?;
+        println!("Results saved to {}", output_file);
     }
     
-    // Print summary
-    println!("\nSummary:");
-    println!("Current price level: {}", result.current_price);
-    println!("Found {} flights", result.flights.len());
+    println!("\n{:>4} {:<24} {:>10} {:<12} Best flight", "#", "Destination", "Price", "Date");
+    for (i, destination) in result.destinations.iter().enumerate() {
+        match (&destination.cheapest_price, &destination.error) {
+            (Some(price), _) => println!(
+                "{:>4} {:<24} {:>10} {:<12} {}",
+                i + 1,
+                destination.destination,
+                price.to_string(),
+                destination.cheapest_date.as_deref().unwrap_or(""),
+                destination.best_flight.as_ref().map(|f| f.name.as_str()).unwrap_or(""),
+            ),
+            (None, Some(error)) => println!("{:>4} {:<24} {:>10} {:<12} {}", i + 1, destination.destination, "-", "-", error),
+            (None, None) => println!("{:>4} {:<24} {:>10} {:<12} No flights found", i + 1, destination.destination, "-", "-"),
+        }
+    }
     
-    if !result.flights.is_empty() {
-        let best_flight = &result.flights[0];
-        println!("Best flight: {} - {}", best_flight.name, best_flight.price);
+    match result.cheapest() {
+        Some(destination) => println!(
+            "\nCheapest destination: {} at {}",
+            destination.destination,
+            destination.cheapest_price.as_ref().map(|p| p.to_string()).unwrap_or_default(),
+        ),
+        None => println!("\nNo prices found for any destination"),
     }
     
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
+/// Search split tickets via each hub and print them next to the through fare
+async fn execute_split_ticket(request: SplitTicketRequest, output: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    println!(
+        "Searching {} → {} on {} direct and via {}...",
+        request.origin, request.destination, request.date, request.hubs.join(", ")
+    );
+    let result = search_split_tickets(&request).await?;
     
-    match cli.command {
-        Commands::Search {
-            from,
-            to,
-            date,
-            return_date,
+    if let Some(output_file) = output {
+        fs::write(&output_file, serde_json::to_string_pretty(&result)?)?;
+        println!("Results saved to {}", output_file);
+    }
+    
+    match (&result.direct, &result.direct_error) {
+        (Some(direct), _) => println!("\nThrough fare: {} - {} ({})", direct.name, direct.price, direct.duration),
+        (None, Some(error)) => println!("\nThrough fare: search failed: {}", error),
+        (None, None) => println!("\nThrough fare: no flights found"),
+    }
+    
+    if result.itineraries.is_empty() {
+        println!("No split-ticket itineraries with a {}-{} minute connection", request.min_connection_minutes, request.max_connection_minutes);
+    } else {
+        println!("\n{:<5} {:>10} {:>11} {:<28} Second ticket", "Hub", "Price", "Connection", "First ticket");
+        for itinerary in result.itineraries.iter().take(10) {
+            println!(
+                "{:<5} {:>10} {:>8} min {:<28} {} {}",
+                itinerary.hub,
+                itinerary.total_price.to_string(),
+                itinerary.connection_minutes,
+                format!("{} {}", itinerary.first.name, itinerary.first.departure),
+                itinerary.second.name,
+                itinerary.second.departure,
+            );
+        }
+    }
+    
+    match result.savings() {
+        Some(savings) if savings > 0 => println!("\nSplitting saves {} over the through fare", savings),
+        Some(_) => println!("\nThe through fare is cheaper"),
+        None => {}
+    }
+    
+    Ok(())
+}
+
+/// Build the price matrix for a round trip and print it as a grid
+async fn execute_price_calendar(
+    from: String,
+    to: String,
+    departure_range: (String, String),
+    nights: std::ops::RangeInclusive<u32>,
+    options: SearchOptions,
+    color: bool,
+    output: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (depart_start, depart_end) = departure_range;
+    println!(
+        "Building price calendar {} ⇄ {} for departures {} to {}, {}-{} nights...",
+        from, to, depart_start, depart_end, nights.start(), nights.end()
+    );
+    let matrix = search_price_matrix(&from, &to, &depart_start, &depart_end, nights, &options).await?;
+    
+    if let Some(output_file) = output {
+        fs::write(&output_file, serde_json::to_string_pretty(&matrix)?)?;
+        println!("Results saved to {}", output_file);
+    }
+    
+    println!("\n{}", render_price_matrix(&matrix, color));
+    
+    match matrix.cheapest() {
+        Some(cell) => println!(
+            "Cheapest: depart {} return {} ({} nights) at {}",
+            cell.departure_date,
+            cell.return_date,
+            cell.nights,
+            cell.price.as_ref().map(|p| p.to_string()).unwrap_or_default(),
+        ),
+        None => println!("No prices found"),
+    }
+    
+    Ok(())
+}
+
+/// Render a price matrix as a grid with departure dates as rows and return dates
+/// as columns. Prices are coloured green/yellow/red by tier and the cheapest
+/// combination is marked with `*`.
+fn render_price_matrix(matrix: &PriceMatrix, color: bool) -> String {
+    const CELL_WIDTH: usize = 9;
+    let short_date = |date: &str| date.get(5..).unwrap_or(date).to_string();
+    let cheapest = matrix.cheapest();
+    
+    let mut grid = format!("{:<12}", "Depart\\Ret");
+    for return_date in &matrix.return_dates {
+        grid.push_str(&format!("{:>width$}", short_date(return_date), width = CELL_WIDTH));
+    }
+    grid.push('\n');
+    
+    for departure_date in &matrix.departure_dates {
+        grid.push_str(&format!("{:<12}", departure_date));
+        for return_date in &matrix.return_dates {
+            let cell = match matrix.get(departure_date, return_date) {
+                Some(cell) => cell,
+                None => {
+                    grid.push_str(&" ".repeat(CELL_WIDTH));
+                    continue;
+                }
+            };
+            let amount = match cell.amount() {
+                Some(amount) => amount,
+                None => {
+                    grid.push_str(&format!("{:>width$}", "-", width = CELL_WIDTH));
+                    continue;
+                }
+            };
+            
+            let is_cheapest = cheapest.is_some_and(|c| std::ptr::eq(c, cell));
+            let text = format!("{}{}{}", if is_cheapest { "*" } else { "" }, cell.price.as_ref().map(|p| p.currency.as_str()).unwrap_or(""), amount);
+            let padded = format!("{:>width$}", text, width = CELL_WIDTH);
+            
+            if color {
+                let code = match matrix.tier(amount) {
+                    Some(PriceTier::Cheap) => "32",
+                    Some(PriceTier::Moderate) => "33",
+                    Some(PriceTier::Expensive) | None => "31",
+                };
+                let weight = if is_cheapest { "1;" } else { "" };
+                grid.push_str(&format!("\x1b[{}{}m{}\x1b[0m", weight, code, padded));
+            } else {
+                grid.push_str(&padded);
+            }
+        }
+        grid.push('\n');
+    }
+    
+    grid
+}
+
+/// Render search results as an aligned table with the current price level and a
+/// `★` on Google's best flights, which are bold when `color` is set. `matched` is
+/// the number of flights before `--limit` was applied.
+fn render_flight_table(
+    result: &rust_flights::FlightResult,
+    matched: usize,
+    search_date: Option<chrono::NaiveDate>,
+    color: bool,
+) -> String {
+    const MAX_AIRLINE_WIDTH: usize = 28;
+    let paint = |text: String, code: &str| if color { format!("\x1b[{}m{}\x1b[0m", code, text) } else { text };
+    let hours = |minutes: i32| format!("{}h {:02}m", minutes / 60, minutes % 60);
+    
+    let level_code = match result.current_price.as_str() {
+        "low" => "32",
+        "typical" => "33",
+        "high" => "31",
+        _ => "0",
+    };
+    let mut table = format!(
+        "Prices are currently {} · showing {} of {} flights\n\n",
+        paint(result.current_price.clone(), &format!("1;{}", level_code)),
+        result.flights.len(),
+        matched,
+    );
+    if result.flights.is_empty() {
+        return table;
+    }
+    
+    let header = ["", "#", "Airline", "Depart", "Arrive", "Duration", "Stops", "Layovers", "Price"];
+    let rows: Vec<[String; 9]> = result.flights.iter()
+        .enumerate()
+        .map(|(i, flight)| {
+            let mut airline = flight.name.clone();
+            if airline.chars().count() > MAX_AIRLINE_WIDTH {
+                airline = airline.chars().take(MAX_AIRLINE_WIDTH - 1).collect::<String>() + "…";
+            }
+            let (depart, arrive) = match search_date.and_then(|date| Some((flight.departure_datetime(date)?, flight.arrival_datetime(date)?))) {
+                Some((departure, arrival)) => {
+                    let days = (arrival.date() - departure.date()).num_days();
+                    let marker = if days > 0 { format!("+{}", days) } else { String::new() };
+                    (departure.format("%H:%M").to_string(), format!("{}{}", arrival.format("%H:%M"), marker))
+                }
+                None => (flight.departure.clone(), flight.arrival.clone()),
+            };
+            let stops = match flight.stops {
+                0 => "Nonstop".to_string(),
+                1 => "1 stop".to_string(),
+                n => format!("{} stops", n),
+            };
+            let layover_minutes = flight.layover_minutes();
+            let layovers = flight.layovers.as_deref().unwrap_or_default()
+                .iter()
+                .enumerate()
+                .map(|(j, code)| match layover_minutes.get(j) {
+                    Some(minutes) => format!("{} {}", code, hours(*minutes)),
+                    None => code.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            [
+                if flight.is_best { "★".to_string() } else { String::new() },
+                (i + 1).to_string(),
+                airline,
+                depart,
+                arrive,
+                flight.duration_minutes().map(hours).unwrap_or_else(|| flight.duration.clone()),
+                stops,
+                layovers,
+                flight.price.to_string(),
+            ]
+        })
+        .collect();
+    
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| rows.iter().map(|row| row[column].chars().count()).chain([header[column].chars().count()]).max().unwrap_or(0))
+        .collect();
+    let line = |cells: &[String]| -> String {
+        cells.iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(column, (cell, width))| {
+                let padding = " ".repeat(width - cell.chars().count());
+                // Numbers and prices are right-aligned
+                if column == 1 || column == header.len() - 1 { format!("{}{}", padding, cell) } else { format!("{}{}", cell, padding) }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    
+    let header: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+    table.push_str(&paint(line(&header), "1"));
+    table.push('\n');
+    for (row, flight) in rows.iter().zip(&result.flights) {
+        let text = line(row);
+        table.push_str(&if flight.is_best { paint(text, "1") } else { text });
+        table.push('\n');
+    }
+    table
+}
+
+/// Export flights from a saved results file
+fn execute_export(
+    input: &str,
+    format: &str,
+    date: &str,
+    pick: Option<usize>,
+    rows: &str,
+    output: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut result: rust_flights::FlightResult = serde_json::from_str(&fs::read_to_string(input)?)?;
+    if let Some(pick) = pick {
+        let flight = pick.checked_sub(1)
+            .and_then(|index| result.flights.get(index))
+            .ok_or_else(|| format!("--pick {} is out of range: {} has {} flights", pick, input, result.flights.len()))?;
+        result.flights = vec![flight.clone()];
+    }
+
+    let exported = match format.to_lowercase().as_str() {
+        "ics" => {
+            let flight = result.flights.first().ok_or_else(|| format!("{} has no flights", input))?;
+            flight_to_ics(flight, parse_date(date)?)?
+        }
+        other => {
+            // The results file does not record the search, so take its airports from the flights
+            let first = result.flights.first();
+            let airport = |code: Option<&Option<String>>| code.cloned().flatten().unwrap_or_default();
+            let metadata = SearchMetadata::new(
+                &airport(first.map(|f| &f.origin_airport)),
+                &airport(first.map(|f| &f.destination_airport)),
+                date,
+            );
+            export_results(&result, &metadata, other.parse()?, rows.parse()?)?
+        }
+    };
+
+    match output {
+        Some(output_file) => {
+            fs::write(&output_file, exported)?;
+            println!("Exported to {}", output_file);
+        }
+        None => print!("{}", exported),
+    }
+    Ok(())
+}
+
+/// The flights to link as slices of segments: each `--leg` value on its own, or
+/// the segments of one flight picked from a results file
+fn selected_flights(
+    legs: &[String],
+    from_results: Option<&str>,
+    pick: usize,
+    date: Option<&str>,
+) -> Result<Vec<Vec<SelectedFlight>>, Box<dyn std::error::Error>> {
+    let Some(path) = from_results else {
+        return Ok(legs.iter().map(|leg| leg.parse().map(|leg| vec![leg])).collect::<Result<_, _>>()?);
+    };
+    
+    let result: rust_flights::FlightResult = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let flight = pick.checked_sub(1)
+        .and_then(|index| result.flights.get(index))
+        .ok_or_else(|| format!("--pick {} is out of range: {} has {} flights", pick, path, result.flights.len()))?;
+    let search_date = match date {
+        Some(date) => parse_date(date)?,
+        // Results show dates without a year; searches are for upcoming flights
+        None => {
+            let today = chrono::Local::now().date_naive();
+            parse_month_day(&flight.departure, today)
+                .ok_or_else(|| format!("'{}' has no date, pass the search date with --date", flight.departure))?
+        }
+    };
+    Ok(vec![SelectedFlight::from_flight(flight, "", "", search_date)?])
+}
+
+/// The `--trip-type` given, else one-way for a flight from `--from-results`, else
+/// inferred from the legs
+fn link_trip_type(trip_type: Option<&str>, from_results: bool, slices: &[Vec<SelectedFlight>]) -> Result<TripType, FlightError> {
+    match trip_type {
+        Some(trip_type) => trip_type.parse::<TripType>(),
+        None if from_results => Ok(TripType::OneWay),
+        None => infer_trip_type(slices),
+    }
+}
+
+/// One-way for a single leg, round-trip for two legs ending where they started;
+/// other chains would be multi-city trips, which links do not support
+fn infer_trip_type(slices: &[Vec<SelectedFlight>]) -> Result<TripType, FlightError> {
+    let first = slices.first().and_then(|slice| slice.first());
+    let last = slices.last().and_then(|slice| slice.last());
+    match (slices.len(), first, last) {
+        (1, _, _) => Ok(TripType::OneWay),
+        (2, Some(first), Some(last)) if first.from_airport == last.to_airport => Ok(TripType::RoundTrip),
+        _ => Err(FlightError::ParseError(format!(
+            "{} legs are neither a one-way nor a round trip; multi-city links are not supported, link each leg on its own",
+            slices.len()
+        ))),
+    }
+}
+
+/// Handle flight search results (output and summary)
+async fn handle_flight_results(
+    result: rust_flights::FlightResult,
+    params: CommonSearchParams,
+    metadata: SearchMetadata,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let to_terminal = params.output.is_none() && std::io::stdout().is_terminal();
+    if params.rows_on_stdout() {
+        write_flight_results(result, &params, &metadata, to_terminal, &mut std::io::stdout(), &mut std::io::stderr())
+    } else {
+        write_flight_results(result, &params, &metadata, to_terminal, &mut std::io::stdout(), &mut std::io::stdout())
+    }
+}
+
+/// Filter, sort and write search results to `out` (or `--output`), with the filter
+/// note, summary and schedule written to `notes`
+fn write_flight_results(
+    result: rust_flights::FlightResult,
+    params: &CommonSearchParams,
+    metadata: &SearchMetadata,
+    to_terminal: bool,
+    out: &mut dyn Write,
+    notes: &mut dyn Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let format = OutputFormat::parse(params.format.as_deref(), &params.rows, to_terminal)?;
+    let zones = params.timezones.as_deref().map(parse_timezones).transpose()?;
+
+    // Apply client-side filtering and sorting
+    let (filter, sort) = params.filter.build()?;
+    let found = result.flights.len();
+    let mut result = result.filtered(&filter);
+    if !filter.is_empty() {
+        writeln!(notes, "Filtered {} flights down to {}", found, result.flights.len())?;
+    }
+    // Tables are sorted by price unless another order was asked for
+    match (sort, &format) {
+        (Some(sort), _) => result = result.sorted_by(&sort),
+        (None, OutputFormat::Table) => result = result.sorted_by(&SortKey::Price),
+        (None, _) => {}
+    }
+    let matched = result.flights.len();
+    if let Some(limit) = params.limit {
+        result.flights.truncate(limit);
+    }
+    
+    // Output results
+    let output = match format {
+        OutputFormat::Table => {
+            let color = to_terminal && std::env::var_os("NO_COLOR").is_none();
+            render_flight_table(&result, matched, parse_date(&params.date).ok(), color)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&result)? + "\n",
+        OutputFormat::Rows(format, rows) => export_results(&result, metadata, format, rows)?,
+    };
+    
+    if let Some(output_file) = &params.output {
+        fs::write(output_file, &output)?;
+        writeln!(notes, "Results saved to {}", output_file)?;
+    } else {
+        write!(out, "{}", output)?;
+    }
+    
+    // Print summary (the table has its own)
+    if !matches!(format, OutputFormat::Table) {
+        writeln!(notes, "\nSummary:")?;
+        writeln!(notes, "Current price level: {}", result.current_price)?;
+        writeln!(notes, "Found {} flights", result.flights.len())?;
+        
+        if !result.flights.is_empty() {
+            let best_flight = &result.flights[0];
+            writeln!(notes, "Best flight: {} - {}", best_flight.name, best_flight.price)?;
+        }
+    }
+    
+    if let Some(zones) = zones {
+        write_schedule(notes, &result.flights, &params.date, &zones)?;
+    }
+    
+    Ok(())
+}
+
+/// Write the top flights' departure and arrival times in each of `zones`
+fn write_schedule(notes: &mut dyn Write, flights: &[rust_flights::Flight], date: &str, zones: &[Tz]) -> Result<(), Box<dyn std::error::Error>> {
+    let search_date = parse_date(date)?;
+    writeln!(notes, "\nSchedule:")?;
+    for flight in flights.iter().take(5) {
+        match flight.times(search_date) {
+            Some(times) => writeln!(notes, "  {} - {}: {}", flight.name, flight.price, times.summary_in(zones))?,
+            None => writeln!(notes, "  {} - {}: times unavailable", flight.name, flight.price)?,
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    
+    match cli.command {
+        Commands::Search {
+            from,
+            to,
+            date,
+            return_date,
             adults,
             children,
             infants_in_seat,
@@ -331,6 +1454,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             arrival_time,
             output,
             trip_type,
+            format,
+            limit,
+            rows,
+            timezones,
+            nearby,
+            metro,
+            compare_one_ways,
+            filter,
         } => {
             let params = CommonSearchParams {
                 date,
@@ -346,9 +1477,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 arrival_time,
                 output,
                 trip_type,
+                format,
+                limit,
+                rows,
+                timezones,
+                filter,
             };
             
-            if let Err(e) = execute_airport_search(from, to, params).await {
+            let mode = match (nearby, metro, compare_one_ways) {
+                (_, _, true) => AirportSearchMode::CompareOneWays,
+                (Some(radius_km), _, false) => AirportSearchMode::Nearby(AirportExpansion::Radius(radius_km)),
+                (None, true, false) => AirportSearchMode::Nearby(AirportExpansion::Metro),
+                (None, false, false) => AirportSearchMode::Standard,
+            };
+
+            if let Err(e) = execute_airport_search(from, to, params, mode).await {
                 eprintln!("Error searching for flights: {}", e);
                 std::process::exit(1);
             }
@@ -369,6 +1512,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             arrival_time,
             output,
             trip_type,
+            format,
+            limit,
+            rows,
+            timezones,
+            filter,
         } => {
             let params = CommonSearchParams {
                 date,
@@ -384,6 +1532,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 arrival_time,
                 output,
                 trip_type,
+                format,
+                limit,
+                rows,
+                timezones,
+                filter,
             };
             
             if let Err(e) = execute_city_search(from_city, to_city, params).await {
@@ -391,40 +1544,232 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 std::process::exit(1);
             }
         }
+        Commands::FlexDates {
+            from,
+            to,
+            start,
+            end,
+            adults,
+            children,
+            class,
+            max_stops,
+            airlines,
+            delay_ms,
+            output,
+        } => {
+            let options = SearchOptions {
+                passengers: Passengers {
+                    adults,
+                    children,
+                    ..Passengers::default()
+                },
+                seat_class: class.parse::<SeatClass>()?,
+                max_stops,
+                airlines: airlines.as_deref().map(split_codes),
+                request_delay: Duration::from_millis(delay_ms),
+                ..SearchOptions::default()
+            };
+            
+            if let Err(e) = execute_date_range_search(from, to, start, end, options, output).await {
+                eprintln!("Error searching date range: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::PriceCalendar {
+            from,
+            to,
+            depart_start,
+            depart_end,
+            min_nights,
+            max_nights,
+            adults,
+            children,
+            class,
+            max_stops,
+            airlines,
+            delay_ms,
+            no_color,
+            output,
+        } => {
+            let options = SearchOptions {
+                passengers: Passengers {
+                    adults,
+                    children,
+                    ..Passengers::default()
+                },
+                seat_class: class.parse::<SeatClass>()?,
+                max_stops,
+                airlines: airlines.as_deref().map(split_codes),
+                request_delay: Duration::from_millis(delay_ms),
+                ..SearchOptions::default()
+            };
+            
+            if let Err(e) = execute_price_calendar(
+                from,
+                to,
+                (depart_start, depart_end),
+                min_nights..=max_nights,
+                options,
+                !no_color,
+                output,
+            ).await {
+                eprintln!("Error building price calendar: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Explore {
+            from,
+            to,
+            all_cities,
+            region,
+            start,
+            end,
+            one_way,
+            adults,
+            children,
+            class,
+            max_stops,
+            airlines,
+            delay_ms,
+            output,
+        } => {
+            let destinations = match (to, all_cities, region) {
+                (Some(to), _, _) => ExploreDestinations::List(split_codes(&to)),
+                (None, true, _) => ExploreDestinations::CachedCities,
+                (None, false, Some(region)) => ExploreDestinations::Region(region.parse::<Region>()?),
+                (None, false, None) => {
+                    eprintln!("Specify destinations with --to, --all-cities or --region");
+                    std::process::exit(1);
+                }
+            };
+            let request = ExploreRequest {
+                origin: from,
+                destinations,
+                start_date: start,
+                end_date: end,
+                round_trip: !one_way,
+                options: SearchOptions {
+                    passengers: Passengers {
+                        adults,
+                        children,
+                        ..Passengers::default()
+                    },
+                    seat_class: class.parse::<SeatClass>()?,
+                    max_stops,
+                    airlines: airlines.as_deref().map(split_codes),
+                    request_delay: Duration::from_millis(delay_ms),
+                    ..SearchOptions::default()
+                },
+            };
+            
+            if let Err(e) = execute_explore(request, output).await {
+                eprintln!("Error exploring destinations: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::SplitTicket {
+            from,
+            to,
+            date,
+            via,
+            min_connection,
+            max_connection,
+            adults,
+            children,
+            class,
+            max_stops,
+            delay_ms,
+            output,
+        } => {
+            let request = SplitTicketRequest {
+                min_connection_minutes: min_connection,
+                max_connection_minutes: max_connection,
+                options: SearchOptions {
+                    passengers: Passengers {
+                        adults,
+                        children,
+                        ..Passengers::default()
+                    },
+                    seat_class: class.parse::<SeatClass>()?,
+                    max_stops,
+                    request_delay: Duration::from_millis(delay_ms),
+                    ..SearchOptions::default()
+                },
+                ..SplitTicketRequest::new(&from, &to, &date, split_codes(&via.to_uppercase()))
+            };
+            
+            if let Err(e) = execute_split_ticket(request, output).await {
+                eprintln!("Error searching split tickets: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Export {
+            input,
+            format,
+            date,
+            pick,
+            rows,
+            output,
+        } => {
+            if let Err(e) = execute_export(&input, &format, &date, pick, &rows, output) {
+                eprintln!("Export failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Link {
+            legs,
+            from_results,
+            pick,
+            date,
+            adults,
+            children,
+            infants_in_seat,
+            infants_on_lap,
+            class,
+            trip_type,
+        } => {
+            let link = || -> Result<String, Box<dyn std::error::Error>> {
+                let flights = selected_flights(&legs, from_results.as_deref(), pick, date.as_deref())?;
+                let trip_type = link_trip_type(trip_type.as_deref(), from_results.is_some(), &flights)?;
+                let passengers = Passengers { adults, children, infants_in_seat, infants_on_lap };
+                let info = build_itinerary_slices(flights, trip_type, passengers, class.parse::<SeatClass>()?)?;
+                Ok(itinerary_url(&info)?)
+            };
+            
+            match link() {
+                Ok(url) => println!("{}", url),
+                Err(e) => {
+                    eprintln!("Error building link: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::CityCache { action } => {
+            if let Err(e) = execute_city_cache(action).await {
+                eprintln!("City cache check failed: {}", e);
+                std::process::exit(1);
+            }
+        }
         Commands::QuickCity {
             from_city,
             to_city,
             date,
             output,
+            format,
+            limit,
         } => {
+            let params = CommonSearchParams::quick(date, output, format, limit);
+            params.status(&format!("Quick city search: {} → {} on {}", from_city, to_city, params.date));
+            
             // Use convenience function for quick searches
-            println!("Quick city search: {} → {} on {}", from_city, to_city, date);
-            match search_flights_between_cities(&from_city, &to_city, &date).await {
-                Ok(result) => {
-                    // Output results
-                    let json = serde_json::to_string_pretty(&result)?;
-                    
-                    if let Some(output_file) = output {
-                        fs::write(&output_file, &json)?;
-                        println!("Results saved to {}", output_file);
-                    } else {
-                        println!("{}", json);
-                    }
-                    
-                    // Print summary
-                    println!("\nSummary:");
-                    println!("Current price level: {}", result.current_price);
-                    println!("Found {} flights", result.flights.len());
-                    
-                    if !result.flights.is_empty() {
-                        let best_flight = &result.flights[0];
-                        println!("Best flight: {} - {}", best_flight.name, best_flight.price);
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Error searching for flights: {}", e);
-                    std::process::exit(1);
-                }
+            let search = async {
+                let result = search_flights_between_cities(&from_city, &to_city, &params.date).await?;
+                let metadata = params.metadata(&from_city, &to_city);
+                handle_flight_results(result, params, metadata).await
+            };
+            if let Err(e) = search.await {
+                eprintln!("Error searching for flights: {}", e);
+                std::process::exit(1);
             }
         }
     }
@@ -435,11 +1780,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    
+    /// A nonstop flight; the binary can't see the library's test builder, so
+    /// tests override fields with struct update syntax
+    fn test_flight(name: &str, amount: i32) -> rust_flights::Flight {
+        rust_flights::Flight {
+            is_best: false,
+            name: name.to_string(),
+            departure: "6:00 AM on Fri, Aug 15".to_string(),
+            arrival: "2:30 PM on Fri, Aug 15".to_string(),
+            duration: "5 hr 30 min".to_string(),
+            stops: 0,
+            price: rust_flights::FlightPrice { amount, currency: "$".to_string() },
+            flight_legs: None,
+            origin_airport: None,
+            destination_airport: None,
+            flight_summary: None,
+            layovers: None,
+            layover_description: None,
+        }
+    }
 
     #[test]
     fn test_cli_parsing() {
         // Test basic search command
-        let cli = Cli::try_parse_from(&[
+        let cli = Cli::try_parse_from([
             "rust-flights",
             "search",
             "--from", "LAX",
@@ -459,7 +1824,7 @@ mod tests {
     #[test]
     fn test_cli_parsing_with_time_windows() {
         // Test search command with time windows
-        let cli = Cli::try_parse_from(&[
+        let cli = Cli::try_parse_from([
             "rust-flights",
             "search",
             "--from", "LAX",
@@ -476,4 +1841,451 @@ mod tests {
             assert_eq!(arrival_time, Some("15:00-21:00".to_string()));
         }
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_cli_parsing_flex_dates() {
+        let cli = Cli::try_parse_from([
+            "rust-flights",
+            "flex-dates",
+            "--from", "LAX",
+            "--to", "JFK",
+            "--start", "2025-09-08",
+            "--end", "2025-09-14",
+        ]);
+        
+        assert!(cli.is_ok());
+        
+        if let Ok(Cli { command: Commands::FlexDates { start, end, delay_ms, .. } }) = cli {
+            assert_eq!(start, "2025-09-08");
+            assert_eq!(end, "2025-09-14");
+            assert_eq!(delay_ms, 1000);
+        }
+    }
+
+    #[test]
+    fn test_render_price_matrix() {
+        use rust_flights::{FlightPrice, PriceCell};
+        
+        let cell = |departure: &str, return_date: &str, amount: Option<i32>| PriceCell {
+            departure_date: departure.to_string(),
+            return_date: return_date.to_string(),
+            nights: 5,
+            price: amount.map(|amount| FlightPrice { amount, currency: "$".to_string() }),
+            current_price: "typical".to_string(),
+            cheapest_flight: None,
+            error: None,
+        };
+        let matrix = PriceMatrix::new("SFO", "HND", vec![
+            cell("2025-10-01", "2025-10-06", Some(900)),
+            cell("2025-10-02", "2025-10-07", Some(750)),
+            cell("2025-10-01", "2025-10-07", None),
+        ]);
+        
+        let grid = render_price_matrix(&matrix, false);
+        let lines: Vec<&str> = grid.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("10-06") && lines[0].contains("10-07"));
+        assert!(lines[1].starts_with("2025-10-01") && lines[1].contains("$900") && lines[1].contains('-'));
+        assert!(lines[2].starts_with("2025-10-02") && lines[2].contains("*$750"));
+        assert!(!grid.contains('\x1b'));
+        
+        let colored = render_price_matrix(&matrix, true);
+        assert!(colored.contains("\x1b[1;32m"));
+        assert!(colored.contains("\x1b[31m"));
+    }
+
+    #[test]
+    fn test_rows_keep_stdout_clean() {
+        use rust_flights::{Flight, FlightResult};
+        
+        let flight = |name: &str, amount: i32| Flight {
+            origin_airport: Some("SFO".to_string()),
+            destination_airport: Some("JFK".to_string()),
+            ..test_flight(name, amount)
+        };
+        let result = FlightResult {
+            current_price: "low".to_string(),
+            flights: vec![flight("JetBlue", 289), flight("United", 612)],
+        };
+        let params = CommonSearchParams {
+            date: "2025-08-15".to_string(),
+            return_date: None,
+            adults: 1,
+            children: 0,
+            infants_in_seat: 0,
+            infants_on_lap: 0,
+            class: "economy".to_string(),
+            max_stops: None,
+            airlines: None,
+            departure_time: None,
+            arrival_time: None,
+            output: None,
+            trip_type: "one-way".to_string(),
+            format: Some("csv".to_string()),
+            limit: None,
+            rows: "flights".to_string(),
+            timezones: Some("ET".to_string()),
+            filter: FilterArgs { max_price: Some(500), ..FilterArgs::default() },
+        };
+        assert!(params.rows_on_stdout());
+        
+        let (mut out, mut notes) = (Vec::new(), Vec::new());
+        write_flight_results(result, &params, &params.metadata("SFO", "JFK"), false, &mut out, &mut notes).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let notes = String::from_utf8(notes).unwrap();
+        
+        // Only the header and one row reach stdout
+        let rows: Vec<rust_flights::FlightRow> = csv::Reader::from_reader(out.as_bytes())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].airline, "JetBlue");
+        assert!(notes.starts_with("Filtered 2 flights down to 1"));
+        assert!(notes.contains("Summary:") && notes.contains("Schedule:"));
+        
+        let json = CommonSearchParams { format: Some("json".to_string()), ..params };
+        assert!(!json.rows_on_stdout());
+    }
+    
+    #[test]
+    fn test_cli_parsing_quick_city() {
+        let cli = Cli::try_parse_from([
+            "rust-flights", "quick-city", "London", "Paris", "2025-08-15", "--format", "csv", "--limit", "3",
+        ]);
+        let Ok(Cli { command: Commands::QuickCity { date, output, format, limit, .. } }) = cli else {
+            panic!("failed to parse quick-city");
+        };
+        let params = CommonSearchParams::quick(date, output, format, limit);
+        assert!(params.rows_on_stdout());
+        
+        // Goes through the same writer as other searches, so --limit and --format apply
+        let result = rust_flights::FlightResult {
+            current_price: "low".to_string(),
+            flights: (0..5).map(|i| test_flight("BA", 100 + i)).collect(),
+        };
+        let (mut out, mut notes) = (Vec::new(), Vec::new());
+        write_flight_results(result, &params, &params.metadata("London", "Paris"), false, &mut out, &mut notes).unwrap();
+        let rows: Vec<rust_flights::FlightRow> = csv::Reader::from_reader(&out[..])
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+    }
+    
+    #[test]
+    fn test_render_flight_table() {
+        use rust_flights::{Flight, FlightResult};
+        
+        let flight = |name: &str, is_best: bool, amount: i32, layovers: Option<&str>| Flight {
+            is_best,
+            departure: "10:00 PM on Fri, Aug 15".to_string(),
+            arrival: "6:35 AM on Sat, Aug 16".to_string(),
+            duration: "5 hr 35 min".to_string(),
+            stops: layovers.map_or(0, |_| 1),
+            layovers: layovers.map(|code| vec![code.to_string()]),
+            layover_description: layovers.map(|code| format!("Layover (1 of 1) is a 1 hr 5 min layover at {}.", code)),
+            ..test_flight(name, amount)
+        };
+        let result = FlightResult {
+            current_price: "low".to_string(),
+            flights: vec![
+                flight("JetBlue", true, 289, None),
+                flight("American Airlines, Alaska and British Airways", false, 312, Some("ORD")),
+            ],
+        };
+        
+        let table = render_flight_table(&result, 7, parse_date("2025-08-15").ok(), false);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "Prices are currently low · showing 2 of 7 flights");
+        assert!(lines[2].starts_with("   #  Airline"));
+        assert!(lines[3].starts_with("★  1  JetBlue ") && lines[3].contains("22:00") && lines[3].contains("06:35+1"));
+        assert!(lines[3].contains("Nonstop") && lines[3].ends_with("$289"));
+        assert!(lines[4].contains("American Airlines, Alaska a…") && lines[4].contains("ORD 1h 05m"));
+        // Columns line up
+        assert_eq!(lines[3].find("$289"), lines[4].find("$312"));
+        assert!(!table.contains('\x1b'));
+        
+        let colored = render_flight_table(&result, 2, None, true);
+        assert!(colored.contains("\x1b[1;32mlow\x1b[0m"));
+        assert!(colored.contains("10:00 PM on Fri, Aug 15"));
+    }
+
+    #[test]
+    fn test_cli_parsing_with_filters() {
+        let cli = Cli::try_parse_from([
+            "rust-flights",
+            "search",
+            "--from", "LAX",
+            "--to", "JFK",
+            "--date", "2024-01-15",
+            "--max-price", "400",
+            "--depart-after", "07:30",
+            "--exclude-airports", "EWR, LGA",
+            "--require-airlines", "AA,DL",
+            "--sort", "value",
+        ]);
+        
+        assert!(cli.is_ok());
+        
+        if let Ok(Cli { command: Commands::Search { filter, .. } }) = cli {
+            let (filter, sort) = filter.build().unwrap();
+            assert_eq!(filter.max_price, Some(400));
+            assert_eq!(filter.departure_after, chrono::NaiveTime::from_hms_opt(7, 30, 0));
+            assert_eq!(filter.excluded_airports, vec!["EWR", "LGA"]);
+            assert_eq!(filter.required_airlines, vec!["AA", "DL"]);
+            assert!(matches!(sort, Some(SortKey::Value(_))));
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_nearby() {
+        let cli = Cli::try_parse_from([
+            "rust-flights", "search",
+            "--from", "SFO",
+            "--to", "JFK",
+            "--date", "2025-09-01",
+            "--nearby", "80",
+        ]);
+
+        if let Ok(Cli { command: Commands::Search { nearby, metro, .. } }) = cli {
+            assert_eq!(nearby, Some(80.0));
+            assert!(!metro);
+        } else {
+            panic!("failed to parse --nearby");
+        }
+
+        let conflicting = Cli::try_parse_from([
+            "rust-flights", "search",
+            "--from", "SFO",
+            "--to", "JFK",
+            "--date", "2025-09-01",
+            "--nearby", "80",
+            "--metro",
+        ]);
+        assert!(conflicting.is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_timezones() {
+        let cli = Cli::try_parse_from([
+            "rust-flights", "search",
+            "--from", "SFO",
+            "--to", "JFK",
+            "--date", "2025-09-01",
+            "--timezones", "PT,ET",
+        ]);
+
+        if let Ok(Cli { command: Commands::Search { timezones, .. } }) = cli {
+            assert_eq!(parse_timezones(&timezones.unwrap()).unwrap().len(), 2);
+        } else {
+            panic!("failed to parse --timezones");
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_explore() {
+        let cli = Cli::try_parse_from([
+            "rust-flights", "explore",
+            "--from", "SEA",
+            "--to", "LIS, Lisbon,AUS",
+            "--start", "2025-10-06",
+            "--end", "2025-10-10",
+        ]);
+
+        if let Ok(Cli { command: Commands::Explore { to, one_way, region, .. } }) = cli {
+            assert_eq!(split_codes(&to.unwrap()), vec!["LIS", "Lisbon", "AUS"]);
+            assert!(!one_way);
+            assert!(region.is_none());
+        } else {
+            panic!("failed to parse explore");
+        }
+
+        let conflicting = Cli::try_parse_from([
+            "rust-flights", "explore",
+            "--from", "SEA",
+            "--all-cities",
+            "--region", "europe",
+            "--start", "2025-10-06",
+            "--end", "2025-10-10",
+        ]);
+        assert!(conflicting.is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_split_ticket() {
+        let cli = Cli::try_parse_from([
+            "rust-flights", "split-ticket",
+            "--from", "SEA",
+            "--to", "MIA",
+            "--date", "2025-09-01",
+            "--via", "DEN,ORD",
+            "--min-connection", "240",
+        ]);
+
+        if let Ok(Cli { command: Commands::SplitTicket { via, min_connection, max_connection, .. } }) = cli {
+            assert_eq!(split_codes(&via), vec!["DEN", "ORD"]);
+            assert_eq!(min_connection, 240);
+            assert_eq!(max_connection, 720);
+        } else {
+            panic!("failed to parse split-ticket");
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_compare_one_ways() {
+        let cli = Cli::try_parse_from([
+            "rust-flights", "search",
+            "--from", "SEA",
+            "--to", "JFK",
+            "--date", "2025-09-01",
+            "--return-date", "2025-09-08",
+            "--compare-one-ways",
+        ]);
+        assert!(matches!(cli, Ok(Cli { command: Commands::Search { compare_one_ways: true, .. } })));
+
+        // Needs a return date
+        let one_way = Cli::try_parse_from([
+            "rust-flights", "search",
+            "--from", "SEA",
+            "--to", "JFK",
+            "--date", "2025-09-01",
+            "--compare-one-ways",
+        ]);
+        assert!(one_way.is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_output_format() {
+        let cli = Cli::try_parse_from([
+            "rust-flights", "search",
+            "--from", "SFO",
+            "--to", "JFK",
+            "--date", "2025-09-01",
+            "--format", "csv",
+            "--rows", "legs",
+        ]);
+
+        if let Ok(Cli { command: Commands::Search { format, rows, .. } }) = cli {
+            assert!(matches!(OutputFormat::parse(format.as_deref(), &rows, true), Ok(OutputFormat::Rows(ExportFormat::Csv, ExportRows::Legs))));
+        } else {
+            panic!("failed to parse --format");
+        }
+        assert!(matches!(OutputFormat::parse(Some("json"), "flights", true), Ok(OutputFormat::Json)));
+        assert!(OutputFormat::parse(Some("xml"), "flights", true).is_err());
+        
+        // Tables are the default on a terminal only
+        assert!(matches!(OutputFormat::parse(None, "flights", true), Ok(OutputFormat::Table)));
+        assert!(matches!(OutputFormat::parse(None, "flights", false), Ok(OutputFormat::Json)));
+        
+        let limited = Cli::try_parse_from(["rust-flights", "search", "-f", "SFO", "-t", "JFK", "-d", "2025-09-01", "--format", "table", "--limit", "5"]);
+        assert!(matches!(limited, Ok(Cli { command: Commands::Search { limit: Some(5), .. } })));
+    }
+
+    #[test]
+    fn test_cli_parsing_export() {
+        let cli = Cli::try_parse_from([
+            "rust-flights", "export",
+            "--input", "flights.json",
+            "--date", "2025-08-15",
+            "--pick", "3",
+        ]);
+
+        if let Ok(Cli { command: Commands::Export { input, format, pick, output, .. } }) = cli {
+            assert_eq!(input, "flights.json");
+            assert_eq!(format, "ics");
+            assert_eq!(pick, Some(3));
+            assert!(output.is_none());
+        } else {
+            panic!("failed to parse export");
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_link() {
+        let cli = Cli::try_parse_from([
+            "rust-flights", "link",
+            "--leg", "LAX:JFK:2025-08-15:AA:100",
+            "--leg", "JFK:LAX:2025-08-22:AA:101",
+            "--class", "business",
+        ]);
+
+        if let Ok(Cli { command: Commands::Link { legs, from_results, trip_type, .. } }) = cli {
+            assert_eq!(legs.len(), 2);
+            assert!(from_results.is_none() && trip_type.is_none());
+            let flights = selected_flights(&legs, None, 1, None).unwrap();
+            assert!(matches!(infer_trip_type(&flights), Ok(TripType::RoundTrip)));
+            assert!(matches!(infer_trip_type(&flights[..1]), Ok(TripType::OneWay)));
+            // LAX → JFK → BOS would be multi-city
+            let onward = vec!["JFK:BOS:2025-08-22:B6:101".parse().unwrap()];
+            assert!(infer_trip_type(&[flights[0].clone(), onward]).is_err());
+        } else {
+            panic!("failed to parse link");
+        }
+
+        let from_results = Cli::try_parse_from([
+            "rust-flights", "link", "--from-results", "results.json", "--pick", "3",
+        ]);
+        assert!(matches!(from_results, Ok(Cli { command: Commands::Link { pick: 3, .. } })));
+
+        // A picked connecting flight is one slice of two segments
+        let connecting = rust_flights::Flight {
+            is_best: true,
+            arrival: "4:30 PM on Fri, Aug 15".to_string(),
+            duration: "7 hr 30 min".to_string(),
+            stops: 1,
+            flight_legs: Some(vec![
+                rust_flights::FlightLeg { airline_code: "UA".to_string(), flight_number: "100".to_string() },
+                rust_flights::FlightLeg { airline_code: "UA".to_string(), flight_number: "200".to_string() },
+            ]),
+            origin_airport: Some("SFO".to_string()),
+            destination_airport: Some("JFK".to_string()),
+            layovers: Some(vec!["DEN".to_string()]),
+            layover_description: Some("Layover (1 of 1) is a 1 hr layover at DEN.".to_string()),
+            ..test_flight("United", 320)
+        };
+        let results = rust_flights::FlightResult { current_price: "typical".to_string(), flights: vec![connecting] };
+        let path = std::env::temp_dir().join(format!("rust-flights-link-test-{}.json", std::process::id()));
+        fs::write(&path, serde_json::to_string(&results).unwrap()).unwrap();
+        let flights = selected_flights(&[], path.to_str(), 1, Some("2025-08-15"));
+        fs::remove_file(&path).unwrap();
+        let flights = flights.unwrap();
+        assert_eq!(flights.len(), 1);
+        assert_eq!(flights[0].len(), 2);
+        let trip_type = link_trip_type(None, true, &flights).unwrap();
+        assert!(matches!(trip_type, TripType::OneWay));
+        let info = build_itinerary_slices(flights.clone(), trip_type, Passengers::default(), SeatClass::Economy).unwrap();
+        assert_eq!(info.data.len(), 1);
+        assert_eq!(info.data[0].from_flight.as_ref().unwrap().airport, "SFO");
+        assert_eq!(info.data[0].to_flight.as_ref().unwrap().airport, "JFK");
+        assert_eq!(info.data[0].selected_flight.len(), 2);
+        assert!(build_itinerary_slices(flights, TripType::MultiCity, Passengers::default(), SeatClass::Economy).is_err());
+        
+        // A link needs flights from exactly one source
+        assert!(Cli::try_parse_from(["rust-flights", "link"]).is_err());
+        assert!(Cli::try_parse_from([
+            "rust-flights", "link", "--leg", "LAX:JFK:2025-08-15:AA:100", "--from-results", "results.json",
+        ]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_city_cache() {
+        let audit = Cli::try_parse_from(["rust-flights", "city-cache", "audit", "--online"]);
+        assert!(matches!(
+            audit,
+            Ok(Cli { command: Commands::CityCache { action: CityCacheAction::Audit { online: true, .. } } })
+        ));
+
+        let cli = Cli::try_parse_from([
+            "rust-flights", "city-cache", "regenerate",
+            "--cities", "Chongqing,Bangalore",
+        ]);
+        if let Ok(Cli { command: Commands::CityCache { action: CityCacheAction::Regenerate { cities, output, write_cache, .. } } }) = cli {
+            assert_eq!(cities.as_deref(), Some("Chongqing,Bangalore"));
+            assert_eq!(output, "city_cache.diff.json");
+            assert!(write_cache.is_none());
+        } else {
+            panic!("failed to parse city-cache regenerate");
+        }
+    }
+}
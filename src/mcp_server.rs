@@ -9,8 +9,12 @@ use rmcp::{
 use rust_flights::{
     get_flights as get_flights_internal, get_flights_by_city as get_flights_by_city_internal,
     CityFlightData, CityFlightSearchRequest, FlightData, FlightResult, FlightSearchRequest,
-    Passengers, SeatClass, TimeWindow, TripType, SelectedFlight, build_itinerary_info, encode_to_base64,
+    Passengers, SeatClass, TimeWindow, TripType, SelectedFlight, build_itinerary_info, itinerary_url,
+    FlightFilter, SortKey, SearchOptions, search_date_range as search_date_range_internal,
+    get_flights_nearby as get_flights_nearby_internal, AirportExpansion,
+    explore as explore_internal, ExploreDestinations, ExploreRequest, Region, FlightError,
 };
+use rust_flights::filter::parse_clock_time;
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
 
@@ -36,10 +40,10 @@ pub struct FlightSearchParams {
     pub to_airport: String,
     // City search parameters
     #[serde(default)]
-    #[schemars(description = "Origin city name (e.g., Los Angeles, New York) - use for city-based search")]
+    #[schemars(description = "Origin city name (e.g., Los Angeles, New York) - use for city-based search. Append a country code to pick between same-named cities (e.g., Portland, US). Local-language names and former names also work (e.g., München, Bombay)")]
     pub from_city: String,
     #[serde(default)]
-    #[schemars(description = "Destination city name (e.g., New York, London) - use for city-based search")]
+    #[schemars(description = "Destination city name (e.g., New York, London) - use for city-based search. Append a country code to pick between same-named cities (e.g., Paris, FR). Local-language names and former names also work (e.g., Köln, 東京)")]
     pub to_city: String,
     // Common search parameters
     #[schemars(description = "Departure date in YYYY-MM-DD format")]
@@ -80,6 +84,46 @@ pub struct FlightSearchParams {
     #[serde(default = "default_max_flights")]
     #[schemars(description = "Maximum number of flights to return (default: 30)")]
     pub max_flights: usize,
+    // Client-side filtering and sorting parameters
+    #[serde(default = "default_no_limit")]
+    #[schemars(description = "Minimum price (default: -1 means no limit)")]
+    pub min_price: i32,
+    #[serde(default = "default_no_limit")]
+    #[schemars(description = "Maximum price (default: -1 means no limit)")]
+    pub max_price: i32,
+    #[serde(default = "default_no_limit")]
+    #[schemars(description = "Maximum total travel time in minutes (default: -1 means no limit)")]
+    pub max_duration_minutes: i32,
+    #[serde(default = "default_no_limit")]
+    #[schemars(description = "Maximum layover time in minutes (default: -1 means no limit)")]
+    pub max_layover_minutes: i32,
+    #[serde(default)]
+    #[schemars(description = "Only flights departing at or after this local time (HH:MM)")]
+    pub departure_after: String,
+    #[serde(default)]
+    #[schemars(description = "Only flights departing at or before this local time (HH:MM)")]
+    pub departure_before: String,
+    #[serde(default)]
+    #[schemars(description = "Only flights arriving at or after this local time (HH:MM)")]
+    pub arrival_after: String,
+    #[serde(default)]
+    #[schemars(description = "Only flights arriving at or before this local time (HH:MM)")]
+    pub arrival_before: String,
+    #[serde(default)]
+    #[schemars(description = "Airports to avoid as origin, destination or layover (comma-separated, e.g., 'EWR,LGA')")]
+    pub exclude_airports: String,
+    #[serde(default)]
+    #[schemars(description = "Only keep flights operated entirely by these airlines (comma-separated, e.g., 'AA,DL')")]
+    pub required_airlines: String,
+    #[serde(default)]
+    #[schemars(description = "Sort results by: price, duration, departure, arrival or value (default: Google's order)")]
+    pub sort_by: String,
+    #[serde(default = "default_no_limit")]
+    #[schemars(description = "Airport search only: also search every airport within this many km of the origin and destination and merge the results (default: -1 means off)")]
+    pub nearby_radius_km: i32,
+    #[serde(default)]
+    #[schemars(description = "Airport search only: also search the other airports in the same metro area, e.g. SFO also searches OAK and SJC (default: false)")]
+    pub include_metro_airports: bool,
 }
 
 impl FlightSearchParams {
@@ -123,6 +167,36 @@ impl FlightSearchParams {
     pub fn max_flights_opt(&self) -> Option<usize> {
         if self.max_flights == 30 { None } else { Some(self.max_flights) }
     }
+    
+    pub fn min_price_opt(&self) -> Option<i32> {
+        if self.min_price == -1 { None } else { Some(self.min_price) }
+    }
+    
+    pub fn max_price_opt(&self) -> Option<i32> {
+        if self.max_price == -1 { None } else { Some(self.max_price) }
+    }
+    
+    pub fn max_duration_minutes_opt(&self) -> Option<i32> {
+        if self.max_duration_minutes == -1 { None } else { Some(self.max_duration_minutes) }
+    }
+    
+    pub fn max_layover_minutes_opt(&self) -> Option<i32> {
+        if self.max_layover_minutes == -1 { None } else { Some(self.max_layover_minutes) }
+    }
+    
+    pub fn sort_by_opt(&self) -> Option<String> {
+        if self.sort_by.is_empty() { None } else { Some(self.sort_by.clone()) }
+    }
+    
+    pub fn airport_expansion(&self) -> Option<AirportExpansion> {
+        if self.nearby_radius_km >= 0 {
+            Some(AirportExpansion::Radius(self.nearby_radius_km as f64))
+        } else if self.include_metro_airports {
+            Some(AirportExpansion::Metro)
+        } else {
+            None
+        }
+    }
 }
 
 /// Selected flight information for itinerary links
@@ -143,7 +217,7 @@ pub struct SelectedFlightInfo {
 /// Itinerary link request parameters
 #[derive(Debug, Deserialize, Clone, schemars::JsonSchema)]
 pub struct ItineraryRequest {
-    #[schemars(description = "List of selected flights for the itinerary")]
+    #[schemars(description = "Selected flights for the itinerary: one for a one-way trip, outbound and return for a round trip")]
     pub flights: Vec<SelectedFlightInfo>,
     #[serde(default = "default_adults")]
     #[schemars(description = "Number of adult passengers (default: 1)")]
@@ -165,6 +239,136 @@ pub struct ItineraryRequest {
     pub trip_type: String,
 }
 
+/// Flexible date search parameters
+#[derive(Debug, Deserialize, Clone, schemars::JsonSchema)]
+pub struct DateRangeParams {
+    #[schemars(description = "Origin airport code (e.g., LAX, JFK)")]
+    pub from_airport: String,
+    #[schemars(description = "Destination airport code (e.g., JFK, LHR)")]
+    pub to_airport: String,
+    #[schemars(description = "First departure date to search in YYYY-MM-DD format")]
+    pub start_date: String,
+    #[schemars(description = "Last departure date to search in YYYY-MM-DD format (at most 62 days after start_date)")]
+    pub end_date: String,
+    #[serde(default = "default_adults")]
+    #[schemars(description = "Number of adult passengers (default: 1)")]
+    pub adults: i32,
+    #[serde(default)]
+    #[schemars(description = "Number of child passengers (default: 0)")]
+    pub children: i32,
+    #[serde(default = "default_seat_class")]
+    #[schemars(description = "Seat class: economy, premium-economy, business, first (default: economy)")]
+    pub seat_class: String,
+    #[serde(default = "default_max_stops")]
+    #[schemars(description = "Maximum number of stops (default: -1 means no limit)")]
+    pub max_stops: i32,
+    #[serde(default)]
+    #[schemars(description = "Preferred airlines (comma-separated, e.g., 'AA,DL,UA')")]
+    pub airlines: String,
+}
+
+impl DateRangeParams {
+    /// Convert the tool parameters into library search options
+    pub fn search_options(&self) -> Result<SearchOptions, String> {
+        build_search_options(self.adults, self.children, &self.seat_class, self.max_stops, &self.airlines)
+    }
+}
+
+/// Explore-anywhere search parameters
+#[derive(Debug, Deserialize, Clone, schemars::JsonSchema)]
+pub struct ExploreParams {
+    #[schemars(description = "Origin airport code (e.g., SEA) or city name (e.g., Seattle)")]
+    pub origin: String,
+    #[serde(default)]
+    #[schemars(description = "Candidate destinations as airport codes or city names (comma-separated, e.g., 'LIS,Barcelona,AUS'). Leave empty to use all_cached_cities or region")]
+    pub destinations: String,
+    #[serde(default)]
+    #[schemars(description = "Consider every city in the bundled city cache (default: false)")]
+    pub all_cached_cities: bool,
+    #[serde(default)]
+    #[schemars(description = "Consider every bundled airport city in a region: north-america, south-america, europe, middle-east, africa, asia, oceania")]
+    pub region: String,
+    #[schemars(description = "Departure date (round trip) or first date of a one-way range, in YYYY-MM-DD format")]
+    pub start_date: String,
+    #[schemars(description = "Return date (round trip) or last date of a one-way range, in YYYY-MM-DD format")]
+    pub end_date: String,
+    #[serde(default)]
+    #[schemars(description = "Search one-way flights on every date from start_date to end_date instead of a round trip (default: false)")]
+    pub one_way: bool,
+    #[serde(default = "default_adults")]
+    #[schemars(description = "Number of adult passengers (default: 1)")]
+    pub adults: i32,
+    #[serde(default)]
+    #[schemars(description = "Number of child passengers (default: 0)")]
+    pub children: i32,
+    #[serde(default = "default_seat_class")]
+    #[schemars(description = "Seat class: economy, premium-economy, business, first (default: economy)")]
+    pub seat_class: String,
+    #[serde(default = "default_max_stops")]
+    #[schemars(description = "Maximum number of stops (default: -1 means no limit)")]
+    pub max_stops: i32,
+    #[serde(default)]
+    #[schemars(description = "Preferred airlines (comma-separated, e.g., 'AA,DL,UA')")]
+    pub airlines: String,
+    #[serde(default = "default_max_destinations")]
+    #[schemars(description = "Maximum number of ranked destinations to return (default: 20)")]
+    pub max_destinations: usize,
+}
+
+impl ExploreParams {
+    /// Convert the tool parameters into a library explore request
+    pub fn explore_request(&self) -> Result<ExploreRequest, String> {
+        let destinations = if !self.destinations.trim().is_empty() {
+            let names: Vec<String> = self.destinations
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+            ExploreDestinations::List(names)
+        } else if self.all_cached_cities {
+            ExploreDestinations::CachedCities
+        } else if !self.region.is_empty() {
+            ExploreDestinations::Region(self.region.parse::<Region>().map_err(|e| e.to_string())?)
+        } else {
+            return Err("Specify destinations, all_cached_cities or region".to_string());
+        };
+
+        Ok(ExploreRequest {
+            origin: self.origin.clone(),
+            destinations,
+            start_date: self.start_date.clone(),
+            end_date: self.end_date.clone(),
+            round_trip: !self.one_way,
+            options: build_search_options(self.adults, self.children, &self.seat_class, self.max_stops, &self.airlines)?,
+        })
+    }
+}
+
+/// Build library search options from the common tool parameters
+fn build_search_options(
+    adults: i32,
+    children: i32,
+    seat_class: &str,
+    max_stops: i32,
+    airlines: &str,
+) -> Result<SearchOptions, String> {
+    let seat_class = seat_class
+        .parse::<SeatClass>()
+        .map_err(|e| format!("Invalid seat class: {}", e))?;
+
+    Ok(SearchOptions {
+        passengers: Passengers {
+            adults,
+            children,
+            ..Passengers::default()
+        },
+        seat_class,
+        max_stops: if max_stops == -1 { None } else { Some(max_stops) },
+        airlines: parse_airlines(airlines),
+        ..SearchOptions::default()
+    })
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct FlightInfo {
     pub airline_name: String,
@@ -192,18 +396,28 @@ pub struct FlightSearchResult {
 #[tool(tool_box)]
 impl FlightServer {
     /// Unified flight search with explicit mode selection
-    #[tool(description = "Search for flights between locations. Specify either airport codes (from_airport/to_airport) or city names (from_city/to_city), but not both.")]
+    #[tool(description = "Search for flights between locations. Specify either airport codes (from_airport/to_airport) or city names (from_city/to_city), but not both. Airport searches can optionally include nearby airports (nearby_radius_km or include_metro_airports); merged results carry the actual origin_airport/destination_airport.")]
     async fn get_flights(
         &self,
         #[tool(aggr)] params: FlightSearchParams,
     ) -> String {
         let max_flights = params.max_flights_opt();
         
+        let (filter, sort) = match build_flight_filter(&params) {
+            Ok(refinement) => refinement,
+            Err(e) => return format!(r#"{{"error": "Error building result filter: {}"}}"#, e),
+        };
+        
         let result = match (params.from_airport_opt(), params.to_airport_opt(), params.from_city_opt(), params.to_city_opt()) {
             // Airport-based search
             (Some(from_airport), Some(to_airport), None, None) => {
                 match build_flight_search_request(from_airport, to_airport, params.clone()) {
-                    Ok(request) => get_flights_internal(request).await,
+                    Ok(request) => match params.airport_expansion() {
+                        Some(expansion) => {
+                            get_flights_nearby_internal(request, &expansion, SearchOptions::default().request_delay).await
+                        }
+                        None => get_flights_internal(request).await,
+                    },
                     Err(e) => return format!(r#"{{"error": "Error building flight request: {}"}}"#, e),
                 }
             }
@@ -230,11 +444,74 @@ impl FlightServer {
         };
 
         match result {
-            Ok(flight_result) => format_flight_results_json(flight_result, max_flights),
+            Ok(flight_result) => {
+                let mut flight_result = flight_result.filtered(&filter);
+                if let Some(sort) = sort {
+                    flight_result = flight_result.sorted_by(&sort);
+                }
+                format_flight_results_json(flight_result, max_flights)
+            }
+            Err(FlightError::AmbiguousCity { name, candidates }) => serde_json::json!({
+                "error": format!("City '{}' is ambiguous; ask the user which one they mean and retry, {}", name, rust_flights::wikidata::ambiguity_hint(&name, &candidates)),
+                "ambiguous_city": name,
+                "candidates": candidates,
+            })
+            .to_string(),
+            Err(FlightError::CityNotFound { name, suggestions }) => serde_json::json!({
+                "error": if suggestions.is_empty() {
+                    format!("City '{}' not found", name)
+                } else {
+                    format!("City '{}' not found; ask the user whether they meant one of the suggestions and retry", name)
+                },
+                "city_not_found": name,
+                "suggestions": suggestions,
+            })
+            .to_string(),
             Err(e) => format!(r#"{{"error": "Flight search failed: {}"}}"#, e),
         }
     }
 
+    /// Find the cheapest day to fly within a date range
+    #[tool(description = "Search one-way flights for every departure date in a range (max 62 days) and return the cheapest price, cheapest flight and price level per day, plus the overall cheapest date. Makes one search per date, so keep ranges short.")]
+    async fn search_date_range(
+        &self,
+        #[tool(aggr)] params: DateRangeParams,
+    ) -> String {
+        let options = match params.search_options() {
+            Ok(options) => options,
+            Err(e) => return format!(r#"{{"error": "Error building search options: {}"}}"#, e),
+        };
+
+        match search_date_range_internal(&params.from_airport, &params.to_airport, &params.start_date, &params.end_date, &options).await {
+            Ok(result) => serde_json::to_string(&result).unwrap_or_else(|e| {
+                format!(r#"{{"error": "Failed to serialize results: {}"}}"#, e)
+            }),
+            Err(e) => format!(r#"{{"error": "Date range search failed: {}"}}"#, e),
+        }
+    }
+
+    /// Rank candidate destinations by cheapest fare from one origin
+    #[tool(description = "Explore anywhere: search one origin against many candidate destinations (an explicit list, every cached city, or a region) and return the destinations ranked by cheapest fare with the best flight for each. Round trip by default (out on start_date, back on end_date); one_way searches every date in the range. Makes one search per destination and date (max 300), so keep candidate lists focused.")]
+    async fn explore_destinations(
+        &self,
+        #[tool(aggr)] params: ExploreParams,
+    ) -> String {
+        let request = match params.explore_request() {
+            Ok(request) => request,
+            Err(e) => return format!(r#"{{"error": "Error building explore request: {}"}}"#, e),
+        };
+
+        match explore_internal(&request).await {
+            Ok(mut result) => {
+                result.destinations.truncate(params.max_destinations);
+                serde_json::to_string(&result).unwrap_or_else(|e| {
+                    format!(r#"{{"error": "Failed to serialize results: {}"}}"#, e)
+                })
+            }
+            Err(e) => format!(r#"{{"error": "Explore search failed: {}"}}"#, e),
+        }
+    }
+
     /// Generate a Google Flights itinerary link for selected flights
     #[tool(description = "Generate a Google Flights itinerary link for specific selected flights. Provide flight details including departure date, airline code, and flight number for each flight.")]
     async fn get_itinerary_link(
@@ -282,14 +559,11 @@ impl FlightServer {
             Err(e) => return format!(r#"{{"error": "Error building itinerary: {}"}}"#, e),
         };
 
-        // Encode to base64
-        let encoded = match encode_to_base64(&info) {
-            Ok(encoded) => encoded,
+        // Encode as a Google Flights URL
+        let url = match itinerary_url(&info) {
+            Ok(url) => url,
             Err(e) => return format!(r#"{{"error": "Error encoding itinerary: {}"}}"#, e),
         };
-
-        // Format as Google Flights URL
-        let url = format!("https://www.google.com/travel/flights?tfs={}", encoded);
         
         serde_json::json!({
             "url": url,
@@ -337,13 +611,7 @@ fn build_flight_search_request(
     let return_date = params.return_date_opt();
 
     // Parse comma-delimited airlines string
-    let parsed_airlines = params.airlines_opt().map(|airlines_str| {
-        airlines_str
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<String>>()
-    });
+    let parsed_airlines = parse_airlines(&params.airlines);
 
     let mut flights = vec![FlightData {
         date: params.departure_date,
@@ -413,13 +681,7 @@ fn build_city_flight_search_request(
     let return_date = params.return_date_opt();
 
     // Parse comma-delimited airlines string
-    let parsed_airlines = params.airlines_opt().map(|airlines_str| {
-        airlines_str
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<String>>()
-    });
+    let parsed_airlines = parse_airlines(&params.airlines);
 
     let mut flights = vec![CityFlightData {
         date: params.departure_date,
@@ -451,6 +713,55 @@ fn build_city_flight_search_request(
     })
 }
 
+/// Parse a comma-delimited airlines string, treating an empty string as no preference
+fn parse_airlines(airlines: &str) -> Option<Vec<String>> {
+    if airlines.is_empty() {
+        return None;
+    }
+    Some(
+        airlines
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+    )
+}
+
+fn build_flight_filter(params: &FlightSearchParams) -> Result<(FlightFilter, Option<SortKey>), String> {
+    let parse_time = |value: &str| -> Result<Option<chrono::NaiveTime>, String> {
+        if value.is_empty() {
+            Ok(None)
+        } else {
+            parse_clock_time(value).map(Some).map_err(|e| e.to_string())
+        }
+    };
+
+    let mut filter = FlightFilter::new();
+    filter.min_price = params.min_price_opt();
+    filter.max_price = params.max_price_opt();
+    filter.max_duration_minutes = params.max_duration_minutes_opt();
+    filter.max_layover_minutes = params.max_layover_minutes_opt();
+    filter.departure_after = parse_time(&params.departure_after)?;
+    filter.departure_before = parse_time(&params.departure_before)?;
+    filter.arrival_after = parse_time(&params.arrival_after)?;
+    filter.arrival_before = parse_time(&params.arrival_before)?;
+
+    for airport in params.exclude_airports.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        filter = filter.exclude_airport(airport);
+    }
+    filter = filter.require_airlines(
+        params.required_airlines.split(',').map(str::trim).filter(|s| !s.is_empty()),
+    );
+
+    let sort = params
+        .sort_by_opt()
+        .map(|s| s.parse::<SortKey>())
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    Ok((filter, sort))
+}
+
 fn format_flight_results_json(result: FlightResult, max_flights: Option<usize>) -> String {
     if result.flights.is_empty() {
         return serde_json::json!({
@@ -511,12 +822,14 @@ fn default_seat_class() -> String { "economy".to_string() }
 fn default_trip_type() -> String { "one-way".to_string() }
 fn default_max_stops() -> i32 { -1 } // -1 means no limit
 fn default_max_flights() -> usize { 30 }
+fn default_no_limit() -> i32 { -1 }
+fn default_max_destinations() -> usize { 20 }
 
 #[tool(tool_box)]
 impl ServerHandler for FlightServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
-            instructions: Some("A flight search server with unified airport and city search capabilities. Returns structured JSON results with best_flights and other_flights. Also provides itinerary link generation for selected flights.".into()),
+            instructions: Some("A flight search server with unified airport and city search capabilities. Returns structured JSON results with best_flights and other_flights. Airport searches can be expanded to nearby or same-metro airports. Also provides flexible date range search, explore-anywhere destination ranking and itinerary link generation for selected flights.".into()),
             capabilities: ServerCapabilities::builder().enable_tools().build(),
             ..Default::default()
         }
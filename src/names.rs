@@ -0,0 +1,185 @@
+//! City name normalisation shared by the caches, Wikidata ranking and the airport database.
+//!
+//! Names are compared after Unicode normalisation and diacritic folding, so
+//! "Sao Paulo", "SÃO PAULO" and a decomposed "São Paulo" all match "São Paulo".
+//! Edit distance and trigram similarity measure how close two names are for
+//! typo suggestions.
+
+use std::collections::HashSet;
+use unicode_normalization::UnicodeNormalization;
+
+/// Letters that do not decompose into a base letter plus an accent
+const SPECIAL_LETTERS: &[(char, &str)] = &[
+    ('ß', "ss"),
+    ('ø', "o"),
+    ('æ', "ae"),
+    ('œ', "oe"),
+    ('ł', "l"),
+    ('đ', "d"),
+    ('ð', "d"),
+    ('þ', "th"),
+    ('ı', "i"),
+    ('ħ', "h"),
+];
+
+/// Abbreviated forms of common name prefixes ("Saint Louis" → "st louis")
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("saint", "st"),
+    ("sankt", "st"),
+    ("mount", "mt"),
+    ("fort", "ft"),
+];
+
+/// Trimmed, NFC-composed name with single spaces, as sent to Wikidata
+pub fn normalize_query(name: &str) -> String {
+    name.nfc().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Remove accents from Latin, Greek and Cyrillic letters ("München" → "Munchen"),
+/// keeping case. Other scripts are only NFKC-normalised.
+pub fn fold_diacritics(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.nfkd().filter(|c| !is_diacritic(*c)) {
+        match SPECIAL_LETTERS.iter().find(|(special, _)| *special == c.to_lowercase().next().unwrap_or(c)) {
+            Some((_, replacement)) if c.is_uppercase() => {
+                let mut chars = replacement.chars();
+                folded.extend(chars.next().map(|first| first.to_ascii_uppercase()));
+                folded.push_str(chars.as_str());
+            }
+            Some((_, replacement)) => folded.push_str(replacement),
+            None => folded.push(c),
+        }
+    }
+    // Recompose marks that were kept, such as Japanese voicing marks
+    folded.nfc().collect()
+}
+
+/// Lowercase, diacritic-folded name with single spaces; "  São  PAULO" → "sao paulo"
+pub fn fold_name(name: &str) -> String {
+    fold_diacritics(name).to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Key under which two spellings of the same city compare equal.
+///
+/// On top of [`fold_name`], apostrophes are dropped, other punctuation becomes a
+/// space, and "Saint"/"Mount"/"Fort" are abbreviated: "Saint-Louis" and "St. Louis"
+/// both give "st louis". "City" is kept, since "Jersey" and "Jersey City" are different
+/// places; variants such as "City of St Louis" are listed in `city_aliases.json`.
+pub fn city_key(name: &str) -> String {
+    let folded: String = fold_name(name)
+        .chars()
+        .filter(|c| !matches!(c, '\'' | '’' | 'ʼ' | '`'))
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+
+    let words: Vec<&str> = folded
+        .split_whitespace()
+        .map(|word| {
+            ABBREVIATIONS.iter()
+                .find(|(long, _)| *long == word)
+                .map(|(_, short)| *short)
+                .unwrap_or(word)
+        })
+        .collect();
+
+    words.join(" ")
+}
+
+/// Edit distance counting insertions, deletions, substitutions and swaps of
+/// adjacent characters ("barcelnoa" → "barcelona" is 1)
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Three rolling rows: i - 2, i - 1 and i
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 0..a.len() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for j in 0..b.len() {
+            let substitution = previous[j] + usize::from(a[i] != b[j]);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            if i > 0 && j > 0 && a[i] == b[j - 1] && a[i - 1] == b[j] {
+                current[j + 1] = current[j + 1].min(before[j - 1] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+/// Share of character trigrams two strings have in common (Jaccard index, 0 to 1)
+pub fn trigram_similarity(a: &str, b: &str) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let trigrams = |text: &str| -> HashSet<[char; 3]> {
+        let padded: Vec<char> = format!("  {} ", text).chars().collect();
+        padded.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+    };
+    let (a, b) = (trigrams(a), trigrams(b));
+    a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+}
+
+/// Combining diacritical marks (U+0300–U+036F), as produced by NFKD for accented letters
+fn is_diacritic(c: char) -> bool {
+    ('\u{0300}'..='\u{036F}').contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_diacritics() {
+        assert_eq!(fold_diacritics("München"), "Munchen");
+        assert_eq!(fold_diacritics("São Paulo"), "Sao Paulo");
+        assert_eq!(fold_diacritics("Bogotá"), "Bogota");
+        assert_eq!(fold_diacritics("Łódź"), "Lodz");
+        assert_eq!(fold_diacritics("Gießen"), "Giessen");
+        assert_eq!(fold_diacritics("Hồ Chí Minh"), "Ho Chi Minh");
+        // Decomposed input folds the same way
+        assert_eq!(fold_diacritics("Sa\u{0303}o Paulo"), "Sao Paulo");
+        // Non-Latin scripts keep their letters
+        assert_eq!(fold_diacritics("東京"), "東京");
+        assert_eq!(fold_diacritics("ガ"), "ガ");
+    }
+
+    #[test]
+    fn test_normalize_query() {
+        assert_eq!(normalize_query("  Sa\u{0303}o   Paulo "), "São Paulo");
+        assert_eq!(fold_name("  SÃO  PAULO "), "sao paulo");
+    }
+
+    #[test]
+    fn test_city_key() {
+        assert_eq!(city_key("St. Louis"), "st louis");
+        assert_eq!(city_key("Saint-Louis"), "st louis");
+        assert_eq!(city_key("Xi'an"), "xian");
+        assert_eq!(city_key("Sankt-Peterburg"), "st peterburg");
+        assert_eq!(city_key("Ho Chi Minh City"), "ho chi minh city");
+        assert_ne!(city_key("Jersey"), city_key("Jersey City"));
+        assert_ne!(city_key("Kansas"), city_key("Kansas City"));
+        assert_ne!(city_key("Mexico"), city_key("Mexico City"));
+        assert_ne!(city_key("Oklahoma"), city_key("Oklahoma City"));
+        assert_ne!(city_key("London"), city_key("City of London"));
+        assert_ne!(city_key("York"), city_key("New York"));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("gaule", "gaulle"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("münchen", "munchen"), 1);
+        assert_eq!(edit_distance("barcelnoa", "barcelona"), 1);
+        assert_eq!(edit_distance("ab", "ba"), 1);
+    }
+
+    #[test]
+    fn test_trigram_similarity() {
+        assert_eq!(trigram_similarity("paris", "paris"), 1.0);
+        assert_eq!(trigram_similarity("", ""), 0.0);
+        assert!(trigram_similarity("san fransisco", "san francisco") > 0.5);
+        assert!(trigram_similarity("paris", "tokyo") < 0.1);
+    }
+}
@@ -0,0 +1,260 @@
+//! Nearby-airport expansion: search every airport within a radius or metro area
+//! of the requested origin/destination and merge the results.
+
+use crate::airports::{airports_within, find_airport, metro_area, metro_area_for_airport};
+use crate::filter::SortKey;
+use crate::{FlightClient, FlightError, FlightResult, FlightSearchRequest};
+use std::time::Duration;
+
+/// Maximum number of airport combinations searched for one nearby search
+pub const MAX_NEARBY_SEARCHES: usize = 25;
+
+/// How to expand an airport code into a set of nearby airports
+#[derive(Debug, Clone, PartialEq)]
+pub enum AirportExpansion {
+    /// Every bundled airport within this many kilometres
+    Radius(f64),
+    /// Every airport in the same named metro group ("NYC", "LON", ...)
+    Metro,
+}
+
+impl AirportExpansion {
+    /// Expand `code` into the airports to search, with the original airport first.
+    ///
+    /// A metro code that is not itself an airport (e.g. "NYC") expands to its
+    /// member airports. Codes that are not in the bundled dataset, such as city
+    /// Freebase IDs, are returned unchanged.
+    pub fn expand(&self, code: &str) -> Vec<String> {
+        let trimmed = code.trim();
+        if !is_iata_like(trimmed) {
+            return vec![trimmed.to_string()];
+        }
+        let code = trimmed.to_uppercase();
+
+        let mut codes = Vec::new();
+        if find_airport(&code).is_some() {
+            codes.push(code.clone());
+        }
+
+        match self {
+            AirportExpansion::Radius(radius_km) => {
+                let centers = match metro_area(&code) {
+                    Some(metro) if find_airport(&code).is_none() => metro.airports.clone(),
+                    _ => vec![code.clone()],
+                };
+                for center in centers {
+                    codes.extend(airports_within(&center, *radius_km).into_iter().map(|(a, _)| a.iata.clone()));
+                }
+            }
+            AirportExpansion::Metro => {
+                let metro = metro_area(&code).or_else(|| metro_area_for_airport(&code));
+                if let Some(metro) = metro {
+                    codes.extend(metro.airports.iter().cloned());
+                }
+            }
+        }
+
+        let mut unique = Vec::with_capacity(codes.len());
+        for code in codes {
+            if !unique.contains(&code) {
+                unique.push(code);
+            }
+        }
+        if unique.is_empty() {
+            unique.push(code);
+        }
+        unique
+    }
+}
+
+fn is_iata_like(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Every variant of `request` with its airports replaced by nearby alternatives.
+///
+/// Each distinct airport is substituted consistently across legs, so a round trip
+/// from SFO that is expanded to OAK also returns to OAK.
+pub fn expand_request(
+    request: &FlightSearchRequest,
+    expansion: &AirportExpansion,
+) -> Result<Vec<FlightSearchRequest>, FlightError> {
+    let mut originals: Vec<String> = Vec::new();
+    for leg in &request.flights {
+        for code in [&leg.from_airport, &leg.to_airport] {
+            if !originals.contains(code) {
+                originals.push(code.clone());
+            }
+        }
+    }
+
+    let options: Vec<Vec<String>> = originals.iter().map(|code| expansion.expand(code)).collect();
+    let total = options.iter().try_fold(1usize, |acc, o| acc.checked_mul(o.len())).unwrap_or(usize::MAX);
+    if total > MAX_NEARBY_SEARCHES {
+        return Err(FlightError::ParseError(format!(
+            "Nearby airport expansion produces {} searches (maximum is {}); use a smaller radius",
+            total, MAX_NEARBY_SEARCHES
+        )));
+    }
+
+    let mut requests = Vec::with_capacity(total);
+    let mut choice = vec![0usize; options.len()];
+    loop {
+        let mut variant = request.clone();
+        for leg in &mut variant.flights {
+            for code in [&mut leg.from_airport, &mut leg.to_airport] {
+                if let Some(i) = originals.iter().position(|o| o == code) {
+                    *code = options[i][choice[i]].clone();
+                }
+            }
+        }
+        // Skip combinations where a leg would depart from its own destination
+        if variant.flights.iter().all(|leg| leg.from_airport != leg.to_airport) {
+            requests.push(variant);
+        }
+
+        // Advance the mixed-radix counter; the last airport varies fastest
+        let mut pos = options.len();
+        loop {
+            if pos == 0 {
+                return Ok(requests);
+            }
+            pos -= 1;
+            choice[pos] += 1;
+            if choice[pos] < options[pos].len() {
+                break;
+            }
+            choice[pos] = 0;
+        }
+    }
+}
+
+impl FlightClient {
+    /// Search `request` from/to every nearby airport and merge the results,
+    /// cheapest first.
+    ///
+    /// Each merged flight's `origin_airport` / `destination_airport` is set to the
+    /// airports actually searched for its first leg when the parser did not find
+    /// them. Failed combinations are skipped with a warning; the first error is
+    /// returned only if every combination fails.
+    pub async fn get_flights_nearby(
+        &self,
+        request: FlightSearchRequest,
+        expansion: &AirportExpansion,
+        request_delay: Duration,
+    ) -> Result<FlightResult, FlightError> {
+        let requests = expand_request(&request, expansion)?;
+        let mut merged: Option<FlightResult> = None;
+        let mut first_error = None;
+
+        for (i, variant) in requests.into_iter().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(request_delay).await;
+            }
+
+            let (from, to) = variant.flights.first()
+                .map(|leg| (leg.from_airport.clone(), leg.to_airport.clone()))
+                .unwrap_or_default();
+
+            match self.get_flights(variant).await {
+                Ok(mut result) => {
+                    for flight in &mut result.flights {
+                        flight.origin_airport.get_or_insert_with(|| from.clone());
+                        flight.destination_airport.get_or_insert_with(|| to.clone());
+                    }
+                    match merged.as_mut() {
+                        Some(all) => all.flights.extend(result.flights),
+                        None => merged = Some(result),
+                    }
+                }
+                Err(e) => {
+                    eprintln!("⚠️  Warning: Search {} → {} failed: {}", from, to, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        match (merged, first_error) {
+            (Some(result), _) => Ok(result.sorted_by(&SortKey::Price)),
+            (None, Some(e)) => Err(e),
+            (None, None) => Ok(FlightResult { current_price: String::new(), flights: Vec::new() }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FlightData, Passengers, SeatClass, TripType};
+
+    fn leg(from: &str, to: &str, date: &str) -> FlightData {
+        FlightData {
+            date: date.to_string(),
+            from_airport: from.to_string(),
+            to_airport: to.to_string(),
+            max_stops: None,
+            airlines: None,
+            departure_time: None,
+            arrival_time: None,
+        }
+    }
+
+    fn request(flights: Vec<FlightData>, trip_type: TripType) -> FlightSearchRequest {
+        FlightSearchRequest {
+            flights,
+            trip_type,
+            passengers: Passengers::default(),
+            seat_class: SeatClass::Economy,
+        }
+    }
+
+    fn routes(requests: &[FlightSearchRequest]) -> Vec<String> {
+        requests.iter()
+            .map(|r| r.flights.iter().map(|l| format!("{}-{}", l.from_airport, l.to_airport)).collect::<Vec<_>>().join(","))
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_metro() {
+        assert_eq!(AirportExpansion::Metro.expand("OAK"), vec!["OAK", "SFO", "SJC"]);
+        assert_eq!(AirportExpansion::Metro.expand("nyc"), vec!["JFK", "LGA", "EWR"]);
+        assert_eq!(AirportExpansion::Metro.expand("DEN"), vec!["DEN"]);
+        assert_eq!(AirportExpansion::Metro.expand("/m/030qb3t"), vec!["/m/030qb3t"]);
+    }
+
+    #[test]
+    fn test_expand_radius() {
+        let codes = AirportExpansion::Radius(60.0).expand("SFO");
+        assert_eq!(codes[0], "SFO");
+        assert!(codes.contains(&"OAK".to_string()));
+        assert!(codes.contains(&"SJC".to_string()));
+        assert_eq!(AirportExpansion::Radius(60.0).expand("XYZ"), vec!["XYZ"]);
+        assert_eq!(AirportExpansion::Radius(0.0).expand("DEN"), vec!["DEN"]);
+    }
+
+    #[test]
+    fn test_expand_request_one_way() {
+        let req = request(vec![leg("SFO", "JFK", "2025-09-01")], TripType::OneWay);
+        let variants = expand_request(&req, &AirportExpansion::Metro).unwrap();
+        assert_eq!(variants.len(), 9);
+        assert_eq!(routes(&variants)[0], "SFO-JFK");
+        assert!(routes(&variants).contains(&"SJC-EWR".to_string()));
+    }
+
+    #[test]
+    fn test_expand_request_round_trip_is_consistent() {
+        let req = request(
+            vec![leg("CHI", "DEN", "2025-09-01"), leg("DEN", "CHI", "2025-09-08")],
+            TripType::RoundTrip,
+        );
+        let variants = expand_request(&req, &AirportExpansion::Metro).unwrap();
+        assert_eq!(routes(&variants), vec!["ORD-DEN,DEN-ORD", "MDW-DEN,DEN-MDW"]);
+        assert_eq!(variants[1].flights[1].date, "2025-09-08");
+    }
+
+    #[test]
+    fn test_expand_request_limit() {
+        let req = request(vec![leg("LON", "LAX", "2025-09-01")], TripType::OneWay);
+        assert!(expand_request(&req, &AirportExpansion::Metro).is_err());
+    }
+}
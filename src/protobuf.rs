@@ -1,7 +1,10 @@
 //! Protobuf definitions and utilities for Google Flights API
 
-use crate::{FlightError, Passengers, SeatClass, TripType};
+use crate::dates::{format_date, parse_date};
+use crate::{Flight, FlightError, Passengers, SeatClass, TripType};
+use chrono::NaiveDate;
 use prost::Message;
+use std::str::FromStr;
 use base64::{Engine as _, engine::general_purpose};
 
 // Include the generated protobuf code
@@ -87,7 +90,7 @@ pub fn build_flight_info(
             departure_latest_hour: flight.departure_time.as_ref().map(|t| t.latest_hour),
             arrival_earliest_hour: flight.arrival_time.as_ref().map(|t| t.earliest_hour),
             arrival_latest_hour: flight.arrival_time.as_ref().map(|t| t.latest_hour),
-            selected_flight: None,
+            selected_flight: vec![],
         };
         
         proto_flight_data.push(proto_flight);
@@ -105,7 +108,7 @@ pub fn build_flight_info(
 }
 
 /// Selected flight information for building itinerary links
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SelectedFlight {
     pub from_airport: String,
     pub to_airport: String,
@@ -114,39 +117,172 @@ pub struct SelectedFlight {
     pub flight_number: String,
 }
 
-/// Build protobuf Info message with selected flights for itinerary links
+impl FromStr for SelectedFlight {
+    type Err = FlightError;
+
+    /// Parse "LAX:JFK:2025-08-15:AA:100" (from, to, date, airline code, flight number)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| FlightError::ParseError(format!(
+            "Invalid flight '{}': {} (expected FROM:TO:YYYY-MM-DD:AIRLINE:NUMBER, e.g. LAX:JFK:2025-08-15:AA:100)",
+            s, reason
+        ));
+        let parts: Vec<&str> = s.trim().split(':').map(str::trim).collect();
+        let [from, to, date, airline, number] = parts[..] else {
+            return Err(invalid("wrong number of fields"));
+        };
+        let is_code = |code: &str, len: usize| code.len() == len && code.chars().all(|c| c.is_ascii_alphanumeric());
+        if !is_code(from, 3) || !is_code(to, 3) {
+            return Err(invalid("airports must be 3-letter IATA codes"));
+        }
+        if !is_code(airline, 2) && !is_code(airline, 3) {
+            return Err(invalid("airline must be a 2- or 3-character code"));
+        }
+        if number.is_empty() || number.len() > 4 || !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid("flight number must be 1-4 digits"));
+        }
+        parse_date(date)?;
+
+        Ok(SelectedFlight {
+            from_airport: from.to_uppercase(),
+            to_airport: to.to_uppercase(),
+            departure_date: date.to_string(),
+            airline_code: airline.to_uppercase(),
+            flight_number: number.to_string(),
+        })
+    }
+}
+
+impl SelectedFlight {
+    /// One selected flight per leg of a search result found on `search_date`.
+    ///
+    /// Legs run between the flight's airports and its layovers (falling back to
+    /// `origin` and `destination` when the flight does not name its endpoints) and
+    /// need the per-leg flight numbers. Later legs are dated from the estimated
+    /// layover times, or the departure date if those are unknown.
+    pub fn from_flight(
+        flight: &Flight,
+        origin: &str,
+        destination: &str,
+        search_date: NaiveDate,
+    ) -> Result<Vec<SelectedFlight>, FlightError> {
+        let origin = flight.origin_airport.as_deref().unwrap_or(origin);
+        let destination = flight.destination_airport.as_deref().unwrap_or(destination);
+        let airports: Vec<&str> = std::iter::once(origin)
+            .chain(flight.layovers.iter().flatten().map(String::as_str))
+            .chain(std::iter::once(destination))
+            .collect();
+        if airports.iter().any(|airport| airport.trim().is_empty()) {
+            return Err(FlightError::ParseError(format!("'{}' does not name all of its airports, so it cannot be linked", flight.name)));
+        }
+        let legs = flight.flight_legs.as_deref().unwrap_or_default();
+        if legs.len() != airports.len() - 1 {
+            return Err(FlightError::ParseError(format!(
+                "'{}' has {} flight number(s) for {} leg(s), so it cannot be linked",
+                flight.name, legs.len(), airports.len() - 1
+            )));
+        }
+
+        let departure = flight.departure_datetime(search_date)
+            .ok_or_else(|| FlightError::TimeParseError(flight.departure.clone()))?;
+        let layover_dates: Vec<NaiveDate> = flight.times_between(origin, destination, search_date)
+            .map(|times| times.layovers.iter().map(|layover| layover.departure.date_naive()).collect())
+            .unwrap_or_default();
+
+        Ok(legs.iter()
+            .enumerate()
+            .map(|(i, leg)| {
+                let date = match i {
+                    0 => departure.date(),
+                    _ => layover_dates.get(i - 1).copied().unwrap_or(departure.date()),
+                };
+                SelectedFlight {
+                    from_airport: airports[i].to_uppercase(),
+                    to_airport: airports[i + 1].to_uppercase(),
+                    departure_date: format_date(date),
+                    airline_code: leg.airline_code.clone(),
+                    flight_number: leg.flight_number.clone(),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Build protobuf Info message with selected flights for itinerary links, each
+/// flight being one slice of the trip (see [`build_itinerary_slices`])
 pub fn build_itinerary_info(
     selected_flights: Vec<SelectedFlight>,
     trip_type: TripType,
     passengers: Passengers,
     seat_class: SeatClass,
 ) -> Result<Info, FlightError> {
+    let slices = selected_flights.into_iter().map(|flight| vec![flight]).collect();
+    build_itinerary_slices(slices, trip_type, passengers, seat_class)
+}
+
+/// Build protobuf Info message for an itinerary of slices, each slice being the
+/// segments of one flight in order (two for SFO→DEN→JFK).
+///
+/// A one-way trip has one slice and a round trip two; multi-city trips are not
+/// supported by Google Flights links.
+pub fn build_itinerary_slices(
+    slices: Vec<Vec<SelectedFlight>>,
+    trip_type: TripType,
+    passengers: Passengers,
+    seat_class: SeatClass,
+) -> Result<Info, FlightError> {
+    let (expected, name) = match trip_type {
+        TripType::OneWay => (1, "one-way"),
+        TripType::RoundTrip => (2, "round-trip"),
+        TripType::MultiCity => {
+            return Err(FlightError::ParseError("Multi-city itineraries cannot be linked, link each flight as a one-way trip".to_string()));
+        }
+    };
+    if slices.len() != expected {
+        return Err(FlightError::ParseError(format!(
+            "A {} link needs {} flight(s), got {}",
+            name, expected, slices.len()
+        )));
+    }
+    
     let mut proto_flight_data = Vec::new();
     
-    for flight in selected_flights {
+    for segments in slices {
+        let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+            return Err(FlightError::ParseError("A linked flight needs at least one segment".to_string()));
+        };
+        if let Some(pair) = segments.windows(2).find(|pair| pair[0].to_airport != pair[1].from_airport) {
+            return Err(FlightError::ParseError(format!(
+                "Segments {} → {} and {} → {} do not connect",
+                pair[0].from_airport, pair[0].to_airport, pair[1].from_airport, pair[1].to_airport
+            )));
+        }
+        
         let from_airport = Airport {
-            airport: flight.from_airport.clone(),
+            airport: first.from_airport.clone(),
         };
         
         let to_airport = Airport {
-            airport: flight.to_airport.clone(),
+            airport: last.to_airport.clone(),
         };
         
-        let selected_flight_data = SelectedFlightData {
-            from_airport: flight.from_airport.clone(),
-            departure_date: flight.departure_date.clone(),
-            to_airport: flight.to_airport.clone(),
-            airline_code: flight.airline_code,
-            flight_number: flight.flight_number,
-        };
+        let date = first.departure_date.clone();
+        let selected_flight = segments.into_iter()
+            .map(|segment| SelectedFlightData {
+                from_airport: segment.from_airport,
+                departure_date: segment.departure_date,
+                to_airport: segment.to_airport,
+                airline_code: segment.airline_code,
+                flight_number: segment.flight_number,
+            })
+            .collect();
         
         let proto_flight = FlightData {
-            date: flight.departure_date,
+            date,
             from_flight: Some(from_airport),
             to_flight: Some(to_airport),
             max_stops: None,
             airlines: vec![],
-            selected_flight: Some(selected_flight_data),
+            selected_flight,
             departure_earliest_hour: None,
             departure_latest_hour: None,
             arrival_earliest_hour: None,
@@ -174,6 +310,11 @@ pub fn encode_to_base64(info: &Info) -> Result<String, FlightError> {
     Ok(general_purpose::URL_SAFE.encode(&buf))
 }
 
+/// Google Flights URL for an itinerary built with [`build_itinerary_info`]
+pub fn itinerary_url(info: &Info) -> Result<String, FlightError> {
+    Ok(format!("https://www.google.com/travel/flights?tfs={}", encode_to_base64(info)?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -426,7 +567,7 @@ mod tests {
         println!("  trip field 19: {}", info.trip);
         println!("  data length: {}", info.data.len());
         if let Some(flight) = info.data.first() {
-            println!("  first flight has selected_flight: {}", flight.selected_flight.is_some());
+            println!("  first flight has selected_flight: {}", !flight.selected_flight.is_empty());
         }
         
         let encoded = encode_to_base64(&info).unwrap();
@@ -436,5 +577,86 @@ mod tests {
         assert_eq!(info.passengers, vec![1]); // Should be [1] for one adult
         assert_eq!(info.seat, 1); // Economy
         assert_eq!(info.trip, 2); // OneWay
+        assert!(itinerary_url(&info).unwrap().starts_with("https://www.google.com/travel/flights?tfs="));
+    }
+
+    #[test]
+    fn test_parse_selected_flight() {
+        let flight: SelectedFlight = "lax:JFK:2025-08-15:aa:100".parse().unwrap();
+        assert_eq!(flight, SelectedFlight {
+            from_airport: "LAX".to_string(),
+            to_airport: "JFK".to_string(),
+            departure_date: "2025-08-15".to_string(),
+            airline_code: "AA".to_string(),
+            flight_number: "100".to_string(),
+        });
+
+        assert!("LAX:JFK:2025-08-15:AA".parse::<SelectedFlight>().is_err());
+        assert!("LAX:JFK:2025-13-15:AA:100".parse::<SelectedFlight>().is_err());
+        assert!("LAXX:JFK:2025-08-15:AA:100".parse::<SelectedFlight>().is_err());
+        assert!("LAX:JFK:2025-08-15:AA:10a".parse::<SelectedFlight>().is_err());
+    }
+
+    #[test]
+    fn test_selected_flights_from_result() {
+        let mut flight = Flight::test("United", 320)
+            .best()
+            .schedule("10:00 PM on Fri, Aug 15", "11:30 AM on Sat, Aug 16", "10 hr 30 min")
+            .legs(&[("UA", "100"), ("UA", "200")])
+            .layovers(&["DEN"], "Layover (1 of 1) is a 3 hr overnight layover at Denver.");
+        let date = parse_date("2025-08-15").unwrap();
+
+        let selected = SelectedFlight::from_flight(&flight, "SFO", "JFK", date).unwrap();
+        assert_eq!(selected.len(), 2);
+        assert_eq!(selected[0], "SFO:DEN:2025-08-15:UA:100".parse().unwrap());
+        // The onward leg leaves Denver after midnight
+        assert_eq!(selected[1], "DEN:JFK:2025-08-16:UA:200".parse().unwrap());
+        assert!(SelectedFlight::from_flight(&flight, "", "JFK", date).is_err());
+
+        flight = flight.legs(&[("UA", "100")]);
+        assert!(SelectedFlight::from_flight(&flight, "SFO", "JFK", date).is_err());
+    }
+
+    #[test]
+    fn test_connecting_flight_is_one_slice() {
+        let flight = Flight::test("United", 320)
+            .schedule("6:00 AM on Fri, Aug 15", "4:30 PM on Fri, Aug 15", "7 hr 30 min")
+            .route("SFO", "JFK")
+            .legs(&[("UA", "100"), ("UA", "200")])
+            .layovers(&["DEN"], "Layover (1 of 1) is a 1 hr layover at Denver.");
+        let segments = SelectedFlight::from_flight(&flight, "", "", parse_date("2025-08-15").unwrap()).unwrap();
+        let info = build_itinerary_slices(vec![segments], TripType::OneWay, Passengers::default(), SeatClass::Economy).unwrap();
+
+        let encoded = encode_to_base64(&info).unwrap();
+        let decoded = Info::decode(&general_purpose::URL_SAFE.decode(&encoded).unwrap()[..]).unwrap();
+        assert_eq!(decoded.trip, Trip::OneWay as i32);
+        assert_eq!(decoded.data.len(), 1);
+        let slice = &decoded.data[0];
+        assert_eq!(slice.date, "2025-08-15");
+        assert_eq!(slice.from_flight.as_ref().unwrap().airport, "SFO");
+        assert_eq!(slice.to_flight.as_ref().unwrap().airport, "JFK");
+        let segments: Vec<(&str, &str, &str)> = slice.selected_flight.iter()
+            .map(|s| (s.from_airport.as_str(), s.to_airport.as_str(), s.flight_number.as_str()))
+            .collect();
+        assert_eq!(segments, vec![("SFO", "DEN", "100"), ("DEN", "JFK", "200")]);
+    }
+
+    #[test]
+    fn test_itinerary_slices_match_trip_type() {
+        let leg = |s: &str| s.parse::<SelectedFlight>().unwrap();
+        let out = leg("LAX:JFK:2025-08-15:AA:100");
+        let back = leg("JFK:LAX:2025-08-22:AA:101");
+        let build = |flights: Vec<SelectedFlight>, trip_type| build_itinerary_info(flights, trip_type, Passengers::default(), SeatClass::Economy);
+
+        let round_trip = build(vec![out.clone(), back.clone()], TripType::RoundTrip).unwrap();
+        assert_eq!(round_trip.data.len(), 2);
+        assert!(round_trip.data.iter().all(|slice| slice.selected_flight.len() == 1));
+        assert!(build(vec![out.clone(), back.clone()], TripType::OneWay).is_err());
+        assert!(build(vec![out.clone()], TripType::RoundTrip).is_err());
+        assert!(build(vec![out.clone(), back.clone()], TripType::MultiCity).is_err());
+        
+        // Segments of one slice must connect
+        let gap = build_itinerary_slices(vec![vec![out, back.clone(), back]], TripType::OneWay, Passengers::default(), SeatClass::Economy);
+        assert!(gap.is_err());
     }
 } 
\ No newline at end of file
@@ -0,0 +1,379 @@
+//! Pluggable city resolution.
+//!
+//! A [`CityResolver`] turns a place name into [`Location`] candidates that Google
+//! Flights can search. The built-in resolvers (bundled city cache, persistent
+//! store, Wikidata, offline airport database) can be combined with your own, such
+//! as an office registry that maps "HQ" to a metro, in a [`ResolverChain`].
+
+use crate::names::fold_name;
+use crate::wikidata::cached_freebase_id;
+use crate::{AirportDb, CityHint, CityInfo, FlightError, WikidataClient, WikidataError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Future returned by [`CityResolver::resolve`]
+pub type ResolveFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<Location>, FlightError>> + Send + 'a>>;
+
+/// What a location's code refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LocationKind {
+    /// A city Freebase ID, covering every airport in the city
+    City,
+    /// A single airport IATA code
+    Airport,
+}
+
+/// A resolved place Google Flights can search from or to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    pub name: String,
+    /// Freebase ID ("/m/04jpl") or IATA code ("LHR"), as sent to Google Flights
+    pub code: String,
+    pub kind: LocationKind,
+    pub country_code: Option<String>,
+    pub coordinates: Option<(f64, f64)>, // (latitude, longitude)
+}
+
+impl Location {
+    pub fn city(name: &str, freebase_id: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            code: freebase_id.to_string(),
+            kind: LocationKind::City,
+            country_code: None,
+            coordinates: None,
+        }
+    }
+
+    pub fn airport(name: &str, iata: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            code: iata.trim().to_uppercase(),
+            kind: LocationKind::Airport,
+            country_code: None,
+            coordinates: None,
+        }
+    }
+
+    /// A Wikidata city; `None` if it has no Freebase ID
+    pub fn from_city_info(city: &CityInfo) -> Option<Self> {
+        Some(Self {
+            country_code: city.country_code.clone(),
+            coordinates: city.coordinates,
+            ..Self::city(&city.name, city.freebase_id.as_deref()?)
+        })
+    }
+}
+
+/// Resolves place names to locations.
+///
+/// An empty candidate list means "not found here" and lets a [`ResolverChain`] try
+/// the next resolver; an error is reported but also falls through, except
+/// [`FlightError::AmbiguousCity`], which stops the chain so the user can choose.
+pub trait CityResolver: Send + Sync {
+    /// Short name used in log messages
+    fn name(&self) -> &str;
+
+    /// Candidates for `query`, best first
+    fn resolve<'a>(&'a self, query: &'a str) -> ResolveFuture<'a>;
+}
+
+impl<R: CityResolver + ?Sized> CityResolver for &R {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn resolve<'a>(&'a self, query: &'a str) -> ResolveFuture<'a> {
+        (**self).resolve(query)
+    }
+}
+
+/// The compiled-in `city_cache.json`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BundledCityCache;
+
+impl CityResolver for BundledCityCache {
+    fn name(&self) -> &str {
+        "city cache"
+    }
+
+    fn resolve<'a>(&'a self, query: &'a str) -> ResolveFuture<'a> {
+        Box::pin(async move {
+            // A country hint means the caller wants a specific same-named city
+            let (name, hint) = CityHint::parse(query);
+            if !hint.is_empty() {
+                return Ok(Vec::new());
+            }
+            Ok(cached_freebase_id(&name).map(|id| Location::city(&name, &id)).into_iter().collect())
+        })
+    }
+}
+
+#[cfg(feature = "city-search")]
+impl CityResolver for crate::CityStore {
+    fn name(&self) -> &str {
+        "city store"
+    }
+
+    fn resolve<'a>(&'a self, query: &'a str) -> ResolveFuture<'a> {
+        Box::pin(async move {
+            let (name, hint) = CityHint::parse(query);
+            if !hint.is_empty() {
+                return Ok(Vec::new());
+            }
+            let stored = self.get(&name)?;
+            Ok(stored
+                .map(|city| Location { coordinates: city.coordinates, ..Location::city(&city.name, &city.freebase_id) })
+                .into_iter()
+                .collect())
+        })
+    }
+}
+
+impl CityResolver for WikidataClient {
+    fn name(&self) -> &str {
+        "Wikidata"
+    }
+
+    fn resolve<'a>(&'a self, query: &'a str) -> ResolveFuture<'a> {
+        Box::pin(async move {
+            let (name, hint) = CityHint::parse(query);
+            match self.resolve_city(&name, &hint).await {
+                Ok(city) => Ok(Location::from_city_info(&city).into_iter().collect()),
+                Err(WikidataError::CityNotFound(_) | WikidataError::NoFreebaseId(_)) => Ok(Vec::new()),
+                Err(WikidataError::AmbiguousCity { name, candidates }) => {
+                    Err(FlightError::AmbiguousCity { name, candidates })
+                }
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+}
+
+impl CityResolver for AirportDb {
+    fn name(&self) -> &str {
+        "airport database"
+    }
+
+    /// Airports serving the city (main airport first), or the airport with that code
+    fn resolve<'a>(&'a self, query: &'a str) -> ResolveFuture<'a> {
+        Box::pin(async move {
+            let (name, hint) = CityHint::parse(query);
+            let mut airports = self.in_city(&name);
+            if airports.is_empty() {
+                airports.extend(self.by_code(&name).filter(|_| name.len() == 3));
+            }
+            Ok(airports
+                .into_iter()
+                .filter(|a| hint.allows_country(&a.country))
+                .map(|a| Location {
+                    country_code: Some(a.country.clone()),
+                    coordinates: Some((a.latitude, a.longitude)),
+                    ..Location::airport(&a.name, &a.iata)
+                })
+                .collect())
+        })
+    }
+}
+
+/// Fixed name → location table, e.g. for office nicknames ("HQ", "Berlin office").
+/// Names match ignoring case and accents.
+#[derive(Debug, Clone, Default)]
+pub struct LocationRegistry {
+    locations: HashMap<String, Location>,
+}
+
+impl LocationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: &str, location: Location) -> Self {
+        self.insert(name, location);
+        self
+    }
+
+    pub fn insert(&mut self, name: &str, location: Location) {
+        self.locations.insert(registry_key(name), location);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Location> {
+        self.locations.get(&registry_key(name))
+    }
+}
+
+impl CityResolver for LocationRegistry {
+    fn name(&self) -> &str {
+        "location registry"
+    }
+
+    fn resolve<'a>(&'a self, query: &'a str) -> ResolveFuture<'a> {
+        Box::pin(async move { Ok(self.get(query).cloned().into_iter().collect()) })
+    }
+}
+
+fn registry_key(name: &str) -> String {
+    fold_name(name)
+}
+
+/// Resolvers tried in order until one returns a candidate
+#[derive(Default)]
+pub struct ResolverChain {
+    resolvers: Vec<Box<dyn CityResolver>>,
+}
+
+impl ResolverChain {
+    /// An empty chain
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in chain: bundled city cache, persistent store (`city-search`
+    /// feature), Wikidata, then the offline airport database
+    pub fn standard() -> Result<Self, FlightError> {
+        let wikidata = WikidataClient::new()?;
+        #[allow(unused_mut)]
+        let mut chain = Self::new().then(BundledCityCache);
+        #[cfg(feature = "city-search")]
+        if let Some(store) = wikidata.store().cloned() {
+            chain = chain.then(store);
+        }
+        Ok(chain.then(wikidata).then(AirportDb::bundled()))
+    }
+
+    /// Append a resolver to the end of the chain
+    pub fn then(mut self, resolver: impl CityResolver + 'static) -> Self {
+        self.resolvers.push(Box::new(resolver));
+        self
+    }
+
+    /// Put a resolver in front of the chain, e.g. a registry that should win over Wikidata
+    pub fn first(mut self, resolver: impl CityResolver + 'static) -> Self {
+        self.resolvers.insert(0, Box::new(resolver));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.resolvers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resolvers.is_empty()
+    }
+
+    /// The best candidate, or [`FlightError::CityNotFound`] (with suggestions) if no
+    /// resolver knows the name
+    pub async fn resolve_one(&self, query: &str) -> Result<Location, FlightError> {
+        self.resolve(query)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| FlightError::city_not_found(query))
+    }
+}
+
+impl CityResolver for ResolverChain {
+    fn name(&self) -> &str {
+        "resolver chain"
+    }
+
+    fn resolve<'a>(&'a self, query: &'a str) -> ResolveFuture<'a> {
+        Box::pin(async move {
+            let mut last_error = None;
+            for resolver in &self.resolvers {
+                match resolver.resolve(query).await {
+                    Ok(candidates) if !candidates.is_empty() => return Ok(candidates),
+                    Ok(_) => {}
+                    Err(e @ FlightError::AmbiguousCity { .. }) => return Err(e),
+                    Err(e) => {
+                        eprintln!("⚠️  Warning: {} could not resolve '{}': {}", resolver.name(), query, e);
+                        last_error = Some(e);
+                    }
+                }
+            }
+            match last_error {
+                Some(e) => Err(e),
+                None => Ok(Vec::new()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Always fails, to check the chain keeps going
+    struct Broken;
+
+    impl CityResolver for Broken {
+        fn name(&self) -> &str {
+            "broken"
+        }
+
+        fn resolve<'a>(&'a self, _query: &'a str) -> ResolveFuture<'a> {
+            Box::pin(async { Err(FlightError::ParseError("offline".to_string())) })
+        }
+    }
+
+    fn offline_chain() -> ResolverChain {
+        ResolverChain::new()
+            .then(LocationRegistry::new().with("HQ", Location::airport("San Francisco", "sfo")))
+            .then(BundledCityCache)
+            .then(Broken)
+            .then(AirportDb::bundled())
+    }
+
+    #[tokio::test]
+    async fn test_registry_resolves_nicknames() {
+        let hq = offline_chain().resolve_one(" hq ").await.unwrap();
+        assert_eq!(hq.code, "SFO");
+        assert_eq!(hq.kind, LocationKind::Airport);
+    }
+
+    #[tokio::test]
+    async fn test_chain_order_and_fallback() {
+        let chain = offline_chain();
+        let london = chain.resolve_one("London").await.unwrap();
+        assert_eq!(london.kind, LocationKind::City);
+        assert_eq!(london.code, "/m/04jpl");
+
+        // Not in the city cache; the broken resolver is skipped and the airport DB answers
+        let manchester = chain.resolve_one("Manchester, GB").await.unwrap();
+        assert_eq!(manchester.code, "MAN");
+        assert_eq!(manchester.country_code.as_deref(), Some("GB"));
+
+        assert!(matches!(chain.resolve_one("Atlantis").await, Err(FlightError::ParseError(_))));
+        assert!(matches!(
+            ResolverChain::new().then(BundledCityCache).resolve_one("Atlantis").await,
+            Err(FlightError::CityNotFound { .. })
+        ));
+        match ResolverChain::new().then(BundledCityCache).resolve_one("Barcelnoa").await {
+            Err(FlightError::CityNotFound { name, suggestions }) => {
+                assert_eq!(name, "Barcelnoa");
+                assert_eq!(suggestions[0], "Barcelona");
+            }
+            other => panic!("Expected CityNotFound, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_airport_db_resolver() {
+        let airports = AirportDb::bundled().resolve("Tokyo").await.unwrap();
+        assert!(airports.len() >= 2);
+        assert!(airports.iter().all(|a| a.kind == LocationKind::Airport));
+        assert_eq!(AirportDb::bundled().resolve("CDG").await.unwrap()[0].code, "CDG");
+    }
+
+    #[tokio::test]
+    async fn test_first_takes_priority() {
+        let chain = ResolverChain::new()
+            .then(BundledCityCache)
+            .first(LocationRegistry::new().with("London", Location::airport("London City", "LCY")));
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.resolve_one("london").await.unwrap().code, "LCY");
+    }
+}
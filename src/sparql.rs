@@ -0,0 +1,381 @@
+//! Minimal typed SPARQL query builder for the Wikidata endpoint.
+//!
+//! Query structure only comes from `&'static str` fragments written in this crate;
+//! user input can only enter a query as an escaped [`Term::literal`] or a validated
+//! [`Term::entity`], so names like `Paris" } ...` cannot change the query.
+
+use crate::WikidataError;
+use std::fmt;
+
+/// Longest city name accepted for Wikidata lookups, in characters
+pub const MAX_CITY_NAME_LENGTH: usize = 100;
+
+/// Punctuation allowed in city names besides letters, marks, digits and spaces
+const CITY_NAME_PUNCTUATION: &[char] = &['-', '\'', '’', '.', ',', '(', ')', '&', '/'];
+
+const PREFIXES: &str = "PREFIX wd: <http://www.wikidata.org/entity/>
+PREFIX wdt: <http://www.wikidata.org/prop/direct/>
+PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+PREFIX skos: <http://www.w3.org/2004/02/skos/core#>";
+
+/// A value placed into a query
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    /// String literal, optionally language-tagged ("Paris"@en)
+    Literal { value: String, lang: Option<String> },
+    /// Wikidata item (wd:Q90)
+    Entity(String),
+}
+
+impl Term {
+    pub fn literal(value: &str) -> Self {
+        Term::Literal { value: value.to_string(), lang: None }
+    }
+
+    /// English-tagged literal, as used by Wikidata labels
+    pub fn english(value: &str) -> Self {
+        Term::Literal { value: value.to_string(), lang: Some("en".to_string()) }
+    }
+
+    /// Literal tagged with `lang` ("München"@de), rejecting malformed language tags
+    pub fn tagged(value: &str, lang: &str) -> Result<Self, WikidataError> {
+        if is_language_tag(lang) {
+            Ok(Term::Literal { value: value.to_string(), lang: Some(lang.to_string()) })
+        } else {
+            Err(WikidataError::SparqlError(format!("Invalid language tag: {:?}", lang)))
+        }
+    }
+
+    /// Wikidata item, rejecting anything that is not a Q-ID
+    pub fn entity(id: &str) -> Result<Self, WikidataError> {
+        if is_wikidata_id(id) {
+            Ok(Term::Entity(id.to_string()))
+        } else {
+            Err(WikidataError::SparqlError(format!("Invalid Wikidata ID: {:?}", id)))
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Literal { value, lang } => {
+                write!(f, "{}", escape_literal(value))?;
+                if let Some(lang) = lang {
+                    write!(f, "@{}", lang)?;
+                }
+                Ok(())
+            }
+            Term::Entity(id) => write!(f, "wd:{}", id),
+        }
+    }
+}
+
+/// A `SELECT` query over the Wikidata prefixes
+#[derive(Debug, Clone, Default)]
+pub struct SparqlQuery {
+    distinct: bool,
+    variables: Vec<&'static str>,
+    samples: Vec<(&'static str, &'static str)>,
+    lines: Vec<String>,
+    group_by: Option<&'static str>,
+    limit: Option<usize>,
+}
+
+impl SparqlQuery {
+    /// `SELECT ?a ?b ...`; variable names are given without the `?`
+    pub fn select(variables: &[&'static str]) -> Self {
+        Self { variables: variables.to_vec(), ..Self::default() }
+    }
+
+    pub fn distinct(mut self) -> Self {
+        self.distinct = true;
+        self
+    }
+
+    /// Also select `(SAMPLE(?source) AS ?variable)`, one value of `source` per group
+    pub fn sample(mut self, variable: &'static str, source: &'static str) -> Self {
+        self.samples.push((variable, source));
+        self
+    }
+
+    /// `GROUP BY ?variable`
+    pub fn group_by(mut self, variable: &'static str) -> Self {
+        self.group_by = Some(variable);
+        self
+    }
+
+    /// Patterns to be used as one side of [`Self::union`]
+    pub fn group() -> Self {
+        Self::default()
+    }
+
+    /// A fixed graph pattern or filter written in this crate
+    pub fn pattern(mut self, pattern: &'static str) -> Self {
+        self.lines.push(pattern.to_string());
+        self
+    }
+
+    /// `VALUES ?variable { term term ... }`
+    pub fn values(mut self, variable: &'static str, terms: impl IntoIterator<Item = Term>) -> Self {
+        let terms: Vec<String> = terms.into_iter().map(|t| t.to_string()).collect();
+        self.lines.push(format!("VALUES ?{} {{ {} }}", variable, terms.join(" ")));
+        self
+    }
+
+    /// Case-insensitive substring filter on a variable
+    pub fn filter_contains(mut self, variable: &'static str, needle: &Term) -> Self {
+        self.lines.push(format!("FILTER(CONTAINS(LCASE(?{}), LCASE({})))", variable, needle));
+        self
+    }
+
+    /// Keep only literals in one of `languages`; invalid language tags are skipped
+    pub fn filter_lang_in(mut self, variable: &'static str, languages: &[String]) -> Self {
+        let languages: Vec<String> = languages.iter()
+            .filter(|lang| is_language_tag(lang))
+            .map(|lang| escape_literal(lang))
+            .collect();
+        self.lines.push(format!("FILTER(LANG(?{}) IN ({}))", variable, languages.join(", ")));
+        self
+    }
+
+    /// `{ left } UNION { right }`, where both sides are built with [`Self::group`]
+    pub fn union(mut self, left: SparqlQuery, right: SparqlQuery) -> Self {
+        let block = |group: SparqlQuery| group.lines.iter()
+            .map(|line| format!("    {}\n", line))
+            .collect::<String>();
+        self.lines.push(format!("{{\n{}  }} UNION {{\n{}  }}", block(left), block(right)));
+        self
+    }
+
+    /// `{ SELECT ... }`, e.g. to limit or group rows before further patterns join in
+    pub fn subquery(mut self, inner: SparqlQuery) -> Self {
+        let body = inner.select_clause().lines()
+            .map(|line| format!("  {}", line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.lines.push(format!("{{\n{}\n  }}", body));
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn build(&self) -> String {
+        format!("{}\n\n{}\n", PREFIXES, self.select_clause())
+    }
+
+    /// The query without prefixes or trailing newline
+    fn select_clause(&self) -> String {
+        let variables: Vec<String> = self.variables.iter()
+            .map(|v| format!("?{}", v))
+            .chain(self.samples.iter().map(|(variable, source)| format!("(SAMPLE(?{}) AS ?{})", source, variable)))
+            .collect();
+        let mut query = format!(
+            "SELECT {}{} WHERE {{\n",
+            if self.distinct { "DISTINCT " } else { "" },
+            variables.join(" ")
+        );
+        for line in &self.lines {
+            query.push_str("  ");
+            query.push_str(line);
+            query.push('\n');
+        }
+        query.push('}');
+        if let Some(variable) = self.group_by {
+            query.push_str(&format!("\nGROUP BY ?{}", variable));
+        }
+        if let Some(limit) = self.limit {
+            query.push_str(&format!("\nLIMIT {}", limit));
+        }
+        query
+    }
+}
+
+/// Quote a string as a SPARQL literal, escaping quotes, backslashes and control characters
+pub fn escape_literal(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Check a city name before it is sent to Wikidata: at most
+/// [`MAX_CITY_NAME_LENGTH`] characters of letters, digits, spaces and common name
+/// punctuation ("Val-d'Or", "St. John's", "Washington, D.C.")
+pub fn validate_city_name(name: &str) -> Result<&str, WikidataError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(WikidataError::InvalidCityName("City name is empty".to_string()));
+    }
+    if trimmed.chars().count() > MAX_CITY_NAME_LENGTH {
+        return Err(WikidataError::InvalidCityName(format!(
+            "City name is longer than {} characters",
+            MAX_CITY_NAME_LENGTH
+        )));
+    }
+    if let Some(bad) = trimmed.chars().find(|c| !is_city_name_char(*c)) {
+        return Err(WikidataError::InvalidCityName(format!(
+            "City name {:?} contains unsupported character {:?}",
+            trimmed, bad
+        )));
+    }
+    Ok(trimmed)
+}
+
+fn is_city_name_char(c: char) -> bool {
+    // Combining marks are not alphabetic but appear in decomposed names
+    c.is_alphanumeric() || c == ' ' || CITY_NAME_PUNCTUATION.contains(&c) || ('\u{0300}'..='\u{036F}').contains(&c)
+}
+
+/// Whether `lang` looks like a BCP 47 language tag as used by Wikidata ("en", "zh-hans")
+pub fn is_language_tag(lang: &str) -> bool {
+    let mut parts = lang.split('-');
+    let primary = parts.next().unwrap_or_default();
+    (2..=3).contains(&primary.len())
+        && primary.chars().all(|c| c.is_ascii_lowercase())
+        && parts.all(|part| (1..=8).contains(&part.len()) && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Whether `id` looks like a Wikidata item ID ("Q90")
+pub fn is_wikidata_id(id: &str) -> bool {
+    id.len() > 1 && id.starts_with('Q') && id[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOSTILE_NAMES: &[&str] = &[
+        r#"Paris" } ; DELETE WHERE { ?s ?p ?o } #"#,
+        "London}}",
+        r#"a\"b"#,
+        "Tokyo\n} LIMIT 1",
+        "Berlin # comment",
+        "Rome<script>",
+        "Oslo\u{0000}",
+    ];
+
+    #[test]
+    fn test_escape_literal() {
+        assert_eq!(escape_literal("Paris"), r#""Paris""#);
+        assert_eq!(escape_literal(r#"a"b\c"#), r#""a\"b\\c""#);
+        assert_eq!(escape_literal("a\nb\u{7}"), r#""a\nb\u0007""#);
+        assert_eq!(Term::english("São Paulo").to_string(), r#""São Paulo"@en"#);
+    }
+
+    #[test]
+    fn test_hostile_literals_stay_inside_quotes() {
+        for name in HOSTILE_NAMES {
+            let literal = escape_literal(name);
+            // Every quote inside the literal is escaped, so the literal ends where it should
+            let inner = &literal[1..literal.len() - 1];
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => assert!(chars.next().is_some(), "dangling escape in {}", literal),
+                    '"' => panic!("unescaped quote in {}", literal),
+                    c => assert!(!c.is_control(), "raw control character in {}", literal),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_city_name() {
+        for name in ["Paris", "São Paulo", "Val-d'Or", "St. John's", "Washington, D.C.", "北京", "Zürich", "Ho Chi Minh City"] {
+            assert!(validate_city_name(name).is_ok(), "{} rejected", name);
+        }
+        assert_eq!(validate_city_name("  Lyon ").unwrap(), "Lyon");
+
+        for name in HOSTILE_NAMES {
+            assert!(validate_city_name(name).is_err(), "{:?} accepted", name);
+        }
+        assert!(validate_city_name("   ").is_err());
+        assert!(validate_city_name(&"a".repeat(MAX_CITY_NAME_LENGTH + 1)).is_err());
+        assert!(validate_city_name(&"a".repeat(MAX_CITY_NAME_LENGTH)).is_ok());
+    }
+
+    #[test]
+    fn test_entity_terms() {
+        assert_eq!(Term::entity("Q90").unwrap().to_string(), "wd:Q90");
+        for id in ["Q", "q90", "Q90 }", "P17", "Q9x"] {
+            assert!(Term::entity(id).is_err(), "{} accepted", id);
+        }
+    }
+
+    #[test]
+    fn test_language_tags() {
+        assert_eq!(Term::tagged("München", "de").unwrap().to_string(), r#""München"@de"#);
+        for lang in ["en", "zh-hans", "pt-br", "yue"] {
+            assert!(is_language_tag(lang), "{} rejected", lang);
+        }
+        for lang in ["", "e", "EN", "en\"", "de) } #", "toolong"] {
+            assert!(Term::tagged("x", lang).is_err(), "{:?} accepted", lang);
+        }
+
+        let query = SparqlQuery::select(&["label"])
+            .filter_lang_in("label", &["en".to_string(), "de\")".to_string(), "ja".to_string()])
+            .build();
+        assert!(query.contains(r#"FILTER(LANG(?label) IN ("en", "ja"))"#));
+    }
+
+    #[test]
+    fn test_union() {
+        let query = SparqlQuery::select(&["city"])
+            .union(
+                SparqlQuery::group().pattern("?city rdfs:label ?matched ."),
+                SparqlQuery::group().pattern("?city skos:altLabel ?matched ."),
+            )
+            .build();
+        assert!(query.contains("  {\n    ?city rdfs:label ?matched .\n  } UNION {\n    ?city skos:altLabel ?matched .\n  }\n"));
+    }
+
+    #[test]
+    fn test_grouped_subquery() {
+        let query = SparqlQuery::select(&["city", "population"])
+            .subquery(
+                SparqlQuery::select(&["city"])
+                    .sample("matched", "label")
+                    .pattern("?city rdfs:label ?label .")
+                    .group_by("city")
+                    .limit(3),
+            )
+            .pattern("OPTIONAL { ?city wdt:P1082 ?population . }")
+            .build();
+        assert!(query.contains(
+            "  {\n  SELECT ?city (SAMPLE(?label) AS ?matched) WHERE {\n    ?city rdfs:label ?label .\n  }\n  GROUP BY ?city\n  LIMIT 3\n  }\n"
+        ));
+        assert!(query.trim_end().ends_with("wdt:P1082 ?population . }\n}"));
+    }
+
+    #[test]
+    fn test_build_query() {
+        let query = SparqlQuery::select(&["city", "cityLabel"])
+            .distinct()
+            .pattern("?city rdfs:label ?cityLabel .")
+            .filter_contains("cityLabel", &Term::literal(r#"Paris" } #"#))
+            .values("city", [Term::entity("Q90").unwrap(), Term::entity("Q60").unwrap()])
+            .limit(5)
+            .build();
+
+        assert!(query.starts_with("PREFIX wd:"));
+        assert!(query.contains("SELECT DISTINCT ?city ?cityLabel WHERE {"));
+        assert!(query.contains(r#"FILTER(CONTAINS(LCASE(?cityLabel), LCASE("Paris\" } #")))"#));
+        assert!(query.contains("VALUES ?city { wd:Q90 wd:Q60 }"));
+        assert!(query.trim_end().ends_with("}\nLIMIT 5"));
+    }
+}
@@ -0,0 +1,308 @@
+//! Split-ticket search: two separate one-way tickets via a hub, compared with
+//! the through fare.
+
+use crate::dates::{cheapest_flight, format_date, parse_date};
+use crate::{Flight, FlightClient, FlightError, FlightPrice, SearchOptions};
+use chrono::{Duration as ChronoDuration, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+/// Default minimum time between the two tickets at the hub (bags, re-check-in, security)
+pub const DEFAULT_MIN_CONNECTION_MINUTES: i64 = 180;
+
+/// Default maximum time between the two tickets at the hub
+pub const DEFAULT_MAX_CONNECTION_MINUTES: i64 = 12 * 60;
+
+/// Maximum number of split itineraries kept in a result
+pub const MAX_SPLIT_ITINERARIES: usize = 50;
+
+/// Parameters for a split-ticket search
+#[derive(Debug, Clone)]
+pub struct SplitTicketRequest {
+    pub origin: String,
+    pub destination: String,
+    /// Departure date of the first ticket (YYYY-MM-DD)
+    pub date: String,
+    /// Hub airports to try connecting through
+    pub hubs: Vec<String>,
+    pub min_connection_minutes: i64,
+    pub max_connection_minutes: i64,
+    pub options: SearchOptions,
+}
+
+impl SplitTicketRequest {
+    pub fn new(origin: &str, destination: &str, date: &str, hubs: Vec<String>) -> Self {
+        Self {
+            origin: origin.to_string(),
+            destination: destination.to_string(),
+            date: date.to_string(),
+            hubs,
+            min_connection_minutes: DEFAULT_MIN_CONNECTION_MINUTES,
+            max_connection_minutes: DEFAULT_MAX_CONNECTION_MINUTES,
+            options: SearchOptions::default(),
+        }
+    }
+}
+
+/// Two separate tickets joined by a self-transfer at `hub`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitItinerary {
+    pub hub: String,
+    pub first: Flight,
+    pub second: Flight,
+    pub total_price: FlightPrice,
+    pub connection_minutes: i64,
+    /// Time in the air plus the connection, if both durations were parsed
+    pub total_minutes: Option<i64>,
+}
+
+/// Split-ticket itineraries compared with the direct (through-fare) result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitTicketResult {
+    pub origin: String,
+    pub destination: String,
+    pub date: String,
+    /// Cheapest through fare from origin to destination
+    pub direct: Option<Flight>,
+    pub direct_error: Option<String>,
+    /// Valid split itineraries, cheapest first
+    pub itineraries: Vec<SplitItinerary>,
+    /// Hubs whose searches failed, with the error
+    pub hub_errors: Vec<(String, String)>,
+}
+
+impl SplitTicketResult {
+    /// The cheapest split itinerary
+    pub fn cheapest(&self) -> Option<&SplitItinerary> {
+        self.itineraries.first()
+    }
+
+    /// How much the cheapest split saves over the cheapest through fare
+    /// (negative when the through fare is cheaper)
+    pub fn savings(&self) -> Option<i32> {
+        let direct = self.direct.as_ref()?.price.amount;
+        Some(direct - self.cheapest()?.total_price.amount)
+    }
+}
+
+/// Combine first and second tickets whose self-transfer at the hub is within
+/// `[min_connection, max_connection]` minutes, cheapest first.
+///
+/// Each flight is paired with the date it was searched on, which anchors the
+/// parsed local departure and arrival times.
+pub fn combine_split_tickets(
+    hub: &str,
+    first: &[(NaiveDate, Flight)],
+    second: &[(NaiveDate, Flight)],
+    min_connection_minutes: i64,
+    max_connection_minutes: i64,
+) -> Vec<SplitItinerary> {
+    let timed = |flights: &[(NaiveDate, Flight)], arrival: bool| -> Vec<(NaiveDateTime, Flight)> {
+        flights.iter()
+            .filter(|(_, f)| f.price.amount > 0)
+            .filter_map(|(date, f)| {
+                let time = if arrival { f.arrival_datetime(*date) } else { f.departure_datetime(*date) };
+                time.map(|t| (t, f.clone()))
+            })
+            .collect()
+    };
+    let arrivals = timed(first, true);
+    let departures = timed(second, false);
+
+    let mut itineraries = Vec::new();
+    for (arrival, first) in &arrivals {
+        for (departure, second) in &departures {
+            let connection = (*departure - *arrival).num_minutes();
+            if connection < min_connection_minutes || connection > max_connection_minutes {
+                continue;
+            }
+
+            let total_minutes = match (first.duration_minutes(), second.duration_minutes()) {
+                (Some(a), Some(b)) => Some(a as i64 + connection + b as i64),
+                _ => None,
+            };
+            itineraries.push(SplitItinerary {
+                hub: hub.to_string(),
+                first: first.clone(),
+                second: second.clone(),
+                total_price: FlightPrice {
+                    amount: first.price.amount + second.price.amount,
+                    currency: first.price.currency.clone(),
+                },
+                connection_minutes: connection,
+                total_minutes,
+            });
+        }
+    }
+
+    sort_itineraries(&mut itineraries);
+    itineraries
+}
+
+/// Cheapest first, then shortest overall
+fn sort_itineraries(itineraries: &mut [SplitItinerary]) {
+    itineraries.sort_by_key(|i| (i.total_price.amount, i.total_minutes.unwrap_or(i64::MAX)));
+}
+
+impl FlightClient {
+    /// Search origin → hub and hub → destination one-ways for every hub and combine
+    /// them into self-transfer itineraries, alongside the direct origin → destination
+    /// result for comparison.
+    ///
+    /// Hub → destination flights are searched on every date a connection could fall
+    /// on (usually the travel date, sometimes the next day). A failed hub is recorded
+    /// in `hub_errors` rather than aborting the search.
+    pub async fn search_split_tickets(&self, request: &SplitTicketRequest) -> Result<SplitTicketResult, FlightError> {
+        let date = parse_date(&request.date)?;
+        if request.min_connection_minutes > request.max_connection_minutes {
+            return Err(FlightError::ParseError(
+                "Minimum connection time must not exceed the maximum".to_string(),
+            ));
+        }
+
+        let options = &request.options;
+        let mut result = SplitTicketResult {
+            origin: request.origin.clone(),
+            destination: request.destination.clone(),
+            date: request.date.clone(),
+            direct: None,
+            direct_error: None,
+            itineraries: Vec::new(),
+            hub_errors: Vec::new(),
+        };
+
+        match self.get_flights(options.one_way_request(&request.origin, &request.destination, &request.date)).await {
+            Ok(direct) => result.direct = cheapest_flight(&direct.flights).cloned(),
+            Err(e) => {
+                eprintln!("⚠️  Warning: Direct search failed: {}", e);
+                result.direct_error = Some(e.to_string());
+            }
+        }
+
+        for hub in &request.hubs {
+            if *hub == request.origin || *hub == request.destination {
+                continue;
+            }
+            tokio::time::sleep(options.request_delay).await;
+
+            let first: Vec<(NaiveDate, Flight)> = match self.get_flights(options.one_way_request(&request.origin, hub, &request.date)).await {
+                Ok(found) => found.flights.into_iter().map(|f| (date, f)).collect(),
+                Err(e) => {
+                    eprintln!("⚠️  Warning: Search {} → {} failed: {}", request.origin, hub, e);
+                    result.hub_errors.push((hub.clone(), e.to_string()));
+                    continue;
+                }
+            };
+
+            let mut second = Vec::new();
+            for second_date in connection_dates(&first, request.min_connection_minutes, request.max_connection_minutes) {
+                tokio::time::sleep(options.request_delay).await;
+                let second_date_str = format_date(second_date);
+                match self.get_flights(options.one_way_request(hub, &request.destination, &second_date_str)).await {
+                    Ok(found) => second.extend(found.flights.into_iter().map(|f| (second_date, f))),
+                    Err(e) => {
+                        eprintln!("⚠️  Warning: Search {} → {} on {} failed: {}", hub, request.destination, second_date_str, e);
+                        result.hub_errors.push((hub.clone(), e.to_string()));
+                    }
+                }
+            }
+
+            result.itineraries.extend(combine_split_tickets(
+                hub,
+                &first,
+                &second,
+                request.min_connection_minutes,
+                request.max_connection_minutes,
+            ));
+        }
+
+        sort_itineraries(&mut result.itineraries);
+        result.itineraries.truncate(MAX_SPLIT_ITINERARIES);
+        Ok(result)
+    }
+}
+
+/// Dates on which a second ticket could depart given the first tickets' hub arrivals
+fn connection_dates(first: &[(NaiveDate, Flight)], min_connection_minutes: i64, max_connection_minutes: i64) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    for (date, flight) in first {
+        let Some(arrival) = flight.arrival_datetime(*date) else {
+            continue;
+        };
+        let earliest = (arrival + ChronoDuration::minutes(min_connection_minutes)).date();
+        let latest = (arrival + ChronoDuration::minutes(max_connection_minutes)).date();
+        for day in earliest.iter_days().take_while(|d| *d <= latest) {
+            if !dates.contains(&day) {
+                dates.push(day);
+            }
+        }
+    }
+    dates.sort();
+    dates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flight(name: &str, departure: &str, arrival: &str, duration: &str, amount: i32) -> Flight {
+        Flight::test(name, amount).schedule(departure, arrival, duration)
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 9, d).unwrap()
+    }
+
+    #[test]
+    fn test_combine_split_tickets() {
+        let first = vec![
+            (day(1), flight("Early", "6:00 AM on Mon, Sep 1", "9:00 AM on Mon, Sep 1", "3 hr", 100)),
+            (day(1), flight("Late", "4:00 PM on Mon, Sep 1", "7:00 PM on Mon, Sep 1", "3 hr", 60)),
+        ];
+        let second = vec![
+            (day(1), flight("Noon", "12:30 PM on Mon, Sep 1", "9:00 PM on Mon, Sep 1", "8 hr 30 min", 300)),
+            (day(1), flight("Evening", "8:00 PM on Mon, Sep 1", "6:00 AM on Tue, Sep 2", "10 hr", 250)),
+        ];
+
+        let itineraries = combine_split_tickets("DEN", &first, &second, 180, 720);
+        let pairs: Vec<(&str, &str)> = itineraries.iter().map(|i| (i.first.name.as_str(), i.second.name.as_str())).collect();
+        // Late→Evening is only a 60 minute connection
+        assert_eq!(pairs, vec![("Early", "Evening"), ("Early", "Noon")]);
+        assert_eq!(itineraries[0].total_price.amount, 350);
+        assert_eq!(itineraries[0].connection_minutes, 660);
+        assert_eq!(itineraries[1].connection_minutes, 210);
+        assert_eq!(itineraries[1].total_minutes, Some(180 + 210 + 510));
+    }
+
+    #[test]
+    fn test_connection_dates() {
+        let first = vec![
+            (day(1), flight("Morning", "6:00 AM", "9:00 AM", "3 hr", 100)),
+            (day(1), flight("Night", "6:00 PM", "11:00 PM", "5 hr", 100)),
+        ];
+        assert_eq!(connection_dates(&first, 180, 720), vec![day(1), day(2)]);
+        assert_eq!(connection_dates(&first[..1], 180, 720), vec![day(1)]);
+    }
+
+    #[test]
+    fn test_savings() {
+        let split = combine_split_tickets(
+            "DEN",
+            &[(day(1), flight("A", "6:00 AM", "9:00 AM", "3 hr", 100))],
+            &[(day(1), flight("B", "1:00 PM", "5:00 PM", "4 hr", 150))],
+            180,
+            720,
+        );
+        let mut result = SplitTicketResult {
+            origin: "SEA".to_string(),
+            destination: "MIA".to_string(),
+            date: "2025-09-01".to_string(),
+            direct: Some(flight("Direct", "7:00 AM", "4:00 PM", "6 hr", 420)),
+            direct_error: None,
+            itineraries: split,
+            hub_errors: Vec::new(),
+        };
+        assert_eq!(result.savings(), Some(170));
+        result.direct = None;
+        assert_eq!(result.savings(), None);
+    }
+}
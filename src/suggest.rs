@@ -0,0 +1,188 @@
+//! "Did you mean" suggestions for misspelled city names.
+//!
+//! Known names come from the bundled city cache and alias table, the offline
+//! airport database (cities and metro areas) and, with the `city-search`
+//! feature, the persistent city store. A name is suggested when it is within a
+//! few typos of the query or shares most of its trigrams.
+
+use crate::names::{city_key, edit_distance, trigram_similarity};
+use crate::wikidata::{bundled_city_aliases, bundled_city_cache};
+use crate::AirportDb;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// Suggestions attached to a [`crate::FlightError::CityNotFound`]
+pub const MAX_CITY_SUGGESTIONS: usize = 3;
+
+/// Trigram similarity above which a name is suggested regardless of edit distance
+const TRIGRAM_THRESHOLD: f64 = 0.5;
+
+/// A set of known city names to suggest from
+#[derive(Debug, Clone, Default)]
+pub struct CitySuggester {
+    /// (display name, city key)
+    names: Vec<(String, String)>,
+    keys: HashSet<String>,
+}
+
+impl CitySuggester {
+    /// An empty suggester
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names from the bundled city cache, alias table and airport database
+    pub fn bundled() -> Self {
+        let db = AirportDb::bundled();
+        let mut cities: Vec<&String> = bundled_city_cache().keys().chain(bundled_city_aliases().keys()).collect();
+        cities.sort();
+        Self::new()
+            .with_names(cities)
+            .with_names(db.airports().iter().map(|airport| &airport.city))
+            .with_names(db.metro_areas().iter().map(|metro| &metro.name))
+    }
+
+    /// [`Self::bundled`] plus the shared city store (`city-search` feature)
+    pub fn standard() -> Self {
+        #[allow(unused_mut)]
+        let mut suggester = Self::bundled();
+        #[cfg(feature = "city-search")]
+        if let Some(store) = crate::CityStore::shared() {
+            suggester = suggester.with_store(&store);
+        }
+        suggester
+    }
+
+    /// Add names; spellings of a name already known are skipped
+    pub fn with_names<S: AsRef<str>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        for name in names {
+            let name = name.as_ref().trim();
+            let key = city_key(name);
+            if !key.is_empty() && self.keys.insert(key.clone()) {
+                self.names.push((name.to_string(), key));
+            }
+        }
+        self
+    }
+
+    /// Add every city and alias in `store`
+    #[cfg(feature = "city-search")]
+    pub fn with_store(self, store: &crate::CityStore) -> Self {
+        match store.cities() {
+            Ok(cities) => self.with_names(cities.into_iter().flat_map(|city| std::iter::once(city.name).chain(city.aliases))),
+            Err(e) => {
+                eprintln!("⚠️  Warning: City store unavailable for suggestions: {}", e);
+                self
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Up to `limit` known names close to `query`, closest first. A name equal to
+    /// the query (ignoring case and accents) is not suggested.
+    pub fn suggest(&self, query: &str, limit: usize) -> Vec<String> {
+        self.suggest_with(&CitySuggester::new(), query, limit)
+    }
+
+    /// Like [`Self::suggest`], also drawing on the names of `extra` that this
+    /// suggester does not know, without merging the two sets
+    pub fn suggest_with(&self, extra: &CitySuggester, query: &str, limit: usize) -> Vec<String> {
+        let query = city_key(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let budget = typo_budget(&query);
+
+        let names = self.names.iter()
+            .chain(extra.names.iter().filter(|(_, key)| !self.keys.contains(key)));
+        let mut matches: Vec<(usize, f64, &str)> = names
+            .filter(|(_, key)| *key != query && key.chars().count().abs_diff(query.chars().count()) <= budget.max(3))
+            .filter_map(|(name, key)| {
+                let distance = edit_distance(&query, key);
+                let similarity = trigram_similarity(&query, key);
+                (distance <= budget || similarity >= TRIGRAM_THRESHOLD).then_some((distance, similarity, name.as_str()))
+            })
+            .collect();
+        matches.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)).then(a.2.cmp(b.2)));
+        matches.into_iter().take(limit).map(|(_, _, name)| name.to_string()).collect()
+    }
+}
+
+/// Typos tolerated in a name of this length
+fn typo_budget(key: &str) -> usize {
+    match key.chars().count() {
+        0..=4 => 1,
+        5..=8 => 2,
+        _ => 3,
+    }
+}
+
+/// Suggestions for a city name that could not be resolved, from the same names as
+/// [`CitySuggester::standard`]. The bundled names are indexed once per process;
+/// only the city store is read again, as it grows while the process runs.
+pub fn suggest_cities(query: &str) -> Vec<String> {
+    static BUNDLED: OnceLock<CitySuggester> = OnceLock::new();
+    let bundled = BUNDLED.get_or_init(CitySuggester::bundled);
+    #[allow(unused_mut)]
+    let mut stored = CitySuggester::new();
+    #[cfg(feature = "city-search")]
+    if let Some(store) = crate::CityStore::shared() {
+        stored = stored.with_store(&store);
+    }
+    bundled.suggest_with(&stored, query, MAX_CITY_SUGGESTIONS)
+}
+
+/// ". Did you mean: Barcelona, Barcelos?" (empty without suggestions), appended to error messages
+pub(crate) fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(". Did you mean: {}?", suggestions.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_typos() {
+        let suggester = CitySuggester::bundled();
+        assert!(suggester.len() > 300);
+        assert_eq!(suggester.suggest("Barcelnoa", 3)[0], "Barcelona");
+        assert_eq!(suggester.suggest("Los Angles", 3)[0], "Los Angeles");
+        assert_eq!(suggester.suggest("san fransisco", 3)[0], "San Francisco");
+        assert_eq!(suggester.suggest("Muenchn", 3)[0], "Muenchen");
+        assert!(suggester.suggest("Barcelnoa", 1).len() == 1);
+    }
+
+    #[test]
+    fn test_no_suggestions_for_exact_or_unrelated_names() {
+        let suggester = CitySuggester::new().with_names(["Paris", "Parma", "PARIS"]);
+        assert_eq!(suggester.len(), 2);
+        assert_eq!(suggester.suggest("paris", 3), vec!["Parma".to_string()]);
+        assert!(suggester.suggest("Xyzzyville", 3).is_empty());
+        assert!(suggester.suggest("  ", 3).is_empty());
+    }
+
+    #[test]
+    fn test_suggest_with_extra_names() {
+        let bundled = CitySuggester::new().with_names(["Paris", "Parma"]);
+        let stored = CitySuggester::new().with_names(["PARMA", "Pariz"]);
+        assert_eq!(bundled.suggest_with(&stored, "Parus", 3), vec!["Paris".to_string(), "Pariz".to_string(), "Parma".to_string()]);
+        assert_eq!(bundled.suggest_with(&CitySuggester::new(), "Parus", 3), bundled.suggest("Parus", 3));
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        assert_eq!(did_you_mean(&[]), "");
+        assert_eq!(did_you_mean(&["Barcelona".to_string()]), ". Did you mean: Barcelona?");
+    }
+}
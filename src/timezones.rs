@@ -0,0 +1,445 @@
+//! Time-zone-aware flight and itinerary times.
+//!
+//! Google Flights reports departure and arrival times local to each airport.
+//! Using the airport database's IANA time zones, these are turned into aware
+//! `DateTime`s, so elapsed time is exact across zones and a schedule can be
+//! shown in any office's time ("arrives 12:00 PDT / 15:00 EDT").
+//!
+//! Layover times are estimates: Google only reports how long each layover is,
+//! so the flying time is split between the segments in proportion to their
+//! great-circle distance.
+
+use crate::airports::AirportDb;
+use crate::dates::parse_date;
+use crate::itinerary::PricedItinerary;
+use crate::{Flight, FlightError};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+pub use chrono_tz::Tz;
+use serde::Serialize;
+
+/// Common time zone abbreviations and the zone they stand for
+const ZONE_ABBREVIATIONS: &[(&str, &str)] = &[
+    ("PT", "America/Los_Angeles"),
+    ("PST", "America/Los_Angeles"),
+    ("PDT", "America/Los_Angeles"),
+    ("MT", "America/Denver"),
+    ("MST", "America/Denver"),
+    ("MDT", "America/Denver"),
+    ("CT", "America/Chicago"),
+    ("CST", "America/Chicago"),
+    ("CDT", "America/Chicago"),
+    ("ET", "America/New_York"),
+    ("EST", "America/New_York"),
+    ("EDT", "America/New_York"),
+    ("AKT", "America/Anchorage"),
+    ("HT", "Pacific/Honolulu"),
+    ("HST", "Pacific/Honolulu"),
+    ("UK", "Europe/London"),
+    ("BST", "Europe/London"),
+    ("CET", "Europe/Paris"),
+    ("CEST", "Europe/Paris"),
+    ("EET", "Europe/Athens"),
+    ("IST", "Asia/Kolkata"),
+    ("SGT", "Asia/Singapore"),
+    ("HKT", "Asia/Hong_Kong"),
+    ("JST", "Asia/Tokyo"),
+    ("KST", "Asia/Seoul"),
+    ("AET", "Australia/Sydney"),
+    ("AEST", "Australia/Sydney"),
+    ("AEDT", "Australia/Sydney"),
+    ("NZT", "Pacific/Auckland"),
+];
+
+/// Time zone of an airport in the bundled database
+pub fn airport_timezone(code: &str) -> Option<Tz> {
+    AirportDb::bundled().by_code(code)?.timezone.parse().ok()
+}
+
+/// Parse a time zone given as an IANA name ("Europe/London"), a common
+/// abbreviation ("PT", "ET", "CET", "UTC") or an airport code ("JFK")
+pub fn parse_timezone(name: &str) -> Result<Tz, FlightError> {
+    let name = name.trim();
+    if let Ok(tz) = name.parse::<Tz>() {
+        return Ok(tz);
+    }
+    let upper = name.to_uppercase();
+    if let Some((_, zone)) = ZONE_ABBREVIATIONS.iter().find(|(abbreviation, _)| *abbreviation == upper) {
+        return zone.parse().map_err(|e| FlightError::ParseError(format!("Invalid time zone '{}': {}", zone, e)));
+    }
+    airport_timezone(&upper)
+        .ok_or_else(|| FlightError::ParseError(format!("Unknown time zone '{}': use an IANA name, PT/ET-style abbreviation or airport code", name)))
+}
+
+/// Parse a comma-separated list of time zones ("PT,ET,Europe/London")
+pub fn parse_timezones(list: &str) -> Result<Vec<Tz>, FlightError> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(parse_timezone)
+        .collect()
+}
+
+/// A local wall-clock time at `tz`, taking the earlier instant when a DST change makes it ambiguous
+fn localize(tz: Tz, local: NaiveDateTime) -> Option<DateTime<Tz>> {
+    tz.from_local_datetime(&local).earliest()
+        // Times in a DST gap do not exist; Google shows them an hour early
+        .or_else(|| tz.from_local_datetime(&(local + Duration::hours(1))).earliest())
+}
+
+/// Days between a local departure and an arrival at `arrival_time` that best fit a
+/// flight of `duration_minutes`: the offset whose elapsed time across the two
+/// airports' zones is non-negative and closest to the duration. SYD 10:00 → LAX 06:00
+/// on a 13-hour flight lands the same calendar day.
+pub(crate) fn arrival_day_offset(
+    origin: &str,
+    destination: &str,
+    departure: NaiveDateTime,
+    arrival_time: NaiveTime,
+    duration_minutes: i32,
+) -> Option<i64> {
+    let departed = localize(airport_timezone(origin)?, departure)?;
+    let zone = airport_timezone(destination)?;
+    (-1..=2)
+        .filter_map(|offset| {
+            let arrival = localize(zone, (departure.date() + Duration::days(offset)).and_time(arrival_time))?;
+            let elapsed = (arrival - departed).num_minutes();
+            (elapsed >= 0).then(|| (offset, (elapsed - duration_minutes as i64).abs()))
+        })
+        .min_by_key(|(_, miss)| *miss)
+        .map(|(offset, _)| offset)
+}
+
+/// "12:00 PDT", with a "+1" day marker when the date differs from `reference`
+fn format_clock(time: &DateTime<Tz>, reference: NaiveDate) -> String {
+    let days = (time.date_naive() - reference).num_days();
+    match days {
+        0 => time.format("%H:%M %Z").to_string(),
+        d if d > 0 => format!("{}+{}", time.format("%H:%M %Z"), d),
+        d => format!("{}{}", time.format("%H:%M %Z"), d),
+    }
+}
+
+/// `time` in each of `zones`, joined with " / ": "09:00 PDT / 12:00 EDT".
+///
+/// A "+1" (or "-1") marks a zone where the date differs from the first zone's.
+pub fn format_in_zones(time: &DateTime<Tz>, zones: &[Tz]) -> String {
+    let first = match zones.first() {
+        Some(first) => time.with_timezone(first).date_naive(),
+        None => return format_clock(time, time.date_naive()),
+    };
+    zones.iter()
+        .map(|tz| format_clock(&time.with_timezone(tz), first))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+/// "5h 30m"
+fn format_minutes(minutes: i64) -> String {
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// An estimated connection at an intermediate airport
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LayoverTimes {
+    pub airport: String,
+    pub minutes: i32,
+    /// Arrival at the layover airport, in its local time
+    pub arrival: DateTime<Tz>,
+    /// Onward departure, in the layover airport's local time
+    pub departure: DateTime<Tz>,
+}
+
+/// Departure, arrival and layover times of a flight, each in its airport's time zone
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FlightTimes {
+    pub origin: String,
+    pub destination: String,
+    pub departure: DateTime<Tz>,
+    pub arrival: DateTime<Tz>,
+    /// Empty when the layover airports or durations were not reported
+    pub layovers: Vec<LayoverTimes>,
+}
+
+impl FlightTimes {
+    /// Attach airport time zones to local departure and arrival times.
+    ///
+    /// `layovers` are (airport, minutes) pairs in flight order. Returns `None` if an
+    /// airport's time zone is unknown or the arrival would precede the departure.
+    pub fn new(
+        origin: &str,
+        destination: &str,
+        local_departure: NaiveDateTime,
+        local_arrival: NaiveDateTime,
+        layovers: &[(String, i32)],
+    ) -> Option<Self> {
+        let departure = localize(airport_timezone(origin)?, local_departure)?;
+        let arrival = localize(airport_timezone(destination)?, local_arrival)?;
+        if arrival < departure {
+            return None;
+        }
+
+        let mut times = Self {
+            origin: origin.to_uppercase(),
+            destination: destination.to_uppercase(),
+            departure,
+            arrival,
+            layovers: Vec::new(),
+        };
+        times.layovers = times.estimate_layovers(layovers).unwrap_or_default();
+        Some(times)
+    }
+
+    /// Split the flying time between segments by distance to place each layover
+    fn estimate_layovers(&self, layovers: &[(String, i32)]) -> Option<Vec<LayoverTimes>> {
+        if layovers.is_empty() {
+            return Some(Vec::new());
+        }
+        let db = AirportDb::bundled();
+        let stops: Vec<_> = std::iter::once(self.origin.as_str())
+            .chain(layovers.iter().map(|(airport, _)| airport.as_str()))
+            .chain(std::iter::once(self.destination.as_str()))
+            .map(|code| db.by_code(code))
+            .collect::<Option<_>>()?;
+
+        let connecting: i64 = layovers.iter().map(|(_, minutes)| *minutes as i64).sum();
+        let flying = self.elapsed_minutes() - connecting;
+        if flying <= 0 {
+            return None;
+        }
+        let distances: Vec<f64> = stops.windows(2).map(|pair| pair[0].distance_km(pair[1])).collect();
+        let total_km: f64 = distances.iter().sum();
+
+        let mut clock = self.departure.with_timezone(&chrono::Utc);
+        let mut estimated = Vec::with_capacity(layovers.len());
+        for (i, (airport, minutes)) in layovers.iter().enumerate() {
+            let share = if total_km > 0.0 { distances[i] / total_km } else { 1.0 / distances.len() as f64 };
+            clock += Duration::minutes((flying as f64 * share).round() as i64);
+            let tz: Tz = stops[i + 1].timezone.parse().ok()?;
+            let arrival = clock.with_timezone(&tz);
+            clock += Duration::minutes(*minutes as i64);
+            estimated.push(LayoverTimes {
+                airport: airport.to_uppercase(),
+                minutes: *minutes,
+                arrival,
+                departure: clock.with_timezone(&tz),
+            });
+        }
+        Some(estimated)
+    }
+
+    /// True time from departure to arrival, regardless of time zones
+    pub fn elapsed(&self) -> Duration {
+        self.arrival.signed_duration_since(self.departure)
+    }
+
+    pub fn elapsed_minutes(&self) -> i64 {
+        self.elapsed().num_minutes()
+    }
+
+    /// Departure time in another zone
+    pub fn departure_in(&self, tz: Tz) -> DateTime<Tz> {
+        self.departure.with_timezone(&tz)
+    }
+
+    /// Arrival time in another zone
+    pub fn arrival_in(&self, tz: Tz) -> DateTime<Tz> {
+        self.arrival.with_timezone(&tz)
+    }
+
+    /// "LAX 06:00 PDT → JFK 14:30 EDT (5h 30m)", with "+1" on a next-day arrival
+    pub fn local_summary(&self) -> String {
+        format!(
+            "{} {} → {} {} ({})",
+            self.origin,
+            format_clock(&self.departure, self.departure.date_naive()),
+            self.destination,
+            format_clock(&self.arrival, self.departure.date_naive()),
+            format_minutes(self.elapsed_minutes()),
+        )
+    }
+
+    /// "LAX → JFK: departs 06:00 PDT / 09:00 EDT, arrives 11:30 PDT / 14:30 EDT (5h 30m)"
+    pub fn summary_in(&self, zones: &[Tz]) -> String {
+        format!(
+            "{} → {}: departs {}, arrives {} ({})",
+            self.origin,
+            self.destination,
+            format_in_zones(&self.departure, zones),
+            format_in_zones(&self.arrival, zones),
+            format_minutes(self.elapsed_minutes()),
+        )
+    }
+}
+
+impl Flight {
+    /// Time-zone-aware times for a flight found by a search on `search_date`, using
+    /// its `origin_airport` and `destination_airport`
+    pub fn times(&self, search_date: NaiveDate) -> Option<FlightTimes> {
+        let origin = self.origin_airport.as_deref()?;
+        let destination = self.destination_airport.as_deref()?;
+        self.times_between(origin, destination, search_date)
+    }
+
+    /// Like [`Flight::times`], for a flight known to fly `origin` → `destination`
+    pub fn times_between(&self, origin: &str, destination: &str, search_date: NaiveDate) -> Option<FlightTimes> {
+        let durations = self.layover_minutes();
+        let layovers: Vec<(String, i32)> = match &self.layovers {
+            Some(airports) if airports.len() == durations.len() => airports.iter().cloned().zip(durations).collect(),
+            _ => Vec::new(),
+        };
+        FlightTimes::new(
+            origin,
+            destination,
+            self.departure_datetime(search_date)?,
+            self.arrival_datetime_between(Some((origin, destination)), search_date)?,
+            &layovers,
+        )
+    }
+}
+
+impl PricedItinerary {
+    /// Times of the cheapest one-way flight on each leg (`None` where a leg
+    /// has no flight or its times could not be placed)
+    pub fn leg_times(&self) -> Vec<Option<FlightTimes>> {
+        self.legs.iter()
+            .map(|quote| {
+                let flight = quote.cheapest.as_ref()?;
+                let date = parse_date(&quote.leg.date).ok()?;
+                flight.times_between(&quote.leg.from_airport, &quote.leg.to_airport, date)
+            })
+            .collect()
+    }
+
+    /// One line per leg with its departure and arrival in each of `zones`
+    pub fn render_in(&self, zones: &[Tz]) -> String {
+        self.legs.iter()
+            .zip(self.leg_times())
+            .enumerate()
+            .map(|(i, (quote, times))| match times {
+                Some(times) => format!("{}. {} {}", i + 1, quote.leg.date, times.summary_in(zones)),
+                None => format!("{}. {} {} → {}: times unavailable", i + 1, quote.leg.date, quote.leg.from_airport, quote.leg.to_airport),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::itinerary::{Itinerary, ItineraryLeg, LegQuote};
+
+    fn flight(departure: &str, arrival: &str, duration: &str, layovers: Option<(&str, &str)>) -> Flight {
+        let flight = Flight::test("United", 300).schedule(departure, arrival, duration).route("SFO", "JFK");
+        match layovers {
+            Some((airport, length)) => flight.layovers(&[airport], &format!("Layover (1 of 1) is a {} layover at {}.", length, airport)),
+            None => flight,
+        }
+    }
+
+    fn date(text: &str) -> NaiveDate {
+        parse_date(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse_timezone() {
+        assert_eq!(parse_timezone("Europe/London").unwrap(), chrono_tz::Europe::London);
+        assert_eq!(parse_timezone("pt").unwrap(), chrono_tz::America::Los_Angeles);
+        assert_eq!(parse_timezone("ET").unwrap(), chrono_tz::America::New_York);
+        assert_eq!(parse_timezone("UTC").unwrap(), chrono_tz::UTC);
+        assert_eq!(parse_timezone("nrt").unwrap(), chrono_tz::Asia::Tokyo);
+        assert!(parse_timezone("Mars/Olympus").is_err());
+        assert_eq!(parse_timezones("PT, ET,").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_flight_times_across_zones() {
+        let times = flight("6:00 AM on Fri, Aug 15", "2:30 PM on Fri, Aug 15", "5 hr 30 min", None)
+            .times(date("2025-08-15"))
+            .unwrap();
+        // 8.5 hours on the clocks, but only 5.5 in the air
+        assert_eq!(times.elapsed_minutes(), 330);
+        assert_eq!(times.departure.format("%H:%M %Z").to_string(), "06:00 PDT");
+        assert_eq!(times.arrival_in(chrono_tz::America::Los_Angeles).format("%H:%M").to_string(), "11:30");
+        assert_eq!(times.local_summary(), "SFO 06:00 PDT → JFK 14:30 EDT (5h 30m)");
+
+        let zones = parse_timezones("PT,ET,Europe/London").unwrap();
+        assert_eq!(
+            times.summary_in(&zones),
+            "SFO → JFK: departs 06:00 PDT / 09:00 EDT / 14:00 BST, arrives 11:30 PDT / 14:30 EDT / 19:30 BST (5h 30m)"
+        );
+    }
+
+    #[test]
+    fn test_overnight_arrival_marker() {
+        let times = flight("10:00 PM on Fri, Aug 15", "6:30 AM on Sat, Aug 16", "5 hr 30 min", None)
+            .times(date("2025-08-15"))
+            .unwrap();
+        assert_eq!(times.local_summary(), "SFO 22:00 PDT → JFK 06:30 EDT+1 (5h 30m)");
+        assert_eq!(format_in_zones(&times.arrival, &[chrono_tz::America::Los_Angeles, chrono_tz::America::New_York]), "03:30 PDT / 06:30 EDT");
+    }
+
+    #[test]
+    fn test_arrival_day_without_marker() {
+        // 10:00 AEST is 17:00 PDT the day before, so 06:00 in LA is the same date
+        let mut sydney = flight("10:00 AM", "6:00 AM", "13 hr", None);
+        sydney.origin_airport = Some("SYD".to_string());
+        sydney.destination_airport = Some("LAX".to_string());
+        let times = sydney.times(date("2025-06-02")).unwrap();
+        assert_eq!(times.elapsed_minutes(), 13 * 60);
+        assert_eq!(times.local_summary(), "SYD 10:00 AEST → LAX 06:00 PDT (13h 00m)");
+        assert_eq!(sydney.arrival_datetime(date("2025-06-02")), date("2025-06-02").and_hms_opt(6, 0, 0));
+
+        // Westbound across the date line lands two calendar days later
+        let mut honolulu = flight("11:30 PM", "5:30 AM", "10 hr", None);
+        honolulu.origin_airport = Some("HNL".to_string());
+        honolulu.destination_airport = Some("SYD".to_string());
+        assert_eq!(honolulu.arrival_datetime(date("2025-06-02")), date("2025-06-04").and_hms_opt(5, 30, 0));
+    }
+
+    #[test]
+    fn test_layover_estimates() {
+        let times = flight("6:00 AM on Fri, Aug 15", "5:30 PM on Fri, Aug 15", "8 hr 30 min", Some(("DEN", "1 hr")))
+            .times(date("2025-08-15"))
+            .unwrap();
+        let layover = &times.layovers[0];
+        assert_eq!(layover.airport, "DEN");
+        assert_eq!((layover.departure - layover.arrival).num_minutes(), 60);
+        assert_eq!(layover.arrival.timezone(), chrono_tz::America::Denver);
+        assert!(layover.arrival > times.departure && layover.departure < times.arrival);
+
+        // Layovers longer than the trip cannot be placed
+        let odd = flight("6:00 AM on Fri, Aug 15", "5:30 PM on Fri, Aug 15", "8 hr 30 min", Some(("DEN", "12 hr")));
+        assert!(odd.times(date("2025-08-15")).unwrap().layovers.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_airport_has_no_times() {
+        let mut unknown = flight("6:00 AM", "2:30 PM", "5 hr 30 min", None);
+        unknown.origin_airport = Some("ZZZ".to_string());
+        assert!(unknown.times(date("2025-08-15")).is_none());
+        unknown.origin_airport = None;
+        assert!(unknown.times(date("2025-08-15")).is_none());
+    }
+
+    #[test]
+    fn test_render_itinerary_in_zones() {
+        let priced = PricedItinerary {
+            itinerary: Itinerary::open_jaw("SFO", "JFK", "BOS", "SFO", "2025-08-15", "2025-08-20").unwrap(),
+            multi_city: None,
+            multi_city_error: None,
+            legs: vec![
+                LegQuote {
+                    leg: ItineraryLeg::new("SFO", "JFK", "2025-08-15"),
+                    cheapest: Some(flight("6:00 AM on Fri, Aug 15", "2:30 PM on Fri, Aug 15", "5 hr 30 min", None)),
+                    error: None,
+                },
+                LegQuote { leg: ItineraryLeg::new("BOS", "SFO", "2025-08-20"), cheapest: None, error: Some("failed".to_string()) },
+            ],
+        };
+        let rendered = priced.render_in(&parse_timezones("PT,ET").unwrap());
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "1. 2025-08-15 SFO → JFK: departs 06:00 PDT / 09:00 EDT, arrives 11:30 PDT / 14:30 EDT (5h 30m)");
+        assert_eq!(lines[1], "2. 2025-08-20 BOS → SFO: times unavailable");
+    }
+}
@@ -3,9 +3,14 @@
 //! This module provides functionality to query Wikidata's SPARQL endpoint
 //! to resolve city names to their Freebase IDs for use with Google Flights API.
 
+#[cfg(feature = "city-search")]
+use crate::city_store::{CityStore, StoredCity};
+use crate::names::{city_key, normalize_query};
+use crate::sparql::{is_language_tag, is_wikidata_id, validate_city_name, SparqlQuery, Term};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::OnceLock;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Wikidata-specific error types
@@ -25,6 +30,24 @@ pub enum WikidataError {
     
     #[error("SPARQL query failed: {0}")]
     SparqlError(String),
+    
+    #[error("Wikidata endpoint returned HTTP {status}: {body}")]
+    HttpStatus { status: u16, body: String },
+    
+    #[error("Wikidata rate limit exceeded{}", .retry_after.map(|d| format!(", retry after {}s", d.as_secs())).unwrap_or_default())]
+    RateLimited { retry_after: Option<Duration> },
+    
+    #[error("Invalid Wikidata client configuration: {0}")]
+    InvalidConfig(String),
+    
+    #[error("City store error: {0}")]
+    StoreError(String),
+    
+    #[error("Invalid city name: {0}")]
+    InvalidCityName(String),
+    
+    #[error("City '{name}' is ambiguous: {}", describe_candidates(.candidates))]
+    AmbiguousCity { name: String, candidates: Vec<CityInfo> },
 }
 
 /// City information from Wikidata
@@ -37,6 +60,273 @@ pub struct CityInfo {
     pub wikidata_id: String,
     pub population: Option<i64>,
     pub coordinates: Option<(f64, f64)>, // (latitude, longitude)
+    /// Other labels the city was matched by ("München", "Bombay")
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// ISO 3166-2 codes of the subdivisions the city is in ("US-ME")
+    #[serde(default)]
+    pub regions: Vec<String>,
+}
+
+impl CityInfo {
+    /// Short human-readable description, e.g. "Springfield, US-MO (pop. 169176, Q28515)"
+    pub fn describe(&self) -> String {
+        let mut text = self.name.clone();
+        if let Some(region) = self.regions.first() {
+            text.push_str(&format!(", {}", region));
+        } else if let Some(country) = self.country_code.as_ref().or(self.country.as_ref()) {
+            text.push_str(&format!(", {}", country));
+        }
+        let mut details = Vec::new();
+        if let Some(population) = self.population {
+            details.push(format!("pop. {}", population));
+        }
+        if !self.wikidata_id.is_empty() {
+            details.push(self.wikidata_id.clone());
+        }
+        if !details.is_empty() {
+            text.push_str(&format!(" ({})", details.join(", ")));
+        }
+        text
+    }
+}
+
+/// One line listing ambiguous candidates, for error messages
+pub(crate) fn describe_candidates(candidates: &[CityInfo]) -> String {
+    candidates.iter().map(CityInfo::describe).collect::<Vec<_>>().join("; ")
+}
+
+/// How to pick one of the ambiguous candidates: a country code when they are in
+/// different countries, a state or province code when they are in different
+/// subdivisions, otherwise a Wikidata ID or coordinates
+pub fn ambiguity_hint(name: &str, candidates: &[CityInfo]) -> String {
+    let mut countries: Vec<&str> = candidates.iter().filter_map(|c| c.country_code.as_deref()).collect();
+    countries.dedup();
+    if countries.len() > 1 {
+        return format!("add a country code (e.g. '{}, {}')", name, countries[0]);
+    }
+    let mut regions: Vec<&str> = candidates.iter()
+        .filter_map(|c| c.regions.first())
+        .filter_map(|code| code.split_once('-').map(|(_, region)| region))
+        .collect();
+    regions.dedup();
+    if regions.len() > 1 {
+        return format!("add a state or province code (e.g. '{}, {}')", name, regions[0]);
+    }
+    let mut examples = Vec::new();
+    if let Some(first) = candidates.first() {
+        if !first.wikidata_id.is_empty() {
+            examples.push(format!("'{}, {}'", name, first.wikidata_id));
+        }
+        if let Some((lat, lon)) = first.coordinates {
+            examples.push(format!("'{}, {:.2}, {:.2}'", name, lat, lon));
+        }
+    }
+    if examples.is_empty() {
+        format!("add a Wikidata ID or coordinates (e.g. '{}, Q28515')", name)
+    } else {
+        format!("add a Wikidata ID or coordinates (e.g. {})", examples.join(" or "))
+    }
+}
+
+/// Extra information used to pick between cities with the same name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CityHint {
+    /// ISO 3166-1 alpha-2 country code the city should be in
+    pub country_code: Option<String>,
+    /// Coordinates (latitude, longitude) the city should be near
+    pub near: Option<(f64, f64)>,
+    /// Wikidata item the city must be ("Q28515")
+    pub wikidata_id: Option<String>,
+    /// Subdivision code the city should be in, the part of its ISO 3166-2 code
+    /// after the country ("ME" for US-ME). Set together with `country_code` when
+    /// a suffix could be either ("Portland, ME"); the city then needs to match one.
+    pub region: Option<String>,
+}
+
+impl CityHint {
+    pub fn country(country_code: &str) -> Self {
+        Self { country_code: Some(country_code.trim().to_uppercase()), ..Self::default() }
+    }
+    
+    pub fn near(latitude: f64, longitude: f64) -> Self {
+        Self { near: Some((latitude, longitude)), ..Self::default() }
+    }
+    
+    pub fn region(region: &str) -> Self {
+        Self { region: Some(region.trim().to_uppercase()), ..Self::default() }
+    }
+    
+    /// Hint for a short code that may be a country or a subdivision: "US" is a
+    /// country, "QC" a province, "ME" either Montenegro or Maine. "UK" means GB.
+    pub fn place(code: &str) -> Self {
+        let code = code.trim().to_uppercase();
+        let code = if code == "UK" { "GB".to_string() } else { code };
+        let country = ISO_COUNTRY_CODES.contains(&code.as_str());
+        let region = !country || SUBDIVISION_CODES.contains(&code.as_str());
+        Self {
+            country_code: country.then(|| code.clone()),
+            region: region.then_some(code),
+            ..Self::default()
+        }
+    }
+    
+    pub fn wikidata(wikidata_id: &str) -> Self {
+        Self { wikidata_id: Some(wikidata_id.trim().to_uppercase()), ..Self::default() }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.country_code.is_none() && self.near.is_none() && self.wikidata_id.is_none() && self.region.is_none()
+    }
+    
+    /// Whether a place in `country_code` fits the hint; a subdivision hint
+    /// ("Portland, ME") cannot be checked against a country alone, so it allows any
+    pub fn allows_country(&self, country_code: &str) -> bool {
+        self.region.is_some() || self.country_code.as_ref().is_none_or(|code| code.eq_ignore_ascii_case(country_code))
+    }
+    
+    /// Whether `city` is in the hinted country or subdivision; `None` without such
+    /// a hint or when the city has no country or region details to check
+    fn matches_place(&self, city: &CityInfo) -> Option<bool> {
+        if self.country_code.is_none() && self.region.is_none() {
+            return None;
+        }
+        if city.country_code.is_none() && city.regions.is_empty() {
+            return None;
+        }
+        let in_country = matches!((&self.country_code, &city.country_code), (Some(wanted), Some(actual)) if wanted.eq_ignore_ascii_case(actual));
+        let in_region = self.region.as_ref().is_some_and(|wanted| {
+            city.regions.iter().any(|code| code.split_once('-').is_some_and(|(_, region)| region.eq_ignore_ascii_case(wanted)))
+        });
+        Some(in_country || in_region)
+    }
+    
+    /// Split a trailing hint off a city query: a country or subdivision code
+    /// ("Portland, US", "Portland, ME", "Perth, WA"; see [`CityHint::place`]), a
+    /// Wikidata ID ("Springfield, Q28515") or coordinates ("Springfield, 39.80, -89.65")
+    pub fn parse(query: &str) -> (String, CityHint) {
+        if let Some((rest, lon)) = query.rsplit_once(',') {
+            if let Some((name, lat)) = rest.rsplit_once(',') {
+                if let (Ok(lat), Ok(lon)) = (lat.trim().parse::<f64>(), lon.trim().parse::<f64>()) {
+                    if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) && !name.trim().is_empty() {
+                        return (name.trim().to_string(), CityHint::near(lat, lon));
+                    }
+                }
+            }
+        }
+        if let Some((name, code)) = query.rsplit_once(',') {
+            let code = code.trim();
+            if !name.trim().is_empty() {
+                if code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic())
+                    || SUBDIVISION_CODES.contains(&code.to_uppercase().as_str())
+                {
+                    return (name.trim().to_string(), CityHint::place(code));
+                }
+                if is_wikidata_id(&code.to_uppercase()) {
+                    return (name.trim().to_string(), CityHint::wikidata(code));
+                }
+            }
+        }
+        (query.trim().to_string(), CityHint::default())
+    }
+}
+
+/// ISO 3166-1 alpha-2 country codes
+const ISO_COUNTRY_CODES: &[&str] = &[
+    "AD", "AE", "AF", "AG", "AI", "AL", "AM", "AO", "AQ", "AR", "AS", "AT", "AU", "AW", "AX", "AZ",
+    "BA", "BB", "BD", "BE", "BF", "BG", "BH", "BI", "BJ", "BL", "BM", "BN", "BO", "BQ", "BR", "BS",
+    "BT", "BV", "BW", "BY", "BZ", "CA", "CC", "CD", "CF", "CG", "CH", "CI", "CK", "CL", "CM", "CN",
+    "CO", "CR", "CU", "CV", "CW", "CX", "CY", "CZ", "DE", "DJ", "DK", "DM", "DO", "DZ", "EC", "EE",
+    "EG", "EH", "ER", "ES", "ET", "FI", "FJ", "FK", "FM", "FO", "FR", "GA", "GB", "GD", "GE", "GF",
+    "GG", "GH", "GI", "GL", "GM", "GN", "GP", "GQ", "GR", "GS", "GT", "GU", "GW", "GY", "HK", "HM",
+    "HN", "HR", "HT", "HU", "ID", "IE", "IL", "IM", "IN", "IO", "IQ", "IR", "IS", "IT", "JE", "JM",
+    "JO", "JP", "KE", "KG", "KH", "KI", "KM", "KN", "KP", "KR", "KW", "KY", "KZ", "LA", "LB", "LC",
+    "LI", "LK", "LR", "LS", "LT", "LU", "LV", "LY", "MA", "MC", "MD", "ME", "MF", "MG", "MH", "MK",
+    "ML", "MM", "MN", "MO", "MP", "MQ", "MR", "MS", "MT", "MU", "MV", "MW", "MX", "MY", "MZ", "NA",
+    "NC", "NE", "NF", "NG", "NI", "NL", "NO", "NP", "NR", "NU", "NZ", "OM", "PA", "PE", "PF", "PG",
+    "PH", "PK", "PL", "PM", "PN", "PR", "PS", "PT", "PW", "PY", "QA", "RE", "RO", "RS", "RU", "RW",
+    "SA", "SB", "SC", "SD", "SE", "SG", "SH", "SI", "SJ", "SK", "SL", "SM", "SN", "SO", "SR", "SS",
+    "ST", "SV", "SX", "SY", "SZ", "TC", "TD", "TF", "TG", "TH", "TJ", "TK", "TL", "TM", "TN", "TO",
+    "TR", "TT", "TV", "TW", "TZ", "UA", "UG", "UM", "US", "UY", "UZ", "VA", "VC", "VE", "VG", "VI",
+    "VN", "VU", "WF", "WS", "YE", "YT", "ZA", "ZM", "ZW",
+];
+
+/// Subdivision codes commonly written after a city name: US states, Canadian
+/// provinces and territories, and Australian states
+const SUBDIVISION_CODES: &[&str] = &[
+    "AL", "AK", "AZ", "AR", "CA", "CO", "CT", "DE", "DC", "FL", "GA", "HI", "ID", "IL", "IN", "IA",
+    "KS", "KY", "LA", "ME", "MD", "MA", "MI", "MN", "MS", "MO", "MT", "NE", "NV", "NH", "NJ", "NM",
+    "NY", "NC", "ND", "OH", "OK", "OR", "PA", "RI", "SC", "SD", "TN", "TX", "UT", "VT", "VA", "WA",
+    "WV", "WI", "WY",
+    "AB", "BC", "MB", "NB", "NL", "NS", "NT", "NU", "ON", "PE", "QC", "SK", "YT",
+    "NSW", "VIC", "QLD", "SA", "TAS", "ACT",
+];
+
+/// Score margin below which two same-named cities are considered ambiguous
+/// (roughly a 4x population difference)
+const AMBIGUITY_MARGIN: f64 = 6.0;
+
+/// Maximum number of cities fetched when searching for candidates
+const CITY_CANDIDATE_LIMIT: usize = 20;
+
+/// Maximum number of city names per batched search query
+pub const NAME_BATCH_SIZE: usize = 40;
+
+/// Maximum number of Wikidata items per details query
+pub const DETAILS_BATCH_SIZE: usize = 50;
+
+/// Maximum number of candidates reported in an ambiguity error
+const MAX_AMBIGUOUS_CANDIDATES: usize = 5;
+
+/// Languages whose labels and aliases are matched by default
+pub const DEFAULT_LABEL_LANGUAGES: &[&str] = &[
+    "en", "de", "fr", "es", "it", "pt", "nl", "pl", "ru", "uk", "tr", "ar", "hi", "ja", "zh", "ko",
+];
+
+/// Rank cities matching `query`: exact name matches first, then by hint and population
+pub fn rank_cities(query: &str, cities: Vec<CityInfo>, hint: &CityHint) -> Vec<CityInfo> {
+    score_cities(query, cities, hint).into_iter().map(|(city, _)| city).collect()
+}
+
+/// Cities with their scores, best first
+fn score_cities(query: &str, cities: Vec<CityInfo>, hint: &CityHint) -> Vec<(CityInfo, f64)> {
+    let mut scored: Vec<(CityInfo, f64)> = cities.into_iter()
+        .map(|city| {
+            let score = score_city(query, &city, hint);
+            (city, score)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored
+}
+
+/// Whether `query` is the city's name or one of its aliases, ignoring case and accents
+fn is_exact_name(query: &str, city: &CityInfo) -> bool {
+    let query = city_key(query);
+    std::iter::once(&city.name).chain(&city.aliases).any(|name| city_key(name) == query)
+}
+
+fn score_city(query: &str, city: &CityInfo, hint: &CityHint) -> f64 {
+    let query_key = city_key(query);
+    let mut score = if is_exact_name(query, city) {
+        100.0
+    } else if city_key(&city.name).starts_with(&query_key) {
+        20.0
+    } else {
+        10.0
+    };
+    
+    if let Some(matches) = hint.matches_place(city) {
+        score += if matches { 50.0 } else { -50.0 };
+    }
+    if let (Some((lat, lon)), Some((city_lat, city_lon))) = (hint.near, city.coordinates) {
+        let distance = crate::airports::haversine_km(lat, lon, city_lat, city_lon);
+        score += 50.0 * (1.0 - distance / 500.0).max(0.0);
+    }
+    if let Some(population) = city.population.filter(|p| *p > 0) {
+        score += 10.0 * (population as f64).log10();
+    }
+    score
 }
 
 /// SPARQL query results structure
@@ -50,7 +340,7 @@ struct SparqlResults {
     bindings: Vec<HashMap<String, SparqlValue>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 struct SparqlValue {
     value: String,
 }
@@ -72,20 +362,259 @@ fn get_city_cache() -> &'static HashMap<String, String> {
     CITY_CACHE.get_or_init(load_city_cache)
 }
 
+/// The compiled-in city name → Freebase ID cache
+pub fn bundled_city_cache() -> &'static HashMap<String, String> {
+    get_city_cache()
+}
+
+/// Global alias table - other names for cities in the city cache
+static CITY_ALIASES: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// The compiled-in alias → city cache name table ("Bombay" → "Mumbai", "München" → "Munich")
+pub fn bundled_city_aliases() -> &'static HashMap<String, String> {
+    CITY_ALIASES.get_or_init(|| {
+        serde_json::from_str(include_str!("city_aliases.json")).unwrap_or_else(|e| {
+            eprintln!("Warning: Failed to load city aliases: {}. Using no aliases.", e);
+            HashMap::new()
+        })
+    })
+}
+
+/// City cache keyed by [`city_key`] of every cached name and alias
+static CITY_INDEX: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+fn get_city_index() -> &'static HashMap<String, String> {
+    CITY_INDEX.get_or_init(|| {
+        let cache = get_city_cache();
+        let mut index: HashMap<String, String> = cache.iter()
+            .map(|(name, freebase_id)| (city_key(name), freebase_id.clone()))
+            .collect();
+        // Cached names win over aliases that fold to the same key
+        for (alias, target) in bundled_city_aliases() {
+            if let Some(freebase_id) = cache.get(target) {
+                index.entry(city_key(alias)).or_insert_with(|| freebase_id.clone());
+            }
+        }
+        index.remove("");
+        index
+    })
+}
+
+/// Public Wikidata SPARQL endpoint
+pub const DEFAULT_WIKIDATA_ENDPOINT: &str = "https://query.wikidata.org/sparql";
+
+/// Environment variable overriding the default endpoint (e.g. a local QLever or Blazegraph mirror)
+pub const WIKIDATA_ENDPOINT_ENV: &str = "RUST_FLIGHTS_WIKIDATA_ENDPOINT";
+
+/// User agent sent to Wikidata, as required by the Wikimedia user-agent policy
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    "rust-flights/",
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("CARGO_PKG_REPOSITORY"),
+    ")"
+);
+
+/// Default time limit for a whole SPARQL request; Wikidata stops queries after 60 seconds
+pub const DEFAULT_WIKIDATA_TIMEOUT: Duration = Duration::from_secs(65);
+
+/// Default time limit for connecting to the endpoint
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest part of an error response body kept in [`WikidataError::HttpStatus`]
+const MAX_ERROR_BODY_CHARS: usize = 300;
+
+/// Wait before retrying a rate-limited batch when Wikidata sends no `Retry-After`
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+/// Longest `Retry-After` honoured before retrying a rate-limited batch
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
 /// Wikidata SPARQL client
 pub struct WikidataClient {
     client: reqwest::Client,
+    endpoint: reqwest::Url,
+    /// Label languages matched when searching
+    languages: Vec<String>,
+    /// Persistent cache of cities resolved from Wikidata
+    #[cfg(feature = "city-search")]
+    store: Option<CityStore>,
 }
 
-impl WikidataClient {
-    /// Create a new Wikidata client
-    pub fn new() -> Result<Self, WikidataError> {
-        let client = reqwest::Client::new();
+/// Which persistent store a built client uses
+#[cfg(feature = "city-search")]
+enum StoreChoice {
+    Shared,
+    Custom(CityStore),
+    Disabled,
+}
+
+/// Builder for a [`WikidataClient`] with a custom endpoint, user agent or timeouts
+///
+/// ```rust,no_run
+/// use rust_flights::WikidataClient;
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), rust_flights::WikidataError> {
+/// let client = WikidataClient::builder()
+///     .endpoint("http://localhost:7001/sparql")
+///     .user_agent("acme-travel/2.1 (travel-ops@acme.example)")
+///     .timeout(Duration::from_secs(20))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct WikidataClientBuilder {
+    endpoint: String,
+    user_agent: String,
+    timeout: Duration,
+    connect_timeout: Duration,
+    languages: Vec<String>,
+    #[cfg(feature = "city-search")]
+    store: StoreChoice,
+}
+
+impl WikidataClientBuilder {
+    fn new() -> Self {
+        Self {
+            endpoint: std::env::var(WIKIDATA_ENDPOINT_ENV).unwrap_or_else(|_| DEFAULT_WIKIDATA_ENDPOINT.to_string()),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            timeout: DEFAULT_WIKIDATA_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            languages: DEFAULT_LABEL_LANGUAGES.iter().map(|lang| lang.to_string()).collect(),
+            #[cfg(feature = "city-search")]
+            store: StoreChoice::Shared,
+        }
+    }
+    
+    /// SPARQL endpoint URL (default [`DEFAULT_WIKIDATA_ENDPOINT`], or `$RUST_FLIGHTS_WIKIDATA_ENDPOINT`)
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.trim().to_string();
+        self
+    }
+    
+    /// User agent identifying your application; Wikimedia asks for contact details
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = user_agent.to_string();
+        self
+    }
+    
+    /// Time limit for a whole request, including reading the response
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    
+    /// Time limit for connecting to the endpoint
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+    
+    /// Label languages matched when searching, see [`WikidataClient::with_languages`]
+    pub fn languages(mut self, languages: &[&str]) -> Self {
+        self.languages = valid_languages(languages);
+        self
+    }
+    
+    /// Use `store` instead of the shared persistent city store
+    #[cfg(feature = "city-search")]
+    pub fn store(mut self, store: CityStore) -> Self {
+        self.store = StoreChoice::Custom(store);
+        self
+    }
+    
+    /// Don't persist resolved cities (a no-op without the `city-search` feature)
+    #[allow(unused_mut)]
+    pub fn without_store(mut self) -> Self {
+        #[cfg(feature = "city-search")]
+        {
+            self.store = StoreChoice::Disabled;
+        }
+        self
+    }
+    
+    /// Build the client; fails on an invalid endpoint URL or user agent
+    pub fn build(self) -> Result<WikidataClient, WikidataError> {
+        let endpoint = reqwest::Url::parse(&self.endpoint)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .ok_or_else(|| WikidataError::InvalidConfig(format!("Invalid SPARQL endpoint: {:?}", self.endpoint)))?;
+        let user_agent = reqwest::header::HeaderValue::from_str(&self.user_agent)
+            .map_err(|_| WikidataError::InvalidConfig(format!("Invalid user agent: {:?}", self.user_agent)))?;
+        
+        let client = reqwest::Client::builder()
+            .user_agent(user_agent)
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout)
+            .build()?;
         
-        Ok(Self {
+        Ok(WikidataClient {
             client,
+            endpoint,
+            languages: self.languages,
+            #[cfg(feature = "city-search")]
+            store: match self.store {
+                StoreChoice::Shared => CityStore::shared(),
+                StoreChoice::Custom(store) => Some(store),
+                StoreChoice::Disabled => None,
+            },
         })
     }
+}
+
+impl WikidataClient {
+    /// Create a new Wikidata client with the default settings
+    /// (see [`Self::builder`] to change the endpoint, user agent or timeouts)
+    ///
+    /// With the `city-search` feature, the client uses the shared persistent
+    /// [`CityStore`] when it can be opened.
+    pub fn new() -> Result<Self, WikidataError> {
+        Self::builder().build()
+    }
+    
+    /// Configure a client
+    pub fn builder() -> WikidataClientBuilder {
+        WikidataClientBuilder::new()
+    }
+    
+    /// The SPARQL endpoint queries are sent to
+    pub fn endpoint(&self) -> &str {
+        self.endpoint.as_str()
+    }
+    
+    /// Match city labels and aliases in these languages (Wikidata language codes
+    /// such as "de" or "zh-hans") instead of [`DEFAULT_LABEL_LANGUAGES`].
+    /// Invalid codes are skipped with a warning.
+    pub fn with_languages(mut self, languages: &[&str]) -> Self {
+        self.languages = valid_languages(languages);
+        self
+    }
+    
+    /// Label languages matched when searching
+    pub fn languages(&self) -> &[String] {
+        &self.languages
+    }
+    
+    /// Use `store` as the persistent city cache
+    #[cfg(feature = "city-search")]
+    pub fn with_store(mut self, store: CityStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+    
+    /// Don't persist resolved cities
+    #[cfg(feature = "city-search")]
+    pub fn without_store(mut self) -> Self {
+        self.store = None;
+        self
+    }
+    
+    /// The persistent city cache, if any
+    #[cfg(feature = "city-search")]
+    pub fn store(&self) -> Option<&CityStore> {
+        self.store.as_ref()
+    }
     
     /// Populate the cache by fetching Freebase IDs for a list of cities
     /// This is a utility function for building/updating the cache
@@ -94,26 +623,25 @@ impl WikidataClient {
         let mut successful = 0;
         let mut failed = 0;
         
-        println!("Populating cache for {} cities...", cities.len());
+        println!("Populating cache for {} cities in batches of {}...", cities.len(), NAME_BATCH_SIZE);
+        let batch = self.resolve_cities(&cities).await;
         
-        for (i, city) in cities.iter().enumerate() {
-            print!("Processing {}/{}: {} ... ", i + 1, cities.len(), city);
-            std::io::Write::flush(&mut std::io::stdout()).unwrap();
-            
-            match self.get_freebase_id_from_wikidata(city).await {
-                Ok(freebase_id) => {
+        // Results arrive together once every batch has resolved
+        for city in &cities {
+            match batch.resolved.get(*city).and_then(|info| info.freebase_id.as_ref()) {
+                Some(freebase_id) => {
                     cache.insert(city.to_string(), freebase_id.clone());
-                    println!("✅ {}", freebase_id);
+                    println!("✅ {}: {}", city, freebase_id);
                     successful += 1;
                 }
-                Err(e) => {
-                    println!("❌ {}", e);
+                None => {
+                    match batch.failed.iter().find(|(name, _)| name == city) {
+                        Some((_, e)) => println!("❌ {}: {}", city, e),
+                        None => println!("❌ {}: not resolved", city),
+                    }
                     failed += 1;
                 }
             }
-            
-            // Add a small delay to be respectful to Wikidata servers
-            tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
         }
         
         println!("\nCache population complete:");
@@ -124,6 +652,185 @@ impl WikidataClient {
         Ok(cache)
     }
     
+    /// Resolve many city names with batched SPARQL queries.
+    ///
+    /// Names are matched exactly in batches of [`NAME_BATCH_SIZE`]; names without an
+    /// exact match (e.g. different capitalisation) are retried one at a time with the
+    /// regular search. Failures are reported per name, and a failed batch only fails
+    /// the names in it. A rate-limited batch is retried once after `Retry-After`;
+    /// if Wikidata still refuses, every remaining name fails with
+    /// [`WikidataError::RateLimited`] without further queries. Country hints
+    /// ("Portland, US") are honoured.
+    pub async fn resolve_cities(&self, names: &[&str]) -> CityBatch {
+        let mut batch = CityBatch::default();
+        let mut requested: Vec<(String, String, CityHint)> = Vec::new();
+        for name in names {
+            if requested.iter().any(|(original, _, _)| original == name)
+                || batch.failed.iter().any(|(original, _)| original == name)
+            {
+                continue;
+            }
+            let (base, hint) = CityHint::parse(name);
+            match validate_city_name(&base) {
+                Ok(_) => requested.push((name.to_string(), normalize_query(&base), hint)),
+                Err(e) => batch.failed.push((name.to_string(), e)),
+            }
+        }
+        
+        let mut bases: Vec<&str> = Vec::new();
+        for (_, base, _) in &requested {
+            if !bases.contains(&base.as_str()) {
+                bases.push(base);
+            }
+        }
+        
+        let mut misses = Vec::new();
+        // Set once Wikidata keeps rate limiting us; the remaining names fail without querying
+        let mut rate_limited: Option<Option<Duration>> = None;
+        for (i, chunk) in bases.chunks(NAME_BATCH_SIZE).enumerate() {
+            if i > 0 {
+                // Be respectful to Wikidata servers
+                tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+            }
+            let in_chunk = requested.iter().filter(|(_, base, _)| chunk.contains(&base.as_str()));
+            
+            if let Some(limit) = &rate_limited {
+                for (original, _, _) in in_chunk {
+                    batch.failed.push((original.clone(), WikidataError::RateLimited { retry_after: *limit }));
+                }
+                continue;
+            }
+            
+            let query = self.build_batch_search_query(chunk);
+            let mut rows_by_name: HashMap<String, Vec<HashMap<String, SparqlValue>>> = HashMap::new();
+            let result = match self.execute_sparql_query(&query).await {
+                Err(WikidataError::RateLimited { retry_after }) => {
+                    let wait = retry_after.unwrap_or(DEFAULT_RETRY_AFTER).min(MAX_RETRY_AFTER);
+                    eprintln!("⚠️  Warning: Wikidata rate limit hit, retrying in {}s", wait.as_secs());
+                    tokio::time::sleep(wait).await;
+                    self.execute_sparql_query(&query).await
+                }
+                result => result,
+            };
+            match result {
+                Ok(response) => {
+                    for binding in response.results.bindings {
+                        if let Some(name) = binding.get("matched").map(|v| v.value.clone()) {
+                            rows_by_name.entry(name).or_default().push(binding);
+                        }
+                    }
+                }
+                Err(WikidataError::RateLimited { retry_after }) => {
+                    eprintln!("⚠️  Warning: Wikidata is still rate limiting, skipping the remaining cities");
+                    rate_limited = Some(retry_after);
+                    for (original, _, _) in in_chunk {
+                        batch.failed.push((original.clone(), WikidataError::RateLimited { retry_after }));
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("⚠️  Warning: Batch city query failed: {}", e);
+                    for (original, _, _) in in_chunk {
+                        batch.failed.push((original.clone(), WikidataError::SparqlError(format!("Batch query failed: {}", e))));
+                    }
+                    continue;
+                }
+            }
+            
+            for (original, base, hint) in in_chunk {
+                match rows_by_name.get(base) {
+                    Some(rows) => {
+                        let cities = self.parse_city_bindings(rows.clone());
+                        match pick_city(base, cities, hint) {
+                            Ok(city) => {
+                                batch.resolved.insert(original.clone(), city);
+                            }
+                            Err(e) => batch.failed.push((original.clone(), e)),
+                        }
+                    }
+                    None => misses.push((original.clone(), base.clone(), hint.clone())),
+                }
+            }
+        }
+        
+        for (original, base, hint) in misses {
+            if let Some(limit) = &rate_limited {
+                batch.failed.push((original, WikidataError::RateLimited { retry_after: *limit }));
+                continue;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+            match self.resolve_city(&base, &hint).await {
+                Ok(city) => {
+                    batch.resolved.insert(original, city);
+                }
+                Err(WikidataError::RateLimited { retry_after }) => {
+                    rate_limited = Some(retry_after);
+                    batch.failed.push((original, WikidataError::RateLimited { retry_after }));
+                }
+                Err(e) => batch.failed.push((original, e)),
+            }
+        }
+        
+        batch
+    }
+    
+    /// Freebase IDs for many cities: cached names are answered locally and the rest
+    /// are resolved with [`Self::resolve_cities`] (and persisted with `city-search`)
+    pub async fn get_freebase_ids(&self, city_names: &[&str]) -> HashMap<String, Result<String, WikidataError>> {
+        let mut results = HashMap::new();
+        let mut stale = HashMap::new();
+        let mut pending = Vec::new();
+        
+        for &city_name in city_names {
+            if results.contains_key(city_name) || pending.contains(&city_name) {
+                continue;
+            }
+            let (name, hint) = CityHint::parse(city_name);
+            if hint.is_empty() {
+                if let Some(freebase_id) = self.get_from_cache(&name) {
+                    results.insert(city_name.to_string(), Ok(freebase_id));
+                    continue;
+                }
+                match self.get_from_store(&name) {
+                    Some((freebase_id, false)) => {
+                        results.insert(city_name.to_string(), Ok(freebase_id));
+                        continue;
+                    }
+                    Some((freebase_id, true)) => {
+                        stale.insert(city_name.to_string(), freebase_id);
+                    }
+                    None => {}
+                }
+            }
+            pending.push(city_name);
+        }
+        
+        if pending.is_empty() {
+            return results;
+        }
+        eprintln!("Cache miss for {} cities, querying Wikidata...", pending.len());
+        
+        let batch = self.resolve_cities(&pending).await;
+        for (city_name, city) in batch.resolved {
+            let (name, hint) = CityHint::parse(&city_name);
+            if hint.is_empty() {
+                self.save_to_store(&name, &city);
+            }
+            results.insert(city_name, Ok(city.freebase_id.unwrap_or_default()));
+        }
+        for (city_name, e) in batch.failed {
+            let result = match stale.remove(&city_name) {
+                Some(freebase_id) => {
+                    eprintln!("⚠️  Warning: Refreshing '{}' failed ({}), using stored entry", city_name, e);
+                    Ok(freebase_id)
+                }
+                None => Err(e),
+            };
+            results.insert(city_name, result);
+        }
+        results
+    }
+    
     /// Get cache statistics
     pub fn get_cache_stats(&self) -> (usize, Vec<String>) {
         let cache = get_city_cache();
@@ -136,48 +843,131 @@ impl WikidataClient {
         self.get_from_cache(city_name).is_some()
     }
     
-    /// Get Freebase ID directly from Wikidata (bypassing cache)
-    async fn get_freebase_id_from_wikidata(&self, city_name: &str) -> Result<String, WikidataError> {
-        // Execute the optimized search directly
-        let sparql_query = self.build_city_search_query(city_name, 5);
+    /// Cities whose name contains `city_name`, best match first
+    pub async fn find_cities(&self, city_name: &str, hint: &CityHint) -> Result<Vec<CityInfo>, WikidataError> {
+        let city_name = normalize_query(validate_city_name(city_name)?);
+        let sparql_query = self.build_city_search_query(&city_name, CITY_CANDIDATE_LIMIT);
         let response = self.execute_sparql_query(&sparql_query).await?;
         let cities = self.parse_multiple_cities_response(response)?;
-        
-        // First try to find an exact match
-        if let Some(city) = cities.iter().find(|c| c.name.to_lowercase() == city_name.to_lowercase()) {
-            if let Some(ref freebase_id) = city.freebase_id {
-                return Ok(freebase_id.clone());
+        Ok(rank_cities(&city_name, cities, hint))
+    }
+    
+    /// Resolve a city name to the single best Wikidata match with a Freebase ID.
+    ///
+    /// Returns [`WikidataError::AmbiguousCity`] when several cities share the name
+    /// and neither the hint nor population clearly separates them.
+    pub async fn resolve_city(&self, city_name: &str, hint: &CityHint) -> Result<CityInfo, WikidataError> {
+        let city_name = normalize_query(validate_city_name(city_name)?);
+        let sparql_query = self.build_city_search_query(&city_name, CITY_CANDIDATE_LIMIT);
+        let response = self.execute_sparql_query(&sparql_query).await?;
+        let cities = self.parse_multiple_cities_response(response)?;
+        if cities.is_empty() {
+            return Err(WikidataError::CityNotFound(city_name));
+        }
+        pick_city(&city_name, cities, hint)
+    }
+    
+    /// Resolve a city and fetch its country, population and coordinates, so
+    /// applications can show users which city was matched.
+    ///
+    /// Accepts the same "Portland, US" country hint as [`Self::get_freebase_id_only`].
+    pub async fn get_city_info(&self, city_name: &str) -> Result<CityInfo, WikidataError> {
+        let (name, hint) = CityHint::parse(city_name);
+        let mut city = [self.resolve_city(&name, &hint).await?];
+        self.enrich_cities(&mut city).await?;
+        let [city] = city;
+        Ok(city)
+    }
+    
+    /// Full details for Wikidata items ("Q90"), fetched in batches of
+    /// [`DETAILS_BATCH_SIZE`] with one SPARQL query per batch
+    pub async fn get_city_details(&self, wikidata_ids: &[&str]) -> Result<Vec<CityInfo>, WikidataError> {
+        let mut cities = Vec::new();
+        for (i, batch) in wikidata_ids.chunks(DETAILS_BATCH_SIZE).enumerate() {
+            if i > 0 {
+                // Be respectful to Wikidata servers
+                tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
             }
+            let query = self.build_city_details_query(batch)?;
+            let response = self.execute_sparql_query(&query).await?;
+            cities.extend(self.parse_multiple_cities_response(response)?);
         }
-        
-        // If no exact match with Freebase ID, take the first city with a Freebase ID
-        if let Some(city) = cities.iter().find(|c| c.freebase_id.is_some()) {
-            return Ok(city.freebase_id.as_ref().unwrap().clone());
+        Ok(cities)
+    }
+    
+    /// Labels and aliases (`skos:altLabel`) of Wikidata items in the client's
+    /// languages, keyed by item ID; useful for building alias tables
+    pub async fn get_city_labels(&self, wikidata_ids: &[&str]) -> Result<HashMap<String, Vec<String>>, WikidataError> {
+        let mut labels: HashMap<String, Vec<String>> = HashMap::new();
+        for (i, batch) in wikidata_ids.chunks(DETAILS_BATCH_SIZE).enumerate() {
+            if i > 0 {
+                // Be respectful to Wikidata servers
+                tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+            }
+            let query = self.build_city_labels_query(batch)?;
+            let response = self.execute_sparql_query(&query).await?;
+            for binding in response.results.bindings {
+                let (Some(city), Some(label)) = (binding.get("city"), binding.get("label")) else {
+                    continue;
+                };
+                let id = city.value.split('/').next_back().unwrap_or("").to_string();
+                let names = labels.entry(id).or_default();
+                if !names.contains(&label.value) {
+                    names.push(label.value.clone());
+                }
+            }
+        }
+        Ok(labels)
+    }
+    
+    /// Fill in missing country, population and coordinates for cities from a search
+    pub async fn enrich_cities(&self, cities: &mut [CityInfo]) -> Result<(), WikidataError> {
+        let ids: Vec<&str> = cities.iter()
+            .map(|c| c.wikidata_id.as_str())
+            .filter(|id| is_wikidata_id(id))
+            .collect();
+        if ids.is_empty() {
+            return Ok(());
         }
         
-        Err(WikidataError::NoFreebaseId(city_name.to_string()))
+        let details = self.get_city_details(&ids).await?;
+        for city in cities.iter_mut() {
+            if let Some(found) = details.iter().find(|d| d.wikidata_id == city.wikidata_id) {
+                merge_city_details(city, found);
+            }
+        }
+        Ok(())
     }
     
 
     
     /// Execute SPARQL query against Wikidata endpoint
+    ///
+    /// HTTP 429 becomes [`WikidataError::RateLimited`] (with the `Retry-After`
+    /// delay when given) and other non-2xx responses [`WikidataError::HttpStatus`].
     async fn execute_sparql_query(&self, query: &str) -> Result<SparqlResponse, WikidataError> {
-        let url = "https://query.wikidata.org/sparql";
-        
         let response = self
             .client
-            .get(url)
+            .get(self.endpoint.clone())
             .query(&[("query", query)])
             .header("Accept", "application/sparql-results+json")
-            .header("User-Agent", "rust-flights/0.1.0 (https://github.com/example/rust-flights)")
             .send()
             .await?;
         
-        if !response.status().is_success() {
-            return Err(WikidataError::SparqlError(format!(
-                "SPARQL query failed with status: {}",
-                response.status()
-            )));
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response.headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after);
+            return Err(WikidataError::RateLimited { retry_after });
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(WikidataError::HttpStatus {
+                status: status.as_u16(),
+                body: body.trim().chars().take(MAX_ERROR_BODY_CHARS).collect(),
+            });
         }
         
         let sparql_response: SparqlResponse = response.json().await?;
@@ -189,140 +979,399 @@ impl WikidataClient {
 
     
     /// Get only the Freebase ID for a city (cached + fallback to Wikidata)
+    ///
+    /// Lookup order: the compiled-in cache, the persistent store (`city-search`
+    /// feature), then Wikidata. Cities resolved from Wikidata are written to the
+    /// store; a stale stored entry is used if the refresh fails.
+    ///
+    /// A trailing country code picks between same-named cities: "Portland, US".
     pub async fn get_freebase_id_only(&self, city_name: &str) -> Result<String, WikidataError> {
+        let (name, hint) = CityHint::parse(city_name);
+        self.get_freebase_id_with_hint(&name, &hint).await
+    }
+    
+    /// Get the Freebase ID for a city, using `hint` to choose between same-named cities.
+    ///
+    /// Hinted lookups always go to Wikidata, since the caches only hold one city per name.
+    pub async fn get_freebase_id_with_hint(&self, city_name: &str, hint: &CityHint) -> Result<String, WikidataError> {
         // First check the cache
-        if let Some(freebase_id) = self.get_from_cache(city_name) {
+        let stored = if hint.is_empty() {
+            if let Some(freebase_id) = self.get_from_cache(city_name) {
+                return Ok(freebase_id);
+            }
+            self.get_from_store(city_name)
+        } else {
+            None
+        };
+        if let Some((freebase_id, false)) = stored {
             return Ok(freebase_id);
         }
         
         // If not in cache, fall back to Wikidata query
         eprintln!("Cache miss for '{}', querying Wikidata...", city_name);
         
-        // Execute the optimized search directly
-        let sparql_query = self.build_city_search_query(city_name, 5);
-        let response = self.execute_sparql_query(&sparql_query).await?;
-        let cities = self.parse_multiple_cities_response(response)?;
-        
-        // First try to find an exact match
-        if let Some(city) = cities.iter().find(|c| c.name.to_lowercase() == city_name.to_lowercase()) {
-            if let Some(ref freebase_id) = city.freebase_id {
-                return Ok(freebase_id.clone());
+        match self.resolve_city(city_name, hint).await {
+            Ok(city) => {
+                if hint.is_empty() {
+                    self.save_to_store(city_name, &city);
+                }
+                Ok(city.freebase_id.unwrap_or_default())
             }
+            Err(e) => match stored {
+                Some((freebase_id, _)) => {
+                    eprintln!("⚠️  Warning: Refreshing '{}' failed ({}), using stored entry", city_name, e);
+                    Ok(freebase_id)
+                }
+                None => Err(e),
+            },
         }
-        
-        // If no exact match with Freebase ID, take the first city with a Freebase ID
-        if let Some(city) = cities.iter().find(|c| c.freebase_id.is_some()) {
-            return Ok(city.freebase_id.as_ref().unwrap().clone());
-        }
-        
-        Err(WikidataError::NoFreebaseId(city_name.to_string()))
     }
     
-    /// Check the cache for a city's Freebase ID
-    fn get_from_cache(&self, city_name: &str) -> Option<String> {
-        let cache = get_city_cache();
-        
-        // Try exact match first
-        if let Some(freebase_id) = cache.get(city_name) {
-            return Some(freebase_id.clone());
-        }
-        
-        // Try case-insensitive match
-        let city_name_lower = city_name.to_lowercase();
-        for (cached_city, freebase_id) in cache.iter() {
-            if cached_city.to_lowercase() == city_name_lower {
-                return Some(freebase_id.clone());
-            }
-        }
-        
-        // Try partial matches for common city variations
-        for (cached_city, freebase_id) in cache.iter() {
-            if self.is_city_name_match(&city_name_lower, &cached_city.to_lowercase()) {
-                return Some(freebase_id.clone());
+    /// Freebase ID from the persistent store and whether the entry is stale
+    #[cfg(feature = "city-search")]
+    fn get_from_store(&self, city_name: &str) -> Option<(String, bool)> {
+        let store = self.store.as_ref()?;
+        match store.get_any(city_name) {
+            Ok(city) => city.map(|c| {
+                let stale = store.is_stale(&c);
+                (c.freebase_id, stale)
+            }),
+            Err(e) => {
+                eprintln!("⚠️  Warning: City store lookup for '{}' failed: {}", city_name, e);
+                None
             }
         }
-        
+    }
+    
+    #[cfg(not(feature = "city-search"))]
+    fn get_from_store(&self, _city_name: &str) -> Option<(String, bool)> {
         None
     }
     
-    /// Check if two city names are likely the same city (handles common variations)
-    fn is_city_name_match(&self, query: &str, cached: &str) -> bool {
-        // Handle common variations
-        let normalize = |name: &str| -> String {
-            name.replace("saint ", "st. ")
-                .replace("st ", "st. ")
-                .replace("mount ", "mt. ")
-                .replace("fort ", "ft. ")
-                .trim()
-                .to_string()
+    /// Persist a city resolved from Wikidata under the name it was looked up by
+    #[cfg(feature = "city-search")]
+    fn save_to_store(&self, city_name: &str, city: &CityInfo) {
+        let (Some(store), Some(stored)) = (&self.store, StoredCity::from_city_info(city_name, city)) else {
+            return;
         };
+        if let Err(e) = store.insert(&stored) {
+            eprintln!("⚠️  Warning: Saving '{}' to the city store failed: {}", city_name, e);
+        }
+    }
+    
+    #[cfg(not(feature = "city-search"))]
+    fn save_to_store(&self, _city_name: &str, _city: &CityInfo) {}
+    
+    /// Check the cache for a city's Freebase ID
+    fn get_from_cache(&self, city_name: &str) -> Option<String> {
+        cached_freebase_id(city_name)
+    }
+    
+    /// `name` tagged with each of the client's languages
+    fn label_terms(&self, name: &str) -> Vec<Term> {
+        self.languages.iter()
+            .filter_map(|lang| Term::tagged(name, lang).ok())
+            .collect()
+    }
+    
+    /// Build SPARQL query matching many city names exactly (labels or aliases in
+    /// any of the client's languages) at once
+    fn build_batch_search_query(&self, names: &[&str]) -> String {
+        SparqlQuery::select(&["matched", "city", "cityLabel", "freebaseId", "population", "coord", "countryCode", "regionCode"])
+            .distinct()
+            .values("matched", names.iter().flat_map(|name| self.label_terms(name)))
+            .pattern("?city rdfs:label|skos:altLabel ?matched .")
+            .pattern("?city wdt:P31/wdt:P279* wd:Q515 .  # instance of city or subclass")
+            .pattern(r#"OPTIONAL { ?city rdfs:label ?cityLabel . FILTER(lang(?cityLabel) = "en") }"#)
+            .pattern("OPTIONAL { ?city wdt:P646 ?freebaseId . }")
+            .pattern("OPTIONAL { ?city wdt:P1082 ?population . }")
+            .pattern("OPTIONAL { ?city wdt:P625 ?coord . }")
+            .pattern("OPTIONAL { ?city wdt:P17 ?country . ?country wdt:P297 ?countryCode . }")
+            // ISO 3166-2 codes of enclosing subdivisions, for "Portland, ME" hints
+            .pattern("OPTIONAL { ?city wdt:P131+ ?region . ?region wdt:P300 ?regionCode . }")
+            .build()
+    }
+    
+    /// Build SPARQL query fetching Freebase ID, country, population and coordinates
+    /// for a batch of Wikidata items
+    fn build_city_details_query(&self, wikidata_ids: &[&str]) -> Result<String, WikidataError> {
+        let items = wikidata_ids.iter()
+            .map(|id| Term::entity(id))
+            .collect::<Result<Vec<_>, _>>()?;
         
-        let normalized_query = normalize(query);
-        let normalized_cached = normalize(cached);
-        
-        // Check if one contains the other (for cases like "New York" vs "New York City")
-        normalized_query.contains(&normalized_cached) || normalized_cached.contains(&normalized_query)
+        Ok(SparqlQuery::select(&["city", "cityLabel", "freebaseId", "population", "coord", "countryCode", "countryLabel"])
+            .values("city", items)
+            .pattern(r#"OPTIONAL { ?city rdfs:label ?cityLabel . FILTER(lang(?cityLabel) = "en") }"#)
+            .pattern("OPTIONAL { ?city wdt:P646 ?freebaseId . }")
+            .pattern("OPTIONAL { ?city wdt:P1082 ?population . }")
+            .pattern("OPTIONAL { ?city wdt:P625 ?coord . }")
+            .pattern(r#"OPTIONAL {
+    ?city wdt:P17 ?country .
+    OPTIONAL { ?country wdt:P297 ?countryCode . }
+    OPTIONAL { ?country rdfs:label ?countryLabel . FILTER(lang(?countryLabel) = "en") }
+  }"#)
+            .build())
     }
     
-
+    /// Build SPARQL query fetching labels and aliases of Wikidata items in the client's languages
+    fn build_city_labels_query(&self, wikidata_ids: &[&str]) -> Result<String, WikidataError> {
+        let items = wikidata_ids.iter()
+            .map(|id| Term::entity(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(SparqlQuery::select(&["city", "label"])
+            .values("city", items)
+            .pattern("?city rdfs:label|skos:altLabel ?label .")
+            .filter_lang_in("label", &self.languages)
+            .build())
+    }
     
-    /// Build SPARQL query to search for cities matching a query (optimized for speed)
+    /// Build SPARQL query to search for cities matching a query (optimized for speed):
+    /// exact labels or aliases in the client's languages, or English labels containing it
     fn build_city_search_query(&self, query: &str, limit: usize) -> String {
-        format!(r#"
-PREFIX wd: <http://www.wikidata.org/entity/>
-PREFIX wdt: <http://www.wikidata.org/prop/direct/>
-PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
-
-SELECT DISTINCT ?city ?cityLabel ?freebaseId WHERE {{
-  # Find cities with names containing the query
-  ?city wdt:P31/wdt:P279* wd:Q515 .  # instance of city or subclass
-  ?city rdfs:label ?cityLabel .
-  FILTER(lang(?cityLabel) = "en")
-  FILTER(CONTAINS(LCASE(?cityLabel), LCASE("{}")))
-  
-  # Get Freebase ID if available
-  OPTIONAL {{ ?city wdt:P646 ?freebaseId . }}
-}}
-LIMIT {}
-"#, query, limit)
-    }
-    
-    /// Parse SPARQL response for multiple cities (simplified)
+        // Rows multiply per matched label and per optional value, so the limit
+        // is applied to distinct cities in a subquery before details join in
+        let candidates = SparqlQuery::select(&["city"])
+            .sample("matched", "label")
+            .union(
+                // Find cities called exactly this in any language ("München", "Bombay")
+                SparqlQuery::group()
+                    .values("label", self.label_terms(query))
+                    .pattern("?city rdfs:label|skos:altLabel ?label ."),
+                // Find cities with English names containing the query
+                SparqlQuery::group()
+                    .pattern("?city rdfs:label ?label .")
+                    .pattern(r#"FILTER(lang(?label) = "en")"#)
+                    .filter_contains("label", &Term::literal(query)),
+            )
+            .pattern("?city wdt:P31/wdt:P279* wd:Q515 .  # instance of city or subclass")
+            .group_by("city")
+            .limit(limit);
+        SparqlQuery::select(&["city", "cityLabel", "matched", "freebaseId", "population", "coord", "countryCode", "regionCode"])
+            .distinct()
+            .subquery(candidates)
+            .pattern(r#"OPTIONAL { ?city rdfs:label ?cityLabel . FILTER(lang(?cityLabel) = "en") }"#)
+            // Get Freebase ID if available
+            .pattern("OPTIONAL { ?city wdt:P646 ?freebaseId . }")
+            // Details used to rank same-named cities
+            .pattern("OPTIONAL { ?city wdt:P1082 ?population . }")
+            .pattern("OPTIONAL { ?city wdt:P625 ?coord . }")
+            .pattern("OPTIONAL { ?city wdt:P17 ?country . ?country wdt:P297 ?countryCode . }")
+            .pattern("OPTIONAL { ?city wdt:P131+ ?region . ?region wdt:P300 ?regionCode . }")
+            .build()
+    }
+    
+    /// Parse SPARQL response for multiple cities, merging rows for the same city
     fn parse_multiple_cities_response(&self, response: SparqlResponse) -> Result<Vec<CityInfo>, WikidataError> {
-        let mut cities = Vec::new();
+        Ok(self.parse_city_bindings(response.results.bindings))
+    }
+    
+    /// Turn SPARQL result rows into cities, one per Wikidata item
+    fn parse_city_bindings(&self, bindings: Vec<HashMap<String, SparqlValue>>) -> Vec<CityInfo> {
+        let mut cities: Vec<CityInfo> = Vec::new();
         
-        for binding in response.results.bindings {
+        for binding in bindings {
             // Extract Wikidata ID from the city URI
             let wikidata_id = binding
                 .get("city")
-                .and_then(|v| v.value.split('/').last())
+                .and_then(|v| v.value.split('/').next_back())
                 .unwrap_or("")
                 .to_string();
             
+            // The label or alias the query matched, when it differs from the English name
+            let matched = binding
+                .get("matched")
+                .map(|v| v.value.clone());
+            
             let name = binding
                 .get("cityLabel")
                 .map(|v| v.value.clone())
+                .or_else(|| matched.clone())
                 .unwrap_or_default();
             
+            let aliases = matched
+                .filter(|m| city_key(m) != city_key(&name))
+                .into_iter()
+                .collect();
+            
             let freebase_id = binding
                 .get("freebaseId")
                 .map(|v| v.value.clone());
             
-            cities.push(CityInfo {
+            let population = binding
+                .get("population")
+                .and_then(|v| v.value.parse::<f64>().ok())
+                .map(|p| p as i64);
+            
+            let coordinates = binding
+                .get("coord")
+                .and_then(|v| parse_wkt_point(&v.value));
+            
+            let country_code = binding
+                .get("countryCode")
+                .map(|v| v.value.clone());
+            
+            let regions = binding
+                .get("regionCode")
+                .map(|v| v.value.clone())
+                .into_iter()
+                .collect();
+            
+            // Only fetched by the details query
+            let country = binding
+                .get("countryLabel")
+                .map(|v| v.value.clone());
+            
+            let city = CityInfo {
                 name,
                 freebase_id,
-                country: None,          // Not fetched in optimized query
-                country_code: None,     // Not fetched in optimized query
+                country,
+                country_code,
                 wikidata_id,
-                population: None,       // Not fetched in optimized query
-                coordinates: None,      // Not fetched in optimized query
-            });
+                population,
+                coordinates,
+                aliases,
+                regions,
+            };
+            
+            // Cities with several populations, coordinates or countries come back
+            // as several rows; keep one entry with the largest population
+            match cities.iter_mut().find(|c| !city.wikidata_id.is_empty() && c.wikidata_id == city.wikidata_id) {
+                Some(existing) => merge_city_details(existing, &city),
+                None => cities.push(city),
+            }
         }
         
-        Ok(cities)
+        cities
+    }
+}
+
+/// Pick the best city with a Freebase ID, or report the ambiguity
+fn pick_city(city_name: &str, cities: Vec<CityInfo>, hint: &CityHint) -> Result<CityInfo, WikidataError> {
+    let mut ranked: Vec<(CityInfo, f64)> = score_cities(city_name, cities, hint)
+        .into_iter()
+        .filter(|(city, _)| city.freebase_id.is_some())
+        .filter(|(city, _)| hint.wikidata_id.as_ref().is_none_or(|id| city.wikidata_id.eq_ignore_ascii_case(id)))
+        .collect();
+    if ranked.is_empty() {
+        if let Some(wikidata_id) = &hint.wikidata_id {
+            return Err(WikidataError::CityNotFound(format!("{} ({})", city_name, wikidata_id)));
+        }
+        return Err(WikidataError::NoFreebaseId(city_name.to_string()));
+    }
+    let best_score = ranked[0].1;
+    
+    let close: Vec<CityInfo> = ranked.iter()
+        .filter(|(city, score)| is_exact_name(city_name, city) && best_score - score < AMBIGUITY_MARGIN)
+        .map(|(city, _)| city.clone())
+        .take(MAX_AMBIGUOUS_CANDIDATES)
+        .collect();
+    if close.len() > 1 {
+        return Err(WikidataError::AmbiguousCity { name: city_name.to_string(), candidates: close });
+    }
+    // A state or province no candidate could be checked against must not be
+    // settled by population alone
+    if hint.region.is_some() && !ranked.iter().any(|(city, _)| hint.matches_place(city) == Some(true)) {
+        let exact: Vec<CityInfo> = ranked.iter()
+            .filter(|(city, _)| is_exact_name(city_name, city))
+            .map(|(city, _)| city.clone())
+            .take(MAX_AMBIGUOUS_CANDIDATES)
+            .collect();
+        if exact.len() > 1 {
+            return Err(WikidataError::AmbiguousCity { name: city_name.to_string(), candidates: exact });
+        }
+    }
+    
+    Ok(ranked.swap_remove(0).0)
+}
+
+/// Look up a city in the compiled-in cache: exact, then by [`city_key`] across
+/// cached names and the alias table, so case, accents, punctuation, common
+/// variations and other-language names ("münchen", "Sao Paulo", "NYC") all hit
+pub(crate) fn cached_freebase_id(city_name: &str) -> Option<String> {
+    // Try exact match first
+    if let Some(freebase_id) = get_city_cache().get(city_name) {
+        return Some(freebase_id.clone());
+    }
+    
+    get_city_index().get(&city_key(city_name)).cloned()
+}
+
+/// Check if two city names are likely the same city (handles common variations)
+///
+/// Case, accents, punctuation and abbreviations are ignored, so "Saint Louis"
+/// matches "St. Louis"; "Jersey" does not match "Jersey City".
+pub(crate) fn is_city_name_match(query: &str, cached: &str) -> bool {
+    let query = city_key(query);
+    !query.is_empty() && query == city_key(cached)
+}
+
+/// Cities resolved by [`WikidataClient::resolve_cities`]
+#[derive(Debug, Default)]
+pub struct CityBatch {
+    /// Resolved cities keyed by the name they were requested as
+    pub resolved: HashMap<String, CityInfo>,
+    /// Names that could not be resolved, with the reason
+    pub failed: Vec<(String, WikidataError)>,
+}
+
+/// Fill fields missing from `city` with those from another row for the same item,
+/// keeping the largest population
+fn merge_city_details(city: &mut CityInfo, other: &CityInfo) {
+    if city.name.is_empty() {
+        city.name = other.name.clone();
+    }
+    city.freebase_id = city.freebase_id.take().or_else(|| other.freebase_id.clone());
+    city.country = city.country.take().or_else(|| other.country.clone());
+    city.country_code = city.country_code.take().or_else(|| other.country_code.clone());
+    city.population = city.population.max(other.population);
+    city.coordinates = city.coordinates.or(other.coordinates);
+    for alias in &other.aliases {
+        if !city.aliases.contains(alias) {
+            city.aliases.push(alias.clone());
+        }
+    }
+    for region in &other.regions {
+        if !city.regions.contains(region) {
+            city.regions.push(region.clone());
+        }
     }
 }
 
+/// Language codes that are valid Wikidata language tags, warning about the rest
+fn valid_languages(languages: &[&str]) -> Vec<String> {
+    languages.iter()
+        .filter(|lang| {
+            let valid = is_language_tag(lang);
+            if !valid {
+                eprintln!("⚠️  Warning: Ignoring invalid language code '{}'", lang);
+            }
+            valid
+        })
+        .map(|lang| lang.to_string())
+        .collect()
+}
+
+/// Parse a `Retry-After` header: delay in seconds or an HTTP date
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Parse a WKT point ("Point(lon lat)") into (latitude, longitude)
+fn parse_wkt_point(text: &str) -> Option<(f64, f64)> {
+    let inner = text.trim().strip_prefix("Point(")?.strip_suffix(')')?;
+    let mut parts = inner.split_whitespace();
+    let longitude = parts.next()?.parse().ok()?;
+    let latitude = parts.next()?.parse().ok()?;
+    Some((latitude, longitude))
+}
+
 impl Default for WikidataClient {
     fn default() -> Self {
         Self::new().expect("Failed to create WikidataClient")
@@ -335,7 +1384,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_cache_functionality() {
-        let client = WikidataClient::new().unwrap();
+        let client = WikidataClient::builder().without_store().build().unwrap();
         
         // Test cache statistics
         let (cache_size, cached_cities) = client.get_cache_stats();
@@ -347,7 +1396,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_london_freebase_id_cached() {
-        let client = WikidataClient::new().unwrap();
+        let client = WikidataClient::builder().without_store().build().unwrap();
         
         // Check if London is in cache
         let is_cached = client.is_city_cached("London");
@@ -374,7 +1423,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_new_york_freebase_id() {
-        let client = WikidataClient::new().unwrap();
+        let client = WikidataClient::builder().without_store().build().unwrap();
         let result = client.get_freebase_id_only("New York").await;
         
         match result {
@@ -393,7 +1442,7 @@ mod tests {
     
     #[tokio::test] 
     async fn test_paris_freebase_id() {
-        let client = WikidataClient::new().unwrap();
+        let client = WikidataClient::builder().without_store().build().unwrap();
         let result = client.get_freebase_id_only("Paris").await;
         
         match result {
@@ -412,7 +1461,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_tokyo_freebase_id() {
-        let client = WikidataClient::new().unwrap();
+        let client = WikidataClient::builder().without_store().build().unwrap();
         let result = client.get_freebase_id_only("Tokyo").await;
         
         match result {
@@ -431,7 +1480,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_sydney_freebase_id() {
-        let client = WikidataClient::new().unwrap();
+        let client = WikidataClient::builder().without_store().build().unwrap();
         let result = client.get_freebase_id_only("Sydney").await;
         
         match result {
@@ -450,7 +1499,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_city_not_found() {
-        let client = WikidataClient::new().unwrap();
+        let client = WikidataClient::builder().without_store().build().unwrap();
         let city_name = "NonexistentCityXYZ123";
         
         // Should not be in cache 
@@ -480,7 +1529,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_cache_hit_vs_miss() {
-        let client = WikidataClient::new().unwrap();
+        let client = WikidataClient::builder().without_store().build().unwrap();
         
         // Test a city that should be in cache
         let popular_city = "Tokyo";
@@ -497,5 +1546,456 @@ mod tests {
         assert!(is_popular_cached, "Popular city should be in cache");
     }
     
-
+    fn city(name: &str, country_code: &str, population: Option<i64>, coordinates: Option<(f64, f64)>) -> CityInfo {
+        CityInfo {
+            name: name.to_string(),
+            freebase_id: Some(format!("/m/{}", name.to_lowercase())),
+            country: None,
+            country_code: Some(country_code.to_string()),
+            wikidata_id: format!("Q{}-{}", name, country_code),
+            population,
+            coordinates,
+            aliases: Vec::new(),
+            regions: Vec::new(),
+        }
+    }
+    
+    #[test]
+    fn test_city_name_match_is_strict() {
+        assert!(is_city_name_match("saint louis", "st. louis"));
+        assert!(!is_city_name_match("jersey", "jersey city"));
+        assert!(is_city_name_match("Sao Paulo", "São Paulo"));
+        assert!(is_city_name_match("DÜSSELDORF", "Düsseldorf"));
+        assert!(!is_city_name_match("york", "new york"));
+        assert!(!is_city_name_match("portland", "portland maine"));
+    }
+    
+    #[test]
+    fn test_cache_lookup_folds_accents_and_aliases() {
+        let munich = cached_freebase_id("Munich").unwrap();
+        for name in ["München", "MUNCHEN", "muenchen", "Mu\u{0308}nchen"] {
+            assert_eq!(cached_freebase_id(name).as_ref(), Some(&munich), "{} missed", name);
+        }
+        assert_eq!(cached_freebase_id("Sao Paulo"), cached_freebase_id("São Paulo"));
+        assert_eq!(cached_freebase_id("NYC"), cached_freebase_id("New York"));
+        assert_eq!(cached_freebase_id("Bombay"), cached_freebase_id("Mumbai"));
+        assert_eq!(cached_freebase_id("東京"), cached_freebase_id("Tokyo"));
+        assert_eq!(cached_freebase_id("Москва"), cached_freebase_id("Moscow"));
+        assert_eq!(cached_freebase_id("York"), None);
+        // "City" variants come from the alias table, not from dropping the word
+        assert_eq!(cached_freebase_id("New York City"), cached_freebase_id("New York"));
+        assert_eq!(cached_freebase_id("City of St Louis"), cached_freebase_id("St. Louis"));
+        assert_eq!(cached_freebase_id("Oklahoma"), None);
+        assert_eq!(cached_freebase_id("Mexico"), None);
+    }
+    
+    #[test]
+    fn test_bundled_aliases_point_at_cached_cities() {
+        let cache = bundled_city_cache();
+        let aliases = bundled_city_aliases();
+        assert!(aliases.len() > 100);
+        for (alias, target) in aliases {
+            assert!(cache.contains_key(target), "alias {} points at unknown city {}", alias, target);
+        }
+    }
+    
+    #[test]
+    fn test_rank_by_alias() {
+        let mut munich = city("Munich", "DE", Some(1_500_000), None);
+        munich.aliases = vec!["München".to_string()];
+        let cities = vec![city("Münchenbernsdorf", "DE", Some(3_000), None), munich];
+        let picked = pick_city("Munchen", cities, &CityHint::default()).unwrap();
+        assert_eq!(picked.name, "Munich");
+    }
+    
+    #[test]
+    fn test_city_hint_parse() {
+        assert_eq!(CityHint::parse("Portland, us"), ("Portland".to_string(), CityHint::country("US")));
+        assert_eq!(CityHint::parse(" Paris "), ("Paris".to_string(), CityHint::default()));
+        assert_eq!(CityHint::parse("Washington, D.C."), ("Washington, D.C.".to_string(), CityHint::default()));
+        assert_eq!(CityHint::parse("Springfield, q28515"), ("Springfield".to_string(), CityHint::wikidata("Q28515")));
+        assert_eq!(CityHint::parse("Springfield, 39.8, -89.65"), ("Springfield".to_string(), CityHint::near(39.8, -89.65)));
+        assert_eq!(CityHint::parse("Springfield, 39.8,-189"), ("Springfield, 39.8,-189".to_string(), CityHint::default()));
+        assert_eq!(CityHint::parse("Quebec, QC"), ("Quebec".to_string(), CityHint::region("QC")));
+        assert_eq!(CityHint::parse("Paris, FR"), ("Paris".to_string(), CityHint::country("FR")));
+        assert_eq!(CityHint::parse("London, UK"), ("London".to_string(), CityHint::country("GB")));
+        assert_eq!(CityHint::parse("Perth, WA").1, CityHint::region("WA"));
+        assert_eq!(CityHint::parse("Sydney, NSW").1, CityHint::region("NSW"));
+        // Montenegro or Maine
+        let portland = CityHint::parse("Portland, ME").1;
+        assert_eq!(portland.country_code.as_deref(), Some("ME"));
+        assert_eq!(portland.region.as_deref(), Some("ME"));
+        assert!(portland.allows_country("US") && portland.allows_country("ME"));
+        assert!(!CityHint::parse("Paris, FR").1.allows_country("US"));
+    }
+    
+    #[test]
+    fn test_pick_city_by_region() {
+        let mut oregon = city("Portland", "US", Some(650_000), Some((45.52, -122.68)));
+        oregon.regions = vec!["US-OR".to_string()];
+        let mut maine = city("Portland", "US", Some(68_000), Some((43.66, -70.26)));
+        maine.regions = vec!["US-ME".to_string()];
+        let portlands = vec![oregon.clone(), maine.clone()];
+        
+        let picked = pick_city("Portland", portlands.clone(), &CityHint::parse("Portland, ME").1).unwrap();
+        assert_eq!(picked.regions, vec!["US-ME".to_string()]);
+        let picked = pick_city("Portland", portlands.clone(), &CityHint::parse("Portland, OR").1).unwrap();
+        assert_eq!(picked.regions, vec!["US-OR".to_string()]);
+        assert_eq!(
+            ambiguity_hint("Portland", &portlands),
+            "add a state or province code (e.g. 'Portland, OR')"
+        );
+        
+        // Without subdivision details the larger city must not win silently
+        let bare = vec![
+            CityInfo { regions: Vec::new(), ..oregon },
+            CityInfo { regions: Vec::new(), ..maine },
+        ];
+        assert!(matches!(
+            pick_city("Portland", bare.clone(), &CityHint::region("ME")),
+            Err(WikidataError::AmbiguousCity { .. })
+        ));
+        assert!(pick_city("Portland", bare, &CityHint::country("US")).is_ok());
+    }
+    
+    #[test]
+    fn test_rank_and_pick_city() {
+        let paris = || vec![
+            city("Paris Hills", "US", Some(900), None),
+            city("Paris", "US", Some(25_000), Some((33.66, -95.55))),
+            city("Paris", "FR", Some(2_100_000), Some((48.86, 2.35))),
+        ];
+        let ranked = rank_cities("paris", paris(), &CityHint::default());
+        assert_eq!(ranked[0].country_code.as_deref(), Some("FR"));
+        assert_eq!(ranked[2].name, "Paris Hills");
+        
+        assert_eq!(pick_city("Paris", paris(), &CityHint::default()).unwrap().country_code.as_deref(), Some("FR"));
+        assert_eq!(pick_city("Paris", paris(), &CityHint::country("US")).unwrap().country_code.as_deref(), Some("US"));
+        assert_eq!(pick_city("Paris", paris(), &CityHint::near(33.0, -95.0)).unwrap().country_code.as_deref(), Some("US"));
+    }
+    
+    #[test]
+    fn test_ambiguous_city() {
+        let springfields = vec![
+            city("Springfield", "US", Some(114_000), Some((39.8, -89.65))),
+            city("Springfield", "US", Some(169_000), Some((37.2, -93.29))),
+        ];
+        match pick_city("Springfield", springfields.clone(), &CityHint::default()) {
+            Err(WikidataError::AmbiguousCity { name, candidates }) => {
+                assert_eq!(name, "Springfield");
+                assert_eq!(candidates.len(), 2);
+                assert_eq!(candidates[0].population, Some(169_000));
+            }
+            other => panic!("Expected an ambiguity, got {:?}", other),
+        }
+        
+        // Same country, so the hint points at Wikidata IDs and coordinates
+        let hint = ambiguity_hint("Springfield", &springfields);
+        assert!(!hint.contains("country code"), "{}", hint);
+        assert!(hint.contains("'Springfield, 39.80, -89.65'"), "{}", hint);
+        let paris = vec![city("Paris", "FR", None, None), city("Paris", "US", None, None)];
+        assert_eq!(ambiguity_hint("Paris", &paris), "add a country code (e.g. 'Paris, FR')");
+        
+        // Coordinates settle it
+        let picked = pick_city("Springfield", springfields.clone(), &CityHint::near(39.78, -89.6)).unwrap();
+        assert_eq!(picked.population, Some(114_000));
+        
+        // So does a Wikidata ID
+        let mut missouri = springfields[1].clone();
+        missouri.wikidata_id = "Q135615".to_string();
+        let mut springfields = springfields;
+        springfields[1] = missouri;
+        let picked = pick_city("Springfield", springfields.clone(), &CityHint::wikidata("Q135615")).unwrap();
+        assert_eq!(picked.population, Some(169_000));
+        assert!(matches!(
+            pick_city("Springfield", springfields, &CityHint::wikidata("Q1")),
+            Err(WikidataError::CityNotFound(_))
+        ));
+    }
+    
+    #[test]
+    fn test_city_details_query() {
+        let client = WikidataClient::builder().without_store().build().unwrap();
+        let query = client.build_city_details_query(&["Q90", "Q60"]).unwrap();
+        assert!(query.contains("VALUES ?city { wd:Q90 wd:Q60 }"));
+        assert!(query.contains("wdt:P297") && query.contains("wdt:P1082") && query.contains("wdt:P625"));
+        assert!(client.build_city_details_query(&["Q90", "Q1 } ?x ?y"]).is_err());
+    }
+    
+    #[test]
+    fn test_parse_city_details_response() {
+        let client = WikidataClient::builder().without_store().build().unwrap();
+        let response: SparqlResponse = serde_json::from_value(serde_json::json!({
+            "results": { "bindings": [
+                {
+                    "city": { "value": "http://www.wikidata.org/entity/Q90" },
+                    "cityLabel": { "value": "Paris" },
+                    "freebaseId": { "value": "/m/05qtj" },
+                    "population": { "value": "2087577" },
+                    "coord": { "value": "Point(2.351388888 48.856944444)" },
+                    "countryCode": { "value": "FR" },
+                    "countryLabel": { "value": "France" }
+                },
+                {
+                    "city": { "value": "http://www.wikidata.org/entity/Q90" },
+                    "cityLabel": { "value": "Paris" },
+                    "population": { "value": "2145906" }
+                }
+            ] }
+        })).unwrap();
+        
+        let cities = client.parse_multiple_cities_response(response).unwrap();
+        assert_eq!(cities.len(), 1);
+        let paris = &cities[0];
+        assert_eq!(paris.wikidata_id, "Q90");
+        assert_eq!(paris.country.as_deref(), Some("France"));
+        assert_eq!(paris.country_code.as_deref(), Some("FR"));
+        assert_eq!(paris.population, Some(2_145_906));
+        assert_eq!(paris.coordinates, Some((48.856944444, 2.351388888)));
+        assert_eq!(paris.describe(), "Paris, FR (pop. 2145906, Q90)");
+    }
+    
+    #[test]
+    fn test_batch_search_query() {
+        let client = WikidataClient::builder().without_store().build().unwrap();
+        let client = client.with_languages(&["en", "fr", "not a tag"]);
+        assert_eq!(client.languages(), ["en".to_string(), "fr".to_string()]);
+        let query = client.build_batch_search_query(&["Lyon", "Saint-Étienne"]);
+        assert!(query.contains(r#"VALUES ?matched { "Lyon"@en "Lyon"@fr "Saint-Étienne"@en "Saint-Étienne"@fr }"#));
+        assert!(query.contains("rdfs:label|skos:altLabel ?matched"));
+        
+        let hostile = client.build_batch_search_query(&[r#"Lyon" } ?x ?y {"#]);
+        assert!(hostile.contains(r#""Lyon\" } ?x ?y {"@en"#));
+    }
+    
+    #[test]
+    fn test_search_query_escapes_input() {
+        let client = WikidataClient::builder().without_store().build().unwrap();
+        let query = client.build_city_search_query(r#"Paris")) } ; DROP ALL #"#, 5);
+        assert!(query.contains(r#"LCASE("Paris\")) } ; DROP ALL #")"#));
+    }
+    
+    #[test]
+    fn test_search_query_limits_cities() {
+        let client = WikidataClient::builder().without_store().build().unwrap();
+        let query = client.build_city_search_query("Springfield", 5);
+        // The limit counts cities, not the label and detail rows they expand to
+        let inner = query.find("SELECT ?city (SAMPLE(?label) AS ?matched) WHERE").unwrap();
+        let limit = query.find("GROUP BY ?city\n  LIMIT 5\n").unwrap();
+        let details = query.find("wdt:P1082").unwrap();
+        assert!(inner < limit && limit < details, "{}", query);
+        assert_eq!(query.matches("LIMIT").count(), 1);
+        assert!(query.trim_end().ends_with('}'));
+    }
+    
+    #[test]
+    fn test_search_query_matches_other_languages() {
+        let client = WikidataClient::builder().without_store().build().unwrap().with_languages(&["en", "de"]);
+        let query = client.build_city_search_query("München", 5);
+        assert!(query.contains(r#"VALUES ?label { "München"@en "München"@de }"#));
+        assert!(query.contains("} UNION {"));
+        
+        let labels = client.build_city_labels_query(&["Q1726"]).unwrap();
+        assert!(labels.contains("skos:altLabel") && labels.contains(r#"IN ("en", "de")"#));
+    }
+    
+    #[test]
+    fn test_parse_matched_alias() {
+        let client = WikidataClient::builder().without_store().build().unwrap();
+        let response: SparqlResponse = serde_json::from_value(serde_json::json!({
+            "results": { "bindings": [
+                {
+                    "city": { "value": "http://www.wikidata.org/entity/Q1156" },
+                    "cityLabel": { "value": "Mumbai" },
+                    "matched": { "value": "Bombay" },
+                    "freebaseId": { "value": "/m/04vmp" }
+                },
+                {
+                    "city": { "value": "http://www.wikidata.org/entity/Q1156" },
+                    "cityLabel": { "value": "Mumbai" },
+                    "matched": { "value": "Mumbai" }
+                }
+            ] }
+        })).unwrap();
+        
+        let cities = client.parse_multiple_cities_response(response).unwrap();
+        assert_eq!(cities.len(), 1);
+        assert_eq!(cities[0].aliases, vec!["Bombay".to_string()]);
+        assert!(is_exact_name("bombay", &cities[0]));
+    }
+    
+    #[tokio::test]
+    async fn test_hostile_names_rejected_before_querying() {
+        let client = WikidataClient::builder().without_store().build().unwrap();
+        for name in [r#"Paris" } #"#, "London}}", "", &"x".repeat(500)] {
+            assert!(matches!(
+                client.resolve_city(name, &CityHint::default()).await,
+                Err(WikidataError::InvalidCityName(_))
+            ), "{:?} was not rejected", name);
+        }
+        
+        let batch = client.resolve_cities(&["{bad}"]).await;
+        assert!(batch.resolved.is_empty());
+        assert!(matches!(batch.failed.as_slice(), [(name, WikidataError::InvalidCityName(_))] if name == "{bad}"));
+    }
+    
+    /// Serve one canned HTTP response on a local port; returns the endpoint URL and
+    /// a handle yielding the request that was received
+    async fn stub_endpoint(response: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/sparql", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 16 * 1024];
+            let read = socket.read(&mut buffer).await.unwrap();
+            if !response.is_empty() {
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            } else {
+                // Never answer, to trigger the client's timeout
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+            String::from_utf8_lossy(&buffer[..read]).to_string()
+        });
+        (endpoint, handle)
+    }
+    
+    #[test]
+    fn test_builder_validates_configuration() {
+        let client = WikidataClient::builder().endpoint("http://localhost:7001/sparql").build().unwrap();
+        assert_eq!(client.endpoint(), "http://localhost:7001/sparql");
+        assert!(DEFAULT_USER_AGENT.starts_with("rust-flights/") && !DEFAULT_USER_AGENT.contains("example"));
+        
+        for endpoint in ["not a url", "ftp://mirror.example/sparql", ""] {
+            assert!(matches!(
+                WikidataClient::builder().endpoint(endpoint).build(),
+                Err(WikidataError::InvalidConfig(_))
+            ), "{:?} accepted", endpoint);
+        }
+        assert!(matches!(
+            WikidataClient::builder().user_agent("bad\nagent").build(),
+            Err(WikidataError::InvalidConfig(_))
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_custom_endpoint_and_user_agent() {
+        let (endpoint, request) = stub_endpoint(concat!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/sparql-results+json\r\nConnection: close\r\n\r\n",
+            r#"{"results": {"bindings": [{"city": {"value": "http://www.wikidata.org/entity/Q456"}, "cityLabel": {"value": "Lyon"}, "freebaseId": {"value": "/m/0dprg"}}]}}"#,
+        )).await;
+        let client = WikidataClient::builder()
+            .endpoint(&endpoint)
+            .user_agent("acme-travel/2.1 (ops@acme.example)")
+            .build()
+            .unwrap();
+        
+        let city = client.resolve_city("Lyon", &CityHint::default()).await.unwrap();
+        assert_eq!(city.freebase_id.as_deref(), Some("/m/0dprg"));
+        let request = request.await.unwrap();
+        assert!(request.starts_with("GET /sparql?query="));
+        assert!(request.to_lowercase().contains("user-agent: acme-travel/2.1 (ops@acme.example)"));
+    }
+    
+    #[tokio::test]
+    async fn test_rate_limit_and_http_errors() {
+        let (endpoint, _) = stub_endpoint(
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 30\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ).await;
+        let client = WikidataClient::builder().endpoint(&endpoint).build().unwrap();
+        match client.find_cities("Lyon", &CityHint::default()).await {
+            Err(WikidataError::RateLimited { retry_after }) => assert_eq!(retry_after, Some(Duration::from_secs(30))),
+            other => panic!("Expected a rate limit error, got {:?}", other),
+        }
+        
+        let (endpoint, _) = stub_endpoint(
+            "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 22\r\nConnection: close\r\n\r\nQuery timeout exceeded",
+        ).await;
+        let client = WikidataClient::builder().endpoint(&endpoint).build().unwrap();
+        match client.find_cities("Lyon", &CityHint::default()).await {
+            Err(WikidataError::HttpStatus { status, body }) => {
+                assert_eq!(status, 500);
+                assert_eq!(body, "Query timeout exceeded");
+            }
+            other => panic!("Expected an HTTP status error, got {:?}", other),
+        }
+    }
+    
+    /// Serve `responses` in order, one connection each; the handle yields the number served
+    async fn stub_responses(responses: Vec<&'static str>) -> (String, tokio::task::JoinHandle<usize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/sparql", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            for response in &responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0u8; 16 * 1024];
+                let _ = socket.read(&mut buffer).await.unwrap();
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+            responses.len()
+        });
+        (endpoint, handle)
+    }
+    
+    #[tokio::test]
+    async fn test_resolve_cities_rate_limited() {
+        const LIMITED: &str = "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        
+        // Retried once after Retry-After
+        let (endpoint, served) = stub_responses(vec![LIMITED, concat!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/sparql-results+json\r\nConnection: close\r\n\r\n",
+            r#"{"results": {"bindings": [{"city": {"value": "http://www.wikidata.org/entity/Q456"}, "cityLabel": {"value": "Lyon"}, "matched": {"value": "Lyon"}, "freebaseId": {"value": "/m/0dprg"}}]}}"#,
+        )]).await;
+        let client = WikidataClient::builder().endpoint(&endpoint).without_store().build().unwrap();
+        let batch = client.resolve_cities(&["Lyon"]).await;
+        assert_eq!(batch.resolved["Lyon"].freebase_id.as_deref(), Some("/m/0dprg"));
+        assert_eq!(served.await.unwrap(), 2);
+        
+        // Still limited after the retry: the later chunks fail without being queried
+        let names: Vec<String> = (0..NAME_BATCH_SIZE + 5).map(|i| format!("Town {}", i)).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let (endpoint, served) = stub_responses(vec![LIMITED, LIMITED]).await;
+        let client = WikidataClient::builder().endpoint(&endpoint).without_store().build().unwrap();
+        let batch = client.resolve_cities(&names).await;
+        assert!(batch.resolved.is_empty());
+        assert_eq!(batch.failed.len(), names.len());
+        for (name, e) in &batch.failed {
+            assert!(matches!(e, WikidataError::RateLimited { .. }), "{}: {:?}", name, e);
+        }
+        assert_eq!(served.await.unwrap(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_timeout() {
+        let (endpoint, _) = stub_endpoint("").await;
+        let client = WikidataClient::builder()
+            .endpoint(&endpoint)
+            .timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        match client.find_cities("Lyon", &CityHint::default()).await {
+            Err(WikidataError::HttpError(e)) => assert!(e.is_timeout()),
+            other => panic!("Expected a timeout, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        let later = (chrono::Utc::now() + chrono::Duration::seconds(90)).to_rfc2822();
+        assert!(parse_retry_after(&later).unwrap() > Duration::from_secs(80));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+    
+    #[test]
+    fn test_parse_wkt_point() {
+        assert_eq!(parse_wkt_point("Point(2.3514 48.8575)"), Some((48.8575, 2.3514)));
+        assert_eq!(parse_wkt_point("garbage"), None);
+    }
 } 
\ No newline at end of file
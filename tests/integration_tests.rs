@@ -8,7 +8,17 @@ use rust_flights::{
     FlightData, FlightSearchRequest, CityFlightData, CityFlightSearchRequest,
     Passengers, SeatClass, TripType, TimeWindow
 };
-use tokio;
+
+/// Point the shared city store at a per-process temp directory so city searches
+/// never touch the developer's `~/.cache/rust-flights/cities`. Must run before
+/// the first city lookup, which opens the store.
+fn isolate_city_store() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        let path = std::env::temp_dir().join(format!("rust-flights-test-cities-{}", std::process::id()));
+        std::env::set_var("RUST_FLIGHTS_CITY_STORE", path);
+    });
+}
 
 /// Helper function to create a basic search request
 fn create_basic_request(from: &str, to: &str, date: &str) -> FlightSearchRequest {
@@ -493,6 +503,7 @@ async fn test_protobuf_encoding_edge_cases() {
 
 /// Helper function to create a city-based request
 fn create_city_request(from_city: &str, to_city: &str, date: &str) -> CityFlightSearchRequest {
+    isolate_city_store();
     CityFlightSearchRequest {
         flights: vec![CityFlightData {
             date: date.to_string(),
@@ -517,6 +528,7 @@ fn create_city_request_with_time_windows(
     departure_time: Option<TimeWindow>,
     arrival_time: Option<TimeWindow>
 ) -> CityFlightSearchRequest {
+    isolate_city_store();
     CityFlightSearchRequest {
         flights: vec![CityFlightData {
             date: date.to_string(),
@@ -551,7 +563,7 @@ async fn test_city_based_search_london_to_paris() {
                 rust_flights::FlightError::ProtobufError(_) => {
                     panic!("Protobuf encoding failed: {}", e);
                 }
-                rust_flights::FlightError::CityNotFound(city) => {
+                rust_flights::FlightError::CityNotFound { name: city, .. } => {
                     println!("City not found (this may indicate Wikidata API issues): {}", city);
                 }
                 _ => {
@@ -580,7 +592,7 @@ async fn test_city_based_search_new_york_to_tokyo() {
                 rust_flights::FlightError::ProtobufError(_) => {
                     panic!("Protobuf encoding failed: {}", e);
                 }
-                rust_flights::FlightError::CityNotFound(city) => {
+                rust_flights::FlightError::CityNotFound { name: city, .. } => {
                     println!("City not found (this may indicate Wikidata API issues): {}", city);
                 }
                 _ => {
@@ -593,6 +605,7 @@ async fn test_city_based_search_new_york_to_tokyo() {
 
 #[tokio::test]
 async fn test_convenience_function_london_to_sydney() {
+    isolate_city_store();
     match search_flights_between_cities("London", "Sydney", "2025-08-20").await {
         Ok(result) => {
             println!("✅ Convenience function (London → Sydney) test passed");
@@ -607,7 +620,7 @@ async fn test_convenience_function_london_to_sydney() {
                 rust_flights::FlightError::ProtobufError(_) => {
                     panic!("Protobuf encoding failed: {}", e);
                 }
-                rust_flights::FlightError::CityNotFound(city) => {
+                rust_flights::FlightError::CityNotFound { name: city, .. } => {
                     println!("City not found (this may indicate Wikidata API issues): {}", city);
                 }
                 _ => {
@@ -645,7 +658,7 @@ async fn test_city_based_search_with_time_windows() {
                 rust_flights::FlightError::ProtobufError(_) => {
                     panic!("Protobuf encoding failed: {}", e);
                 }
-                rust_flights::FlightError::CityNotFound(city) => {
+                rust_flights::FlightError::CityNotFound { name: city, .. } => {
                     println!("City not found (this may indicate Wikidata API issues): {}", city);
                 }
                 _ => {
@@ -675,7 +688,7 @@ async fn test_city_based_business_class() {
                 rust_flights::FlightError::ProtobufError(_) => {
                     panic!("Protobuf encoding failed: {}", e);
                 }
-                rust_flights::FlightError::CityNotFound(city) => {
+                rust_flights::FlightError::CityNotFound { name: city, .. } => {
                     println!("City not found (this may indicate Wikidata API issues): {}", city);
                 }
                 _ => {
@@ -728,7 +741,7 @@ async fn test_city_based_round_trip() {
                 rust_flights::FlightError::ProtobufError(_) => {
                     panic!("Protobuf encoding failed: {}", e);
                 }
-                rust_flights::FlightError::CityNotFound(city) => {
+                rust_flights::FlightError::CityNotFound { name: city, .. } => {
                     println!("City not found (this may indicate Wikidata API issues): {}", city);
                 }
                 _ => {